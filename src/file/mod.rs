@@ -5,10 +5,12 @@ use std::{
     error::Error,
     fs::File,
     io::prelude::*,
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
 };
 use strum_macros::Display;
 
+use crate::setting::SOURCE_FOLDER;
+
 #[cfg(test)]
 mod tests;
 
@@ -16,6 +18,8 @@ mod tests;
 pub enum FileError {
     FileDoesNotExist,
     InaccessibleExtension,
+    /// the path would leave the folder it is searched in; e.g. through a `..` segment
+    InvalidPath,
 }
 
 impl Error for FileError {}
@@ -24,6 +28,7 @@ impl Error for FileError {}
 ///
 /// # Errors
 /// None is returned instead of a PathBuf if file does not exist or the file has unaccessible extensions. Unaccessible extensions are defined in settings.ron for each path.
+/// InvalidPath is returned if url has a `..` segment, a NUL byte or an absolute component; as it could name a file outside of the search folder.
 pub fn parse<F: Fn(&str) -> bool>(
     url: &str,
     search_folder: &str,
@@ -31,11 +36,15 @@ pub fn parse<F: Fn(&str) -> bool>(
 ) -> Result<PathBuf, FileError> {
     let url = url.trim_matches('\\').trim_matches('/');
 
+    if !is_contained(url) {
+        return Err(FileError::InvalidPath);
+    }
+
     let mut path_buffer = PathBuf::new();
 
-    path_buffer.push(r"source");
+    path_buffer.push(SOURCE_FOLDER);
 
-    path_buffer.push(&search_folder);
+    path_buffer.push(search_folder);
 
     match url.rfind('?') {
         Some(index) => path_buffer.push(url[0..index].trim_matches('\\')),
@@ -66,7 +75,16 @@ pub fn parse<F: Fn(&str) -> bool>(
         return Err(FileError::InaccessibleExtension);
     }
 
-    return Ok(path_buffer);
+    Ok(path_buffer)
+}
+
+/// is_contained checks that url, once pushed onto a folder, names a path inside of it
+fn is_contained(url: &str) -> bool {
+    !url.contains('\0')
+        && !url.split(['/', '\\']).any(|segment| segment == "..")
+        && Path::new(url)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
 }
 
 /// get_file_content_string returns string content of a file
@@ -83,8 +101,8 @@ pub fn get_file_content_string(file_path: &Path) -> Option<String> {
     let mut contents: String = String::new();
 
     match file.read_to_string(&mut contents) {
-        Err(_err) => return None,
-        Ok(_) => return Some(contents),
+        Err(_err) => None,
+        Ok(_) => Some(contents),
     }
 }
 
@@ -101,7 +119,7 @@ pub fn get_file_content_bytes(file_path: &Path) -> Option<Vec<u8>> {
     let mut contents: Vec<u8> = Vec::new();
 
     match file.read_to_end(&mut contents) {
-        Err(_err) => return None,
-        Ok(_) => return Some(contents),
+        Err(_err) => None,
+        Ok(_) => Some(contents),
     }
 }
@@ -1 +1,24 @@
+mod parse {
+    use super::super::{parse, FileError};
 
+    #[test]
+    fn traversal_is_rejected() {
+        let paths = [
+            "../../etc/passwd",
+            "/docs/../../etc/passwd",
+            "..\\..\\etc\\passwd",
+            "/index.html\0.png",
+            "//etc/../passwd",
+        ];
+
+        for path in paths {
+            assert_eq!(parse(path, "", |_| true), Err(FileError::InvalidPath), "{path:?}");
+        }
+    }
+
+    #[test]
+    fn dots_inside_a_segment_are_accepted() {
+        assert_eq!(parse("/..missing.html", "", |_| true), Err(FileError::FileDoesNotExist));
+        assert_eq!(parse("/./missing.html", "", |_| true), Err(FileError::FileDoesNotExist));
+    }
+}
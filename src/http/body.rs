@@ -1,5 +1,6 @@
 //! body module is responsible for how HTTP body are parsed and stored
 use super::request::parser_error::ParserError;
+use log::trace;
 use std::{
     fmt::{Debug, Display, Error, Formatter},
     str::FromStr,
@@ -36,9 +37,10 @@ impl ContentType {
         let str_vec: Vec<&str> = raw_str.split("/").collect();
 
         if str_vec.len() < 2 {
-            return Err(ParserError::InvalidMethod(Some(String::from(
-                "Start line must have more than 2 elements",
-            ))));
+            return Err(ParserError::InvalidContentType {
+                offset: 0,
+                reason: format!("{raw_str} isn't a type & subtype separated by /"),
+            });
         }
 
         let type_raw: &str = str_vec[0];
@@ -76,9 +78,10 @@ impl ContentType {
                 let content_type = ContentType::Video(content_type_value);
                 Ok(content_type)
             }
-            _ => Err(ParserError::InvalidMethod(Some(String::from(
-                "Invalid type",
-            )))),
+            _ => Err(ParserError::InvalidContentType {
+                offset: 0,
+                reason: format!("{type_raw} is not a valid type"),
+            }),
         }
     }
 }
@@ -98,7 +101,7 @@ impl Display for ContentType {
 
 impl Debug for ContentType {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-        f.debug_struct(&format!("ContentType: {}", self.to_string()))
+        f.debug_struct(&format!("ContentType: {}", self))
             .finish()
     }
 }
@@ -107,7 +110,7 @@ impl TryFrom<&str> for ContentType {
     type Error = String;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        println!("{}", value);
+        trace!("Content type of extension {value}");
         match value {
             "css" => Ok(Self::Text(Text::css)),
             "csv" => Ok(Self::Text(Text::csv)),
@@ -218,10 +221,10 @@ impl FromStr for Application {
             "zip" => Ok(Application::zip),
             "x-www-form-urlencoded" => Ok(Application::x_www_form_urlencoded),
             "woff" => Ok(Application::woff),
-            _ => Err(ParserError::InvalidMethod(Some(format!(
-                "{} is not a valid Application variant",
-                s
-            )))),
+            _ => Err(ParserError::InvalidContentType {
+                offset: 0,
+                reason: format!("{} is not a valid Application variant", s),
+            }),
         }
     }
 }
@@ -263,10 +266,10 @@ impl FromStr for Audio {
             "x-ms-wma" => Ok(Audio::x_ms_wma),
             "vnd.rn-realaudio" => Ok(Audio::vnd_rn_realaudio),
             "x-wav" => Ok(Audio::x_wav),
-            _ => Err(ParserError::InvalidMethod(Some(format!(
-                "{} is not a valid Audio variant",
-                s
-            )))),
+            _ => Err(ParserError::InvalidContentType {
+                offset: 0,
+                reason: format!("{} is not a valid Audio variant", s),
+            }),
         }
     }
 }
@@ -320,10 +323,10 @@ impl FromStr for Image {
             "x-icon" => Ok(Image::x_icon),
             "vnd.djvu" => Ok(Image::vnd_djvu),
             "svg+xml" => Ok(Image::svg_xml),
-            _ => Err(ParserError::InvalidMethod(Some(format!(
-                "{} is not a valid Image variant",
-                s
-            )))),
+            _ => Err(ParserError::InvalidContentType {
+                offset: 0,
+                reason: format!("{} is not a valid Image variant", s),
+            }),
         }
     }
 }
@@ -368,10 +371,10 @@ impl FromStr for Multipart {
             // "form-data" => Ok(Multipart::form_data {
             //     boundary: String::from(""),
             // }),
-            _ => Err(ParserError::InvalidMethod(Some(format!(
-                "{} is not a valid Multipart variant",
-                s
-            )))),
+            _ => Err(ParserError::InvalidContentType {
+                offset: 0,
+                reason: format!("{} is not a valid Multipart variant", s),
+            }),
         }
     }
 }
@@ -419,10 +422,10 @@ impl FromStr for Text {
             "javascript" => Ok(Text::javascript),
             "plain" => Ok(Text::plain),
             "xml" => Ok(Text::xml),
-            _ => Err(ParserError::InvalidMethod(Some(format!(
-                "{} is not a valid Text variant",
-                s
-            )))),
+            _ => Err(ParserError::InvalidContentType {
+                offset: 0,
+                reason: format!("{} is not a valid Text variant", s),
+            }),
         }
     }
 }
@@ -466,13 +469,13 @@ impl FromStr for Video {
 
         let value: &str = value_parameter_vec[0];
         match value {
-            "mpeg" => return Ok(Video::mpeg),
-            "mp4" => return Ok(Video::mp4),
-            "quicktime" => return Ok(Video::quicktime),
-            "x-ms-wmv" => return Ok(Video::x_ms_wmv),
-            "x-msvideo" => return Ok(Video::x_msvideo),
-            "x-flv" => return Ok(Video::x_flv),
-            "webm" => return Ok(Video::webm),
+            "mpeg" => Ok(Video::mpeg),
+            "mp4" => Ok(Video::mp4),
+            "quicktime" => Ok(Video::quicktime),
+            "x-ms-wmv" => Ok(Video::x_ms_wmv),
+            "x-msvideo" => Ok(Video::x_msvideo),
+            "x-flv" => Ok(Video::x_flv),
+            "webm" => Ok(Video::webm),
             _ => panic!("Invalid variant type"),
         }
     }
@@ -0,0 +1,110 @@
+//! headers module defines the header fields of an HTTP message; looked up case-insensitively, with every value of a repeated field kept
+use std::fmt::{self, Display, Formatter};
+
+/// Headers stores header fields in the order they were received; names are stored in lowercase
+///
+/// A field may be repeated, e.g. `Cookie` or `Accept`; [get](Headers::get) returns its first value & [get_all](Headers::get_all) every value.
+///
+/// # Example
+/// ```
+/// use pipelined_server::http::headers::Headers;
+///
+/// let mut headers = Headers::new();
+/// headers.append("Accept", "text/html");
+/// headers.append("accept", "application/json");
+///
+/// assert_eq!(headers.get("ACCEPT"), Some("text/html"));
+/// assert_eq!(headers.get_all("Accept").collect::<Vec<_>>(), ["text/html", "application/json"]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Headers(Vec<(String, String)>);
+
+impl Headers {
+    /// new creates an empty set of headers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// get returns the first value of the field name; None if it wasn't sent
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// get_all returns every value of the field name; in the order they were received
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.0
+            .iter()
+            .filter(move |(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// contains checks if the field name was sent
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// append adds a value to the field name; keeping any it already has
+    pub fn append(&mut self, name: &str, value: &str) {
+        self.0.push((name.to_ascii_lowercase(), value.to_string()));
+    }
+
+    /// insert sets the value of the field name; replacing any it already has
+    pub fn insert(&mut self, name: &str, value: &str) {
+        self.remove(name);
+        self.append(name, value);
+    }
+
+    /// remove removes every value of the field name
+    ///
+    /// # return
+    /// the removed values; empty if the field wasn't sent
+    pub fn remove(&mut self, name: &str) -> Vec<String> {
+        let (removed, kept) = std::mem::take(&mut self.0)
+            .into_iter()
+            .partition(|(key, _)| key.eq_ignore_ascii_case(name));
+
+        self.0 = kept;
+
+        removed.into_iter().map(|(_, value)| value).collect()
+    }
+
+    /// iter returns every (name, value) pair; in the order they were received
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// len returns the number of values stored
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// is_empty checks if no field was sent
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<N: AsRef<str>, V: AsRef<str>> FromIterator<(N, V)> for Headers {
+    fn from_iter<I: IntoIterator<Item = (N, V)>>(fields: I) -> Self {
+        let mut headers = Headers::new();
+
+        for (name, value) in fields {
+            headers.append(name.as_ref(), value.as_ref());
+        }
+
+        headers
+    }
+}
+
+impl Display for Headers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (name, value) in self.iter() {
+            write!(f, "{name}: {value}\r\n")?;
+        }
+
+        Ok(())
+    }
+}
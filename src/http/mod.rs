@@ -1,6 +1,8 @@
 //! http module is responsible for defining how HTTP requests are parsed and how HTTP responses should be formatted
 pub mod body;
+pub mod headers;
 pub mod request;
 pub mod response;
+pub mod version;
 
 pub use request::method;
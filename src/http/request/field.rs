@@ -0,0 +1,154 @@
+//! field module parses the [header fields](https://www.rfc-editor.org/rfc/rfc9112#section-5) of a request; enforcing the limits set on its header section
+use crate::http::headers::Headers;
+
+use super::{byte_len, parser_error::ParserError};
+
+/// FieldLimits defines how many header fields a request may send & how long each may be; and whether fields folded over several lines are accepted
+///
+/// # Example
+/// ```
+/// use pipelined_server::http::request::field::FieldLimits;
+///
+/// let limits = FieldLimits::default().set_max_fields(32).set_unfold(true);
+///
+/// assert_eq!(limits.max_fields, 32);
+/// assert!(limits.unfold);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldLimits {
+    /// most header fields a request may send
+    pub max_fields: usize,
+    /// longest field line accepted, in bytes; including any lines folded into it
+    pub max_field_size: usize,
+    /// whether [obsolete line folding](https://www.rfc-editor.org/rfc/rfc9112#section-5.2) is replaced with a space; requests using it are rejected otherwise
+    pub unfold: bool,
+}
+
+impl Default for FieldLimits {
+    fn default() -> Self {
+        Self {
+            max_fields: 100,
+            max_field_size: 8192,
+            unfold: false,
+        }
+    }
+}
+
+impl FieldLimits {
+    /// set_max_fields sets the most header fields a request may send
+    pub fn set_max_fields(mut self, max_fields: usize) -> Self {
+        self.max_fields = max_fields;
+        self
+    }
+
+    /// set_max_field_size sets the longest field line accepted, in bytes
+    pub fn set_max_field_size(mut self, max_field_size: usize) -> Self {
+        self.max_field_size = max_field_size;
+        self
+    }
+
+    /// set_unfold sets whether folded field values are unfolded, rather than rejected
+    pub fn set_unfold(mut self, unfold: bool) -> Self {
+        self.unfold = unfold;
+        self
+    }
+}
+
+/// fields parses the header section of a request from its lines; up to the empty line ending it
+///
+/// Lines may end with CRLF or a bare LF. A line starting with whitespace continues the field before it; it is joined to the value with a space if limits allow unfolding.
+///
+/// # Errors
+/// - HeaderFieldsTooLarge if more fields are sent, or a longer field line, than limits allow
+/// - InvalidHeader if a field is malformed, or folded while unfolding isn't allowed
+///
+/// The offset of an error is counted from the start of the first line, as if the lines were joined by LF.
+pub fn fields<'a, I: IntoIterator<Item = &'a str>>(lines: I, limits: &FieldLimits) -> Result<Headers, ParserError> {
+    // each field line; with the offset of the line it starts on
+    let mut field_lines: Vec<(usize, String)> = Vec::new();
+    let mut offset = 0;
+
+    for line in lines {
+        let start = offset;
+        offset += byte_len(line) + 1;
+
+        let line = line.strip_suffix('\r').unwrap_or(line);
+
+        if line.is_empty() {
+            break;
+        }
+
+        let folded = line.starts_with([' ', '\t']);
+
+        match field_lines.last_mut() {
+            Some((_, field_line)) if folded && limits.unfold => {
+                field_line.truncate(field_line.trim_end_matches([' ', '\t']).len());
+                field_line.push(' ');
+                field_line.push_str(line.trim_start_matches([' ', '\t']));
+            }
+            Some(_) if folded => return Err(invalid(start, "Obsolete line folding isn't accepted")),
+            None if folded => return Err(invalid(start, "Header section starts with whitespace")),
+            _ => field_lines.push((start, line.to_string())),
+        }
+
+        let too_long = field_lines
+            .last()
+            .is_some_and(|(_, field_line)| limits.max_field_size < byte_len(field_line));
+
+        if limits.max_fields < field_lines.len() || too_long {
+            return Err(ParserError::HeaderFieldsTooLarge { offset: start });
+        }
+    }
+
+    field_lines
+        .iter()
+        .map(|(start, line)| field(line).map_err(|err| err.offset_by(*start)))
+        .collect()
+}
+
+/// field splits a field line into its name & value; at the first colon, so values may contain colons
+///
+/// The value is stripped of surrounding whitespace.
+///
+/// # Errors
+/// InvalidHeader if there is no colon, the name isn't a [token](https://www.rfc-editor.org/rfc/rfc9110#section-5.6.2), e.g. has whitespace before the colon, or the value has a control character other than a tab
+pub fn field(line: &str) -> Result<(&str, &str), ParserError> {
+    let (name, value) = line
+        .split_once(':')
+        .ok_or_else(|| invalid(0, &format!("No colon in field line: {line}")))?;
+
+    if name.is_empty() {
+        return Err(invalid(0, "Field name is empty"));
+    }
+
+    if let Some(position) = name.chars().position(|char| !is_tchar(char)) {
+        return Err(invalid(position, &format!("Invalid field name: {name}")));
+    }
+
+    let trimmed = value.trim_start_matches([' ', '\t']);
+    let value_start = byte_len(name) + 1 + byte_len(value) - byte_len(trimmed);
+    let value = trimmed.trim_end_matches([' ', '\t']);
+
+    if let Some(position) = value.chars().position(|char| char.is_ascii_control() && char != '\t') {
+        return Err(invalid(value_start + position, &format!("Invalid value of {name}")));
+    }
+
+    Ok((name, value))
+}
+
+/// is_token checks if value is a non-empty [token](https://www.rfc-editor.org/rfc/rfc9110#section-5.6.2)
+pub(super) fn is_token(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(is_tchar)
+}
+
+/// is_tchar checks if char may be part of a token
+fn is_tchar(char: char) -> bool {
+    char.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(char)
+}
+
+fn invalid(offset: usize, reason: &str) -> ParserError {
+    ParserError::InvalidHeader {
+        offset,
+        reason: reason.to_string(),
+    }
+}
@@ -0,0 +1,272 @@
+//! framing module defines how the end of a request's body is found; from its Content-Length, or by decoding its [chunked](https://www.rfc-editor.org/rfc/rfc9112#section-7.1) transfer coding as it arrives
+use super::{
+    field::{field, is_token},
+    latin1,
+    parser_error::ParserError,
+};
+
+/// longest chunk size line accepted; including its extensions
+const MAX_CHUNK_LINE: usize = 4096;
+/// largest trailer section accepted
+const MAX_TRAILERS: usize = 8192;
+
+/// Framing defines how the body of a request is delimited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// the request has no body
+    None,
+    /// the body is the given number of bytes following the head
+    Length(usize),
+    /// the body is sent in chunks, ending with an empty chunk & trailers
+    Chunked,
+}
+
+/// framing reads how the body is delimited from the header fields of a request; as (name, value) pairs
+///
+/// Requests declaring both a Content-Length & a Transfer-Encoding are rejected; as a proxy reading the other header would see a different request, smuggling the rest past it.
+///
+/// # Errors
+/// - InvalidFraming if a Content-Length isn't a number, lengths differ, both headers are present, or chunked isn't the final transfer coding
+/// - UnsupportedEncoding if a transfer coding other than chunked is applied
+///
+/// Errors are found at offset 0; as the framing is read from the header section as a whole.
+pub fn framing<'a, I: IntoIterator<Item = (&'a str, &'a str)>>(fields: I) -> Result<Framing, ParserError> {
+    let mut lengths: Vec<&str> = Vec::new();
+    let mut codings: Vec<String> = Vec::new();
+
+    for (name, value) in fields {
+        let values = value.split(',').map(str::trim).filter(|value| !value.is_empty());
+
+        if name.trim().eq_ignore_ascii_case("content-length") {
+            lengths.extend(values);
+        } else if name.trim().eq_ignore_ascii_case("transfer-encoding") {
+            codings.extend(values.map(str::to_ascii_lowercase));
+        }
+    }
+
+    if !codings.is_empty() {
+        if !lengths.is_empty() {
+            return Err(framing_error("Both Content-Length and Transfer-Encoding are declared"));
+        }
+
+        if codings.last().map(String::as_str) != Some("chunked") {
+            return Err(framing_error("Final transfer coding isn't chunked"));
+        }
+
+        return match codings[..codings.len() - 1].first() {
+            Some(coding) if coding == "chunked" => Err(framing_error("Chunked is applied more than once")),
+            Some(coding) => Err(ParserError::UnsupportedEncoding {
+                offset: 0,
+                coding: coding.clone(),
+            }),
+            None => Ok(Framing::Chunked),
+        };
+    }
+
+    let mut length: Option<usize> = None;
+
+    for value in lengths {
+        let parsed = match value.bytes().all(|byte| byte.is_ascii_digit()) {
+            true => value.parse::<usize>().ok(),
+            false => None,
+        };
+
+        let parsed = parsed.ok_or_else(|| framing_error(&format!("Content-Length is not a number: {value}")))?;
+
+        if length.is_some_and(|length| length != parsed) {
+            return Err(framing_error("Content-Length values differ"));
+        }
+
+        length = Some(parsed);
+    }
+
+    Ok(length.map_or(Framing::None, Framing::Length))
+}
+
+/// ChunkedDecoder decodes a chunked body as it arrives; so the end of the body is found, & limits enforced, without waiting for all of it
+///
+/// Every line of the framing must end with CRLF; a bare LF is rejected, as intermediaries may disagree on where it ends the line. Chunk extensions are checked & ignored.
+#[derive(Debug)]
+pub struct ChunkedDecoder {
+    state: State,
+    /// part of the current framing line received so far
+    line: Vec<u8>,
+    decoded: usize,
+    /// number of bytes of the body read by previous calls to decode
+    position: usize,
+    max_body: usize,
+    trailers: Vec<(String, String)>,
+    trailers_size: usize,
+}
+
+/// State is the part of a chunked body being decoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Size,
+    Data { remaining: usize },
+    DataEnd,
+    Trailers,
+    Complete,
+}
+
+impl ChunkedDecoder {
+    /// new creates a decoder accepting bodies of up to max_body decoded bytes
+    pub fn new(max_body: usize) -> Self {
+        Self {
+            state: State::Size,
+            line: Vec::new(),
+            decoded: 0,
+            position: 0,
+            max_body,
+            trailers: Vec::new(),
+            trailers_size: 0,
+        }
+    }
+
+    /// decode reads input up to the end of the body; appending the decoded data to body if given
+    ///
+    /// # return
+    /// the number of bytes of input read; fewer than its length only if the body is complete
+    ///
+    /// # Errors
+    /// - InvalidChunk if the framing is malformed
+    /// - BodyTooLarge if the decoded body exceeds max_body, or a line or the trailers exceed their limits
+    ///
+    /// The offset of an error is counted from the start of the body; across every call.
+    pub fn decode(&mut self, input: &[u8], mut body: Option<&mut Vec<u8>>) -> Result<usize, ParserError> {
+        let mut read = 0;
+
+        while read < input.len() && self.state != State::Complete {
+            if let State::Data { remaining } = self.state {
+                let data = remaining.min(input.len() - read);
+
+                if let Some(body) = body.as_deref_mut() {
+                    body.extend_from_slice(&input[read..read + data]);
+                }
+
+                read += data;
+                self.state = match remaining - data {
+                    0 => State::DataEnd,
+                    remaining => State::Data { remaining },
+                };
+
+                continue;
+            }
+
+            let byte = input[read];
+            read += 1;
+
+            if byte != b'\n' {
+                let limit = match self.state {
+                    State::Trailers => MAX_TRAILERS.saturating_sub(self.trailers_size),
+                    _ => MAX_CHUNK_LINE,
+                };
+
+                if self.line.len() >= limit {
+                    return Err(ParserError::BodyTooLarge {
+                        offset: self.position + read - 1,
+                    });
+                }
+
+                self.line.push(byte);
+                continue;
+            }
+
+            // errors in a line are found at the LF ending it
+            let at = self.position + read - 1;
+
+            let line = match self.line.strip_suffix(b"\r") {
+                Some(line) => line.to_vec(),
+                None => return Err(chunk_error(at, "Line doesn't end with CRLF")),
+            };
+            self.line.clear();
+
+            self.state = match self.state {
+                State::Size => self.size(&line).map_err(|err| err.offset_by(at))?,
+                State::DataEnd if line.is_empty() => State::Size,
+                State::DataEnd => return Err(chunk_error(at, "Chunk data is longer than its size")),
+                State::Trailers if line.is_empty() => State::Complete,
+                State::Trailers => {
+                    self.trailers_size += line.len() + 2;
+                    self.trailers.push(trailer(&line).map_err(|err| err.offset_by(at))?);
+
+                    State::Trailers
+                }
+                State::Data { .. } | State::Complete => unreachable!("data isn't read by line"),
+            };
+        }
+
+        self.position += read;
+
+        Ok(read)
+    }
+
+    /// is_complete returns true once the empty chunk & trailers ending the body have been decoded
+    pub fn is_complete(&self) -> bool {
+        self.state == State::Complete
+    }
+
+    /// trailers returns the (name, value) header fields sent after the body; names in lowercase
+    pub fn trailers(&self) -> &[(String, String)] {
+        &self.trailers
+    }
+
+    /// size reads a chunk size line; returning the state following it, or an error at offset 0
+    fn size(&mut self, line: &[u8]) -> Result<State, ParserError> {
+        let line = std::str::from_utf8(line).map_err(|_| chunk_error(0, "Chunk size isn't ASCII"))?;
+
+        let (size, extensions) = line.split_once(';').unwrap_or((line, ""));
+        let size = size.trim_end_matches([' ', '\t']);
+
+        if size.is_empty() || !size.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return Err(chunk_error(0, &format!("Invalid chunk size: {size}")));
+        }
+
+        let size = usize::from_str_radix(size, 16).map_err(|_| ParserError::BodyTooLarge { offset: 0 })?;
+
+        if !extensions.is_empty() {
+            for extension in extensions.split(';') {
+                let name = extension.split_once('=').map_or(extension, |(name, _)| name);
+
+                if !is_token(name.trim_matches([' ', '\t'])) || extension.bytes().any(|byte| byte.is_ascii_control() && byte != b'\t') {
+                    return Err(chunk_error(0, &format!("Invalid chunk extension: {extension}")));
+                }
+            }
+        }
+
+        if size == 0 {
+            return Ok(State::Trailers);
+        }
+
+        self.decoded = match self.decoded.checked_add(size) {
+            Some(decoded) if decoded <= self.max_body => decoded,
+            _ => return Err(ParserError::BodyTooLarge { offset: 0 }),
+        };
+
+        Ok(State::Data { remaining: size })
+    }
+}
+
+/// trailer reads a trailer field line as a (name, value) pair; with its name in lowercase, or an error at offset 0
+fn trailer(line: &[u8]) -> Result<(String, String), ParserError> {
+    let line = latin1(line);
+
+    match field(&line) {
+        Ok((name, value)) => Ok((name.to_ascii_lowercase(), value.to_string())),
+        Err(_) => Err(chunk_error(0, &format!("Invalid trailer: {line}"))),
+    }
+}
+
+fn chunk_error(offset: usize, reason: &str) -> ParserError {
+    ParserError::InvalidChunk {
+        offset,
+        reason: reason.to_string(),
+    }
+}
+
+fn framing_error(reason: &str) -> ParserError {
+    ParserError::InvalidFraming {
+        offset: 0,
+        reason: reason.to_string(),
+    }
+}
@@ -1,94 +1,72 @@
 //! Method module define enums and method that store useable information for each [HTTP method](https://developer.mozilla.org/en-US/docs/Web/HTTP/Methods)
-use super::super::body::Body;
+use std::fmt::{self, Display, Formatter};
+
 use super::parser_error::ParserError;
 
-/// Method enum define the HTTP Method and inputted parameters
-#[derive(Debug, PartialEq, Eq)]
+/// Method enum define the HTTP Method of a request; its target & body are stored by the [Request](super::Request)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Method {
-    Get { file: String },
-    Head { file: String },
-    Post { file: String, body: Body },
-    Put { file: String, body: Body },
-    Delete { file: String, body: Option<Body> },
-    Connect { url: String },
-    Options { url: String },
-    Trace { file: String },
-    Patch { file: String, body: Body },
+    Get,
+    Head,
+    Post,
+    Put,
+    Delete,
+    Connect,
+    Options,
+    Trace,
+    Patch,
 }
 
 impl Method {
-    /// new constructor creates a method from the extracted components of the start line of HTTP request
+    /// new constructor creates a method from the method token of the start line of HTTP request
     ///
     /// # Errors
-    /// A ParserError is returned if method isn't provided with a correct paramater
+    /// UnknownMethod is returned if method isn't a known method name
     ///
-    /// This example would return a ParseError because Post method requires a body
+    /// # Example
     /// ```
-    /// Method::new("POST", "target", POST::None);
+    /// use pipelined_server::http::method::Method;
+    ///
+    /// assert_eq!(Method::new("post").unwrap(), Method::Post);
     /// ```
-    pub fn new(method: String, target: String, body: Option<Body>) -> Result<Method, ParserError> {
-        let body_unwrap = |body_tmp: Option<Body>| -> Result<Body, ParserError> {
-            match body_tmp {
-                Some(body) => Ok(body),
-                None => Err(ParserError::InvalidMethod(Some(String::from(
-                    "Http request requires body attribute",
-                )))),
-            }
-        };
-
+    pub fn new(method: &str) -> Result<Method, ParserError> {
         match method.to_ascii_uppercase().as_str() {
-            "GET" => Ok(Method::Get {
-                file: target.to_string(),
-            }),
-            "HEAD" => Ok(Method::Head {
-                file: target.to_string(),
-            }),
-            "POST" => Ok(Method::Post {
-                file: target.to_string(),
-                body: body_unwrap(body)?,
-            }),
-            "PUT" => Ok(Method::Put {
-                file: target.to_string(),
-                body: body_unwrap(body)?,
-            }),
-            "DELETE" => Ok(Method::Delete {
-                file: target.to_string(),
-                body: body,
-            }),
-            "CONNECT" => Ok(Method::Connect {
-                url: target.to_string(),
+            "GET" => Ok(Method::Get),
+            "HEAD" => Ok(Method::Head),
+            "POST" => Ok(Method::Post),
+            "PUT" => Ok(Method::Put),
+            "DELETE" => Ok(Method::Delete),
+            "CONNECT" => Ok(Method::Connect),
+            "OPTIONS" => Ok(Method::Options),
+            "TRACE" => Ok(Method::Trace),
+            "PATCH" => Ok(Method::Patch),
+            _ => Err(ParserError::UnknownMethod {
+                offset: 0,
+                method: method.to_string(),
             }),
-            "OPTIONS" => Ok(Method::Options {
-                url: target.to_string(),
-            }),
-            "TRACE" => Ok(Method::Trace {
-                file: target.to_string(),
-            }),
-            "PATCH" => Ok(Method::Patch {
-                file: target.to_string(),
-                body: body_unwrap(body)?,
-            }),
-            _ => Err(ParserError::InvalidMethod(Some(String::from(format!(
-                "{} is an invalid method name",
-                method.as_str()
-            ))))),
         }
     }
+
+    /// requires_body checks if requests made with the method must have a body
+    pub fn requires_body(&self) -> bool {
+        matches!(self, Method::Post | Method::Put | Method::Patch)
+    }
 }
 
-impl ToString for Method {
-    fn to_string(&self) -> String {
-        #[allow(unused_variables)]
-        match &self {
-            Method::Get { file } => String::from(format!("GET file={}", file)),
-            Method::Head { file } => String::from(format!("Head file={}", file)),
-            Method::Post { file, body } => String::from(format!("POST file={}", file)),
-            Method::Put { file, body } => String::from(format!("PUT file={}", file)),
-            Method::Delete { file, body } => String::from(format!("DELETE file={}", file)),
-            Method::Connect { url } => String::from(format!("CONNECT URL={}", url)),
-            Method::Options { url } => String::from(format!("OPTIONS URL={}", url)),
-            Method::Trace { file } => String::from(format!("TRACE file={}", file)),
-            Method::Patch { file, body } => String::from(format!("PATCH file={}", file)),
-        }
+impl Display for Method {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let method = match self {
+            Method::Get => "GET",
+            Method::Head => "HEAD",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Delete => "DELETE",
+            Method::Connect => "CONNECT",
+            Method::Options => "OPTIONS",
+            Method::Trace => "TRACE",
+            Method::Patch => "PATCH",
+        };
+
+        write!(f, "{method}")
     }
 }
@@ -1,163 +1,284 @@
 //! request module is responsible for enums, structs and functions responsible for parsing Requests
+pub mod field;
+pub mod framing;
 pub mod method;
 pub mod parser_error;
+mod target;
 
 #[cfg(test)]
 mod tests;
 
-use std::{
-    collections::HashMap,
-    str::{FromStr, Split},
+use std::str::{FromStr, Split};
+
+use self::{
+    field::{fields, FieldLimits},
+    framing::{framing, ChunkedDecoder, Framing},
+    method::Method,
+    parser_error::ParserError,
+    target::parse_target,
+};
+
+use super::{
+    body::{Application, Body, ContentType},
+    headers::Headers,
+    version::Version,
 };
 
-use self::{method::Method, parser_error::ParserError};
+pub use self::target::Query;
 
-use super::body::{Body, ContentType};
+/// longest target accepted, in bytes; requests with longer targets are rejected with TargetTooLong
+pub const MAX_TARGET_SIZE: usize = 8192;
 
+/// Request struct defines a parsed HTTP request
 #[derive(Debug, PartialEq, Eq)]
-pub struct Request(pub Method, pub HashMap<String, String>);
+pub struct Request {
+    pub method: Method,
+    /// target of the start line; as it was sent
+    pub target: String,
+    /// percent-decoded path of the target; without its query
+    pub path: String,
+    /// decoded parameters of the target's query
+    pub query: Query,
+    pub headers: Headers,
+    pub version: Version,
+    pub body: Option<Body>,
+}
+
+impl Request {
+    /// new creates a request for target without any headers or body; its path & query are parsed from target
+    ///
+    /// # Example
+    /// ```
+    /// use pipelined_server::http::{method::Method, request::Request, version::Version};
+    ///
+    /// let request = Request::new(Method::Get, "/search?q=pipelined+server", Version::Http1_1);
+    ///
+    /// assert_eq!(request.path, "/search");
+    /// assert_eq!(request.query.get("q"), Some("pipelined server"));
+    /// ```
+    pub fn new(method: Method, target: &str, version: Version) -> Self {
+        let (path, query) = parse_target(target);
+
+        Self {
+            method,
+            target: target.to_string(),
+            path,
+            query,
+            headers: Headers::new(),
+            version,
+            body: None,
+        }
+    }
+
+    /// parse parses a request from the bytes received; the head is decoded as Latin-1 & the body is kept byte for byte
+    ///
+    /// # Errors
+    /// A ParserError is returned if the request is malformed, or its header section exceeds limits
+    ///
+    /// # Example
+    /// ```
+    /// use pipelined_server::http::request::{field::FieldLimits, Request};
+    ///
+    /// let request = b"GET / HTTP/1.1\r\nx-folded: first\r\n second\r\n\r\n";
+    ///
+    /// assert!(Request::try_from(&request[..]).is_err());
+    ///
+    /// let request = Request::parse(request, &FieldLimits::default().set_unfold(true)).unwrap();
+    /// assert_eq!(request.headers.get("x-folded"), Some("first second"));
+    /// ```
+    pub fn parse(bytes: &[u8], limits: &FieldLimits) -> Result<Self, ParserError> {
+        let (head, body) = split_head(bytes);
+        let head = latin1(head);
+        let mut request = head.split("\n");
+
+        let start_line = request.next().unwrap_or_default();
+        let (method, target, version) = get_start_line(start_line)?;
+
+        let offsets = (byte_len(start_line) + 1, bytes.len() - body.len());
+        let (body, headers) = get_data(request, body, limits, offsets)?;
+
+        if method.requires_body() && body.is_none() {
+            return Err(ParserError::MissingBody { offset: offsets.1 });
+        }
+
+        Ok(Request {
+            headers,
+            body,
+            ..Request::new(method, target, version)
+        })
+    }
+}
 
 impl FromStr for Request {
     type Err = ParserError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut request = s.split("\n");
-
-        let (method, target, _version) = match get_start_line(request.next()) {
-            Ok(ok) => ok,
-            Err(err) => return Err(err),
-        };
+        Request::try_from(s.as_bytes())
+    }
+}
 
-        let method = method.to_uppercase();
+impl TryFrom<&[u8]> for Request {
+    type Error = ParserError;
 
-        let (body, meta_data) = get_data(request)?;
+    /// try_from parses a request from the bytes received; with the default [FieldLimits]
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Request::parse(bytes, &FieldLimits::default())
+    }
+}
 
-        let method: Method = Method::new(method.to_string(), target.to_string(), body)?;
+/// latin1 decodes the bytes of a head; every byte is a character, so no byte sent by a client is rejected or replaced
+pub(crate) fn latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&byte| char::from(byte)).collect()
+}
 
-        Ok(Request(method, meta_data))
-    }
+/// byte_len returns the number of bytes part of a head was decoded from by [latin1]; one per character
+pub(crate) fn byte_len(decoded: &str) -> usize {
+    decoded.chars().count()
 }
 
 /// get_start_line method extracts the method, http version and target from the first line of a request
 ///
 /// # Errors
-/// If the first line does not contain all three method, target and http version; in the specified order - then a ParserError is returned for an invalid request.
-fn get_start_line<'a>(
-    start_line: Option<&'a str>,
-) -> Result<(&'a str, &'a str, &'a str), ParserError> {
-    let start_line = match start_line {
-        Some(val) => val,
-        None => {
-            return Err(ParserError::InvalidMethod(Some(String::from(
-                "No start line",
-            ))))
+/// - InvalidStartLine if the first line isn't a method, target & http version separated by whitespace; in the specified order
+/// - UnknownMethod, TargetTooLong or UnsupportedVersion if the method, target or version can't be served
+fn get_start_line(start_line: &str) -> Result<(Method, &str, Version), ParserError> {
+    let mut tokens: Vec<(usize, &str)> = Vec::new();
+    let mut offset = 0;
+
+    for token in start_line.split([' ', '\t', '\r']) {
+        if !token.is_empty() {
+            tokens.push((offset, token));
         }
-    };
-
-    let mut start_line = start_line.split_whitespace();
 
-    let method: &str = start_line
-        .next()
-        .ok_or(ParserError::InvalidMethod(Some(String::from("No method"))))?;
-    let target: &str = start_line
-        .next()
-        .ok_or(ParserError::InvalidMethod(Some(String::from("No targert"))))?;
-    let version: &str = start_line
-        .next()
-        .ok_or(ParserError::InvalidMethod(Some(String::from(
-            "No HTTP version",
-        ))))?;
+        offset += byte_len(token) + 1;
+    }
 
-    Ok((method, target, version))
-}
+    let end = byte_len(start_line.trim_end_matches([' ', '\t', '\r']));
 
-/// get_data method extras metadata and possible Body from request
-///
-/// # Errors
-/// A ParserError is returned if a body doesn't have a valid content-type
-fn get_data<'a>(
-    mut line_iter: Split<&'a str>,
-) -> Result<(Option<Body>, HashMap<String, String>), ParserError> {
-    let mut meta_data: HashMap<String, String> = HashMap::new();
-
-    let mut content_type: Option<&str> = None;
-    let mut content_lenght: Option<&str> = None;
-
-    loop {
-        let line = match line_iter.next() {
-            Some(value) => value,
-            None => break,
-        };
-
-        if line == "\r" || line == "" {
-            break;
-        }
-
-        let (key, value) = get_key_value_pair(line)?;
+    let [(method_at, method), (target_at, target), (version_at, version)] = match tokens[..] {
+        [] => return Err(start_line_error(0, "No method")),
+        [_] => return Err(start_line_error(end, "No target")),
+        [_, _] => return Err(start_line_error(end, "No HTTP version")),
+        [method, target, version] => [method, target, version],
+        [.., (offset, token)] => return Err(start_line_error(offset, &format!("Unexpected {token} after the HTTP version"))),
+    };
 
-        let key = key.trim().to_ascii_lowercase();
-        let value = value.trim();
+    let method = Method::new(method).map_err(|err| err.offset_by(method_at))?;
 
-        if key == "content-type" {
-            content_type = Some(value);
-        } else if key == "content-length" {
-            content_lenght = Some(value);
-        } else {
-            meta_data.insert(key, value.to_string());
-        }
+    if MAX_TARGET_SIZE < byte_len(target) {
+        return Err(ParserError::TargetTooLong {
+            offset: target_at,
+            length: byte_len(target),
+        });
     }
 
-    if content_type.is_none() || content_lenght.is_none() {
-        return Ok((None, meta_data));
-    }
+    let version = Version::parse(version).map_err(|err| err.offset_by(version_at))?;
 
-    let content_type = content_type.unwrap();
-    let _content_lenght = content_lenght.unwrap();
+    Ok((method, target, version))
+}
 
-    let mut body: String = String::from("");
+fn start_line_error(offset: usize, reason: &str) -> ParserError {
+    ParserError::InvalidStartLine {
+        offset,
+        reason: reason.to_string(),
+    }
+}
 
-    loop {
-        let line = match line_iter.next() {
-            Some(value) => value,
-            None => break,
-        };
+/// split_head splits request at the empty line ending its head; the body is empty if there is none
+fn split_head(request: &[u8]) -> (&[u8], &[u8]) {
+    let end = [&b"\n\r\n"[..], b"\n\n"]
+        .iter()
+        .filter_map(|terminator| {
+            request
+                .windows(terminator.len())
+                .position(|window| window == *terminator)
+                .map(|position| (position, position + terminator.len()))
+        })
+        .min();
+
+    match end {
+        Some((head_end, body_start)) => (&request[..head_end], &request[body_start..]),
+        None => (request, &[]),
+    }
+}
 
-        if line.contains("\u{0}") {
-            body.push_str(line.trim_end_matches('\u{0}'));
-            break;
+/// get_data method extras headers and possible Body from request
+///
+/// The body is read as its Content-Length or chunked Transfer-Encoding declares; a body without a content-type is application/octet-stream. Trailers of a chunked body are added to the headers, without adding to any header that was sent.
+///
+/// # Errors
+/// A ParserError is returned if a header field is malformed or exceeds limits, or a body doesn't have a valid content-type, or isn't framed correctly or is shorter than declared; at its offset from the header section & body starting at offsets
+fn get_data(
+    line_iter: Split<&str>,
+    raw_body: &[u8],
+    limits: &FieldLimits,
+    (headers_start, body_start): (usize, usize),
+) -> Result<(Option<Body>, Headers), ParserError> {
+    let mut headers = fields(line_iter.clone(), limits).map_err(|err| err.offset_by(headers_start))?;
+
+    let content = match framing(headers.iter()).map_err(|err| err.offset_by(headers_start))? {
+        Framing::None => return Ok((None, headers)),
+        Framing::Length(length) if raw_body.len() < length => {
+            return Err(ParserError::BodyLengthMismatch {
+                offset: body_start + raw_body.len(),
+                expected: length,
+                received: raw_body.len(),
+            })
         }
-        body.push_str(line);
-    }
+        Framing::Length(length) => raw_body[..length].to_vec(),
+        Framing::Chunked => {
+            let mut decoder = ChunkedDecoder::new(usize::MAX);
+            let mut content = Vec::new();
+
+            decoder
+                .decode(raw_body, Some(&mut content))
+                .map_err(|err| err.offset_by(body_start))?;
+
+            if !decoder.is_complete() {
+                return Err(ParserError::InvalidChunk {
+                    offset: body_start + raw_body.len(),
+                    reason: String::from("Body ends before its last chunk"),
+                });
+            }
+
+            for (name, value) in decoder.trailers() {
+                // framing & routing can't be changed once the body has been read
+                let forbidden = ["content-length", "transfer-encoding", "content-type", "host", "trailer"].contains(&name.as_str());
+
+                if !forbidden && !headers.contains(name) {
+                    headers.append(name, value);
+                }
+            }
+
+            content
+        }
+    };
 
-    let content_type = match ContentType::new(content_type) {
-        Ok(val) => val,
-        Err(err) => return Err(err),
+    let content_type = match headers.get("content-type") {
+        Some(content_type) => ContentType::new(content_type)
+            .map_err(|err| err.offset_by(headers_start + field_offset(line_iter, "content-type")))?,
+        None => ContentType::Application(Application::octet_stream),
     };
 
     let body = Body {
-        content_type: content_type,
-        content: body.as_bytes().to_vec(),
+        content_type,
+        content,
     };
 
-    return Ok((Some(body), meta_data));
+    Ok((Some(body), headers))
 }
 
-/// get_key_value_pair extracts key and associated value from unparsed metadata string
-///
-/// # Errors
-/// A parse Error is returned if the data isn't stored in the format [key]:value
-fn get_key_value_pair<'a>(line: &'a str) -> Result<(&'a str, &'a str), ParserError> {
-    let mut line = line.split(':');
-    let key = line
-        .next()
-        .ok_or(ParserError::InvalidMethod(Some(String::from(
-            "No key in key value pair",
-        ))))?;
-    let value = line
-        .next()
-        .ok_or(ParserError::InvalidMethod(Some(String::from(
-            "No value in key value pair",
-        ))))?;
-
-    Ok((key, value))
+/// field_offset returns the offset of the first field line named name; counted from the start of lines as [fields] counts it
+fn field_offset(lines: Split<&str>, name: &str) -> usize {
+    let mut offset = 0;
+
+    for line in lines {
+        if line.split_once(':').is_some_and(|(field, _)| field.eq_ignore_ascii_case(name)) {
+            break;
+        }
+
+        offset += byte_len(line) + 1;
+    }
+
+    offset
 }
@@ -1,7 +1,101 @@
 //! Parser_error module defines enums required to parsing errors
+use std::fmt::{self, Display, Formatter};
 
 /// ParserError enum defines variants that represent errors that can emerge through parsing of request
-#[derive(Debug)]
+///
+/// Every variant carries the offset of the byte the error was found at; counted from the start of the request, or of the input given to the function returning it.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ParserError {
-    InvalidMethod(Option<String>), //split invalid method into more precise errors
+    /// the start line isn't a method, target & version separated by whitespace
+    InvalidStartLine { offset: usize, reason: String },
+    /// the method isn't one the server knows
+    UnknownMethod { offset: usize, method: String },
+    /// the target is longer than the server accepts
+    TargetTooLong { offset: usize, length: usize },
+    /// the version is well formed, but isn't one the server speaks
+    UnsupportedVersion { offset: usize, version: String },
+    /// a header field is malformed
+    InvalidHeader { offset: usize, reason: String },
+    /// the header section has more fields, or a longer field, than its limits allow
+    HeaderFieldsTooLarge { offset: usize },
+    /// the content-type of the body isn't one the server knows
+    InvalidContentType { offset: usize, reason: String },
+    /// the end of the body can't be told from the Content-Length & Transfer-Encoding headers
+    InvalidFraming { offset: usize, reason: String },
+    /// the request has fewer bytes of body than its Content-Length declares
+    BodyLengthMismatch { offset: usize, expected: usize, received: usize },
+    /// the method requires a body; but the request doesn't declare one
+    MissingBody { offset: usize },
+    /// the chunked transfer coding of the body is malformed
+    InvalidChunk { offset: usize, reason: String },
+    /// the body exceeds the size limit; or a chunk line or the trailers exceed theirs
+    BodyTooLarge { offset: usize },
+    /// a transfer coding the server can't decode is applied to the body
+    UnsupportedEncoding { offset: usize, coding: String },
+}
+
+impl ParserError {
+    /// offset returns the offset of the byte the error was found at
+    pub fn offset(&self) -> usize {
+        match self {
+            ParserError::InvalidStartLine { offset, .. }
+            | ParserError::UnknownMethod { offset, .. }
+            | ParserError::TargetTooLong { offset, .. }
+            | ParserError::UnsupportedVersion { offset, .. }
+            | ParserError::InvalidHeader { offset, .. }
+            | ParserError::HeaderFieldsTooLarge { offset }
+            | ParserError::InvalidContentType { offset, .. }
+            | ParserError::InvalidFraming { offset, .. }
+            | ParserError::BodyLengthMismatch { offset, .. }
+            | ParserError::MissingBody { offset }
+            | ParserError::InvalidChunk { offset, .. }
+            | ParserError::BodyTooLarge { offset }
+            | ParserError::UnsupportedEncoding { offset, .. } => *offset,
+        }
+    }
+
+    /// offset_by moves the error by base bytes; so an error found in part of a request is placed in the whole of it
+    pub(crate) fn offset_by(mut self, base: usize) -> Self {
+        match &mut self {
+            ParserError::InvalidStartLine { offset, .. }
+            | ParserError::UnknownMethod { offset, .. }
+            | ParserError::TargetTooLong { offset, .. }
+            | ParserError::UnsupportedVersion { offset, .. }
+            | ParserError::InvalidHeader { offset, .. }
+            | ParserError::HeaderFieldsTooLarge { offset }
+            | ParserError::InvalidContentType { offset, .. }
+            | ParserError::InvalidFraming { offset, .. }
+            | ParserError::BodyLengthMismatch { offset, .. }
+            | ParserError::MissingBody { offset }
+            | ParserError::InvalidChunk { offset, .. }
+            | ParserError::BodyTooLarge { offset }
+            | ParserError::UnsupportedEncoding { offset, .. } => *offset += base,
+        }
+
+        self
+    }
+}
+
+impl Display for ParserError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParserError::InvalidStartLine { reason, .. } => write!(f, "Invalid start line: {reason}"),
+            ParserError::UnknownMethod { method, .. } => write!(f, "Unknown method: {method}"),
+            ParserError::TargetTooLong { length, .. } => write!(f, "Target of {length} bytes is too long"),
+            ParserError::UnsupportedVersion { version, .. } => write!(f, "Unsupported version: {version}"),
+            ParserError::InvalidHeader { reason, .. } => write!(f, "Invalid header: {reason}"),
+            ParserError::HeaderFieldsTooLarge { .. } => write!(f, "Header fields are too large"),
+            ParserError::InvalidContentType { reason, .. } => write!(f, "Invalid content type: {reason}"),
+            ParserError::InvalidFraming { reason, .. } => write!(f, "Invalid framing: {reason}"),
+            ParserError::BodyLengthMismatch { expected, received, .. } => {
+                write!(f, "Body of {received} bytes is shorter than its Content-Length of {expected}")
+            }
+            ParserError::MissingBody { .. } => write!(f, "Method requires a body"),
+            ParserError::InvalidChunk { reason, .. } => write!(f, "Invalid chunk: {reason}"),
+            ParserError::BodyTooLarge { .. } => write!(f, "Body is too large"),
+            ParserError::UnsupportedEncoding { coding, .. } => write!(f, "Unsupported transfer coding: {coding}"),
+        }?;
+
+        write!(f, " at byte {}", self.offset())
+    }
 }
@@ -0,0 +1,122 @@
+//! target module parses the [request target](https://www.rfc-editor.org/rfc/rfc9112#section-3.2) of a start line into its decoded path & query
+/// Query stores the decoded parameters of a query string in the order they were sent; a parameter may be repeated
+///
+/// # Example
+/// ```
+/// use pipelined_server::http::request::Query;
+///
+/// let query = Query::parse("tag=a&tag=b+c&empty");
+///
+/// assert_eq!(query.get("tag"), Some("a"));
+/// assert_eq!(query.get_all("tag").collect::<Vec<_>>(), ["a", "b c"]);
+/// assert_eq!(query.get("empty"), Some(""));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query(Vec<(String, String)>);
+
+impl Query {
+    /// parse decodes an `application/x-www-form-urlencoded` query string; without its leading `?`
+    pub fn parse(query: &str) -> Self {
+        let parameters = query
+            .split('&')
+            .filter(|parameter| !parameter.is_empty())
+            .map(|parameter| {
+                let (name, value) = parameter.split_once('=').unwrap_or((parameter, ""));
+
+                (decode_component(name), decode_component(value))
+            })
+            .collect();
+
+        Self(parameters)
+    }
+
+    /// get returns the first value of the parameter name; None if it wasn't sent
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// get_all returns every value of the parameter name; in the order they were sent
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.0
+            .iter()
+            .filter(move |(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// contains checks if the parameter name was sent
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// iter returns every (name, value) pair; in the order they were sent
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// is_empty checks if no parameter was sent
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// parse_target splits target into its decoded path & query
+///
+/// Origin-form targets (`/path?query`) & absolute-form targets (`http://host/path?query`) have their path percent-decoded; the path of an absolute-form target without one is `/`. Authority-form (`host:port`) & asterisk-form (`*`) targets are their own path. Any fragment is dropped.
+pub(super) fn parse_target(target: &str) -> (String, Query) {
+    let target = target.split_once('#').map_or(target, |(target, _)| target);
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let path = match path.split_once("://") {
+        Some((scheme, rest)) if !scheme.is_empty() && !scheme.contains('/') => match rest.find('/') {
+            Some(start) => &rest[start..],
+            None => "/",
+        },
+        _ => path,
+    };
+
+    (percent_decode(path, false), Query::parse(query))
+}
+
+/// decode_component decodes a name or value of a query string; where `+` encodes a space
+fn decode_component(component: &str) -> String {
+    percent_decode(component, true)
+}
+
+/// percent_decode decodes every `%XX` escape in value; escapes that aren't valid are kept as is & bytes that don't decode to UTF-8 are replaced
+fn percent_decode(value: &str, plus_as_space: bool) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = match bytes[index] {
+            b'%' => bytes
+                .get(index + 1..index + 3)
+                .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+
+        match (escaped, bytes[index]) {
+            (Some(byte), _) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (None, b'+') if plus_as_space => {
+                decoded.push(b' ');
+                index += 1;
+            }
+            (None, byte) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
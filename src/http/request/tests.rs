@@ -513,19 +513,14 @@ mod make_http_request {
         //test modeled from syntax form https://developer.mozilla.org/en-US/docs/Web/HTTP/Methods/GET
         //GET /index.html
 
-        let Request(method, _meta_data) = match Request::from_str("GET /index.html HTTP/1.1") {
+        let request = match Request::from_str("GET /index.html HTTP/1.1") {
             Ok(val) => val,
             Err(err) => panic!("{:?}", err),
         };
 
-        match method {
-            Method::Get { file } => {
-                assert_eq!(file, String::from("/index.html"));
-            }
-            _ => {
-                panic!("Incorrect variant. Got {} instead", method.to_string());
-            }
-        }
+        assert_eq!(request.method, Method::Get);
+        assert_eq!(request.path, "/index.html");
+        assert!(request.body.is_none());
     }
 
     //head test
@@ -534,19 +529,13 @@ mod make_http_request {
         //test modeled from syntax form https://developer.mozilla.org/en-US/docs/Web/HTTP/Methods/HEAD
         //HEAD /index.html
 
-        let Request(method, _meta_data) = match Request::from_str("HEAD /index.html HTTP/1.1") {
+        let request = match Request::from_str("HEAD /index.html HTTP/1.1") {
             Ok(val) => val,
             Err(err) => panic!("{:?}", err),
         };
 
-        match method {
-            Method::Head { file } => {
-                assert_eq!(file, String::from("/index.html"));
-            }
-            _ => {
-                panic!("Incorrect variant. Got {} instead", method.to_string());
-            }
-        }
+        assert_eq!(request.method, Method::Head);
+        assert_eq!(request.path, "/index.html");
     }
 
     //post test
@@ -554,26 +543,22 @@ mod make_http_request {
     fn post_request() {
         //test modeled from syntax form https://developer.mozilla.org/en-US/docs/Web/HTTP/Methods/POST
 
-        let Request(method, _meta_data) = match Request::from_str("POST /test HTTP/1.1\nHost: foo.example\nContent-Type: application/x-www-form-urlencoded\nContent-Length: 27\n\nfield1=value1&field2=value2") {
+        let request = match Request::from_str("POST /test HTTP/1.1\nHost: foo.example\nContent-Type: application/x-www-form-urlencoded\nContent-Length: 27\n\nfield1=value1&field2=value2") {
             Ok(val) => val,
             Err(err) => panic!("{:?}", err),
         };
 
-        match method {
-            Method::Post { file, body } => {
-                assert_eq!(file, String::from("/test"));
+        assert_eq!(request.method, Method::Post);
+        assert_eq!(request.path, "/test");
 
-                assert_eq!(
-                    body.content_type.to_string(),
-                    "application/x-www-form-urlencoded"
-                );
+        let body = request.body.expect("Missing body");
 
-                assert_eq!(body.content, "field1=value1&field2=value2".as_bytes());
-            }
-            _ => {
-                panic!("Incorrect variant. Got {} instead", method.to_string());
-            }
-        }
+        assert_eq!(
+            body.content_type.to_string(),
+            "application/x-www-form-urlencoded"
+        );
+
+        assert_eq!(body.content, "field1=value1&field2=value2".as_bytes());
     }
 
     //put test
@@ -581,23 +566,19 @@ mod make_http_request {
     fn put_request() {
         //test modeled from syntax form https://developer.mozilla.org/en-US/docs/Web/HTTP/Methods/PUT
 
-        let Request(method, _meta_data) = match Request::from_str("PUT /new.html HTTP/1.1\nHost: example.com\nContent-type: text/html\nContent-length: 16\n\n<p>New File</p>") {
+        let request = match Request::from_str("PUT /new.html HTTP/1.1\nHost: example.com\nContent-type: text/html\nContent-length: 15\n\n<p>New File</p>") {
             Ok(val) => val,
             Err(err) => panic!("{:?}", err),
         };
 
-        match method {
-            Method::Put { file, body } => {
-                assert_eq!(file, String::from("/new.html"));
+        assert_eq!(request.method, Method::Put);
+        assert_eq!(request.path, "/new.html");
 
-                assert_eq!(body.content_type.to_string(), "text/html");
+        let body = request.body.expect("Missing body");
 
-                assert_eq!(body.content, "<p>New File</p>".as_bytes());
-            }
-            _ => {
-                panic!("Incorrect variant. Got {} instead", method.to_string());
-            }
-        }
+        assert_eq!(body.content_type.to_string(), "text/html");
+
+        assert_eq!(body.content, "<p>New File</p>".as_bytes());
     }
 
     //delete test
@@ -605,28 +586,22 @@ mod make_http_request {
     fn delete_request() {
         //test modeled from syntax form https://developer.mozilla.org/en-US/docs/Web/HTTP/Methods/DELETE
 
-        let Request(method, _meta_data) = match Request::from_str("DELETE /file.html HTTP/1.1\nHost: example.com\nContent-type: text/html\nContent-length: 16\n\n<p>New File</p>") {
+        let request = match Request::from_str("DELETE /file.html HTTP/1.1\nHost: example.com\nContent-type: text/html\nContent-length: 15\n\n<p>New File</p>") {
             Ok(val) => val,
             Err(err) => panic!("{:?}", err),
         };
 
-        match method {
-            Method::Delete { file, body } => {
-                assert_eq!(file, String::from("/file.html"));
+        assert_eq!(request.method, Method::Delete);
+        assert_eq!(request.path, "/file.html");
 
-                match body {
-                    Some(body) => {
-                        assert_eq!(body.content_type.to_string(), "text/html");
+        match request.body {
+            Some(body) => {
+                assert_eq!(body.content_type.to_string(), "text/html");
 
-                        assert_eq!(body.content, "<p>New File</p>".as_bytes());
-                    }
-                    None => {
-                        panic!("Missing body");
-                    }
-                }
+                assert_eq!(body.content, "<p>New File</p>".as_bytes());
             }
-            _ => {
-                panic!("Incorrect variant. Got {} instead", method.to_string());
+            None => {
+                panic!("Missing body");
             }
         }
     }
@@ -635,22 +610,14 @@ mod make_http_request {
     fn delete_no_body_request() {
         //test modeled from syntax form https://developer.mozilla.org/en-US/docs/Web/HTTP/Methods/DELETE
 
-        let Request(method, _meta_data) =
-            match Request::from_str("DELETE /file.html HTTP/1.1\nHost: example.com") {
-                Ok(val) => val,
-                Err(err) => panic!("{:?}", err),
-            };
-
-        match method {
-            Method::Delete { file, body } => {
-                assert_eq!(file, String::from("/file.html"));
+        let request = match Request::from_str("DELETE /file.html HTTP/1.1\nHost: example.com") {
+            Ok(val) => val,
+            Err(err) => panic!("{:?}", err),
+        };
 
-                assert!(body.is_none());
-            }
-            _ => {
-                panic!("Incorrect variant. Got {} instead", method.to_string());
-            }
-        }
+        assert_eq!(request.method, Method::Delete);
+        assert_eq!(request.path, "/file.html");
+        assert!(request.body.is_none());
     }
 
     //connect
@@ -658,20 +625,13 @@ mod make_http_request {
     fn connect_request() {
         //test modeled from syntax form https://developer.mozilla.org/en-US/docs/Web/HTTP/Methods/CONNECT
 
-        let Request(method, _meta_data) =
-            match Request::from_str("CONNECT www.example.com:443 HTTP/1.1") {
-                Ok(val) => val,
-                Err(err) => panic!("{:?}", err),
-            };
+        let request = match Request::from_str("CONNECT www.example.com:443 HTTP/1.1") {
+            Ok(val) => val,
+            Err(err) => panic!("{:?}", err),
+        };
 
-        match method {
-            Method::Connect { url } => {
-                assert_eq!(url, String::from("www.example.com:443"));
-            }
-            _ => {
-                panic!("Incorrect variant. Got {} instead", method.to_string());
-            }
-        }
+        assert_eq!(request.method, Method::Connect);
+        assert_eq!(request.target, "www.example.com:443");
     }
 
     //options test
@@ -679,20 +639,14 @@ mod make_http_request {
     fn options_request() {
         //test modeled from syntax form https://developer.mozilla.org/en-US/docs/Web/HTTP/Methods/OPTIONS
 
-        let Request(method, _meta_data) = match Request::from_str("OPTIONS https://example.org -i")
-        {
+        let request = match Request::from_str("OPTIONS https://example.org HTTP/1.1") {
             Ok(val) => val,
             Err(err) => panic!("{:?}", err),
         };
 
-        match method {
-            Method::Options { url } => {
-                assert_eq!(url, String::from("https://example.org"));
-            }
-            _ => {
-                panic!("Incorrect variant. Got {} instead", method.to_string());
-            }
-        }
+        assert_eq!(request.method, Method::Options);
+        assert_eq!(request.target, "https://example.org");
+        assert_eq!(request.path, "/");
     }
 
     //trace test
@@ -700,19 +654,13 @@ mod make_http_request {
     fn trace_request() {
         //test modeled from syntax form https://developer.mozilla.org/en-US/docs/Web/HTTP/Methods/OPTIONS
 
-        let Request(method, _meta_data) = match Request::from_str("TRACE /index.html HTTP/1.1") {
+        let request = match Request::from_str("TRACE /index.html HTTP/1.1") {
             Ok(val) => val,
             Err(err) => panic!("{:?}", err),
         };
 
-        match method {
-            Method::Trace { file } => {
-                assert_eq!(file, String::from("/index.html"));
-            }
-            _ => {
-                panic!("Incorrect variant. Got {} instead", method.to_string());
-            }
-        }
+        assert_eq!(request.method, Method::Trace);
+        assert_eq!(request.path, "/index.html");
     }
 
     //patch test
@@ -720,22 +668,571 @@ mod make_http_request {
     fn patch_request() {
         //test modeled from syntax form https://developer.mozilla.org/en-US/docs/Web/HTTP/Methods/PUT
 
-        let Request(method, _meta_data) = match Request::from_str("PATCH /file.txt HTTP/1.1\nHost: www.example.com\nContent-Type: application/pdf\nIf-Match: 'e0023aa4e'\nContent-Length: 100\n\n[description of changes]") {
+        let request = match Request::from_str("PATCH /file.txt HTTP/1.1\nHost: www.example.com\nContent-Type: application/pdf\nIf-Match: 'e0023aa4e'\nContent-Length: 24\n\n[description of changes]") {
             Ok(val) => val,
             Err(err) => panic!("{:?}", err),
         };
 
-        match method {
-            Method::Patch { file, body } => {
-                assert_eq!(file, String::from("/file.txt"));
+        assert_eq!(request.method, Method::Patch);
+        assert_eq!(request.path, "/file.txt");
+
+        let body = request.body.expect("Missing body");
+
+        assert_eq!(body.content_type.to_string(), "application/pdf");
+
+        assert_eq!(body.content, "[description of changes]".as_bytes());
+    }
+}
+
+mod chunked {
+    use std::str::FromStr;
+
+    use crate::http::{
+        headers::Headers,
+        method::Method,
+        request::{
+            framing::{framing, ChunkedDecoder, Framing},
+            parser_error::ParserError,
+            Request,
+        },
+    };
+
+    const BODY: &[u8] = b"7;name=\"value\"\r\nchunked\r\n1\r\n \r\nA ; last\r\nbody parts\r\n0\r\nexpires: never\r\n\r\n";
+
+    #[test]
+    fn framing_from_headers() {
+        assert_eq!(framing([("host", "example.com")]).unwrap(), Framing::None);
+        assert_eq!(framing([("Content-Length", "12")]).unwrap(), Framing::Length(12));
+        assert_eq!(framing([("content-length", "5, 5"), ("content-length", "5")]).unwrap(), Framing::Length(5));
+        assert_eq!(framing([("Transfer-Encoding", "Chunked")]).unwrap(), Framing::Chunked);
+    }
 
-                assert_eq!(body.content_type.to_string(), "application/pdf");
+    #[test]
+    fn ambiguous_framing_is_rejected() {
+        for fields in [
+            vec![("content-length", "5"), ("transfer-encoding", "chunked")],
+            vec![("transfer-encoding", "chunked"), ("content-length", "0")],
+            vec![("content-length", "5"), ("content-length", "6")],
+            vec![("content-length", "+5")],
+            vec![("content-length", "0x5")],
+            vec![("transfer-encoding", "chunked, identity")],
+            vec![("transfer-encoding", "chunked"), ("transfer-encoding", "chunked")],
+        ] {
+            assert!(matches!(framing(fields.clone()), Err(ParserError::InvalidFraming { .. })), "{fields:?}");
+        }
 
-                assert_eq!(body.content, "[description of changes]".as_bytes());
+        assert!(matches!(
+            framing([("transfer-encoding", "gzip, chunked")]),
+            Err(ParserError::UnsupportedEncoding { coding, .. }) if coding == "gzip"
+        ));
+    }
+
+    #[test]
+    fn decode_in_one_read() {
+        let mut decoder = ChunkedDecoder::new(100);
+        let mut body = Vec::new();
+
+        let read = decoder.decode(&[BODY, b"GET / HTTP/1.1"].concat(), Some(&mut body)).unwrap();
+
+        assert!(decoder.is_complete());
+        assert_eq!(read, BODY.len());
+        assert_eq!(body, b"chunked body parts");
+        assert_eq!(decoder.trailers(), [(String::from("expires"), String::from("never"))]);
+    }
+
+    #[test]
+    fn decode_split_at_any_byte() {
+        for split in 1..BODY.len() {
+            let mut decoder = ChunkedDecoder::new(100);
+            let mut body = Vec::new();
+
+            assert_eq!(decoder.decode(&BODY[..split], Some(&mut body)).unwrap(), split);
+            assert!(!decoder.is_complete(), "split at {split}");
+
+            assert_eq!(decoder.decode(&BODY[split..], Some(&mut body)).unwrap(), BODY.len() - split);
+            assert!(decoder.is_complete(), "split at {split}");
+            assert_eq!(body, b"chunked body parts", "split at {split}");
+        }
+    }
+
+    #[test]
+    fn malformed_chunks() {
+        for body in [
+            &b"x\r\nchunked\r\n0\r\n\r\n"[..],
+            b"\r\n",
+            b" 7\r\nchunked\r\n0\r\n\r\n",
+            b"7\nchunked\r\n0\r\n\r\n",
+            b"7\r\nchunked!\r\n0\r\n\r\n",
+            b"7;=value\r\nchunked\r\n0\r\n\r\n",
+            b"0\r\nno colon\r\n\r\n",
+        ] {
+            let err = ChunkedDecoder::new(100).decode(body, None).unwrap_err();
+
+            assert!(matches!(err, ParserError::InvalidChunk { .. }), "{}", String::from_utf8_lossy(body));
+        }
+    }
+
+    #[test]
+    fn limits_are_enforced_before_data_arrives() {
+        let mut decoder = ChunkedDecoder::new(10);
+        assert_eq!(decoder.decode(b"5\r\nhello\r\n", None).unwrap(), 10);
+        assert!(matches!(decoder.decode(b"6\r\n", None), Err(ParserError::BodyTooLarge { .. })));
+
+        let overflowing = ChunkedDecoder::new(usize::MAX).decode(b"1ffffffffffffffff\r\n", None);
+        assert!(matches!(overflowing, Err(ParserError::BodyTooLarge { .. })));
+
+        let extensions = [b"1".as_slice(), &b";a".repeat(4096)].concat();
+        assert!(matches!(ChunkedDecoder::new(10).decode(&extensions, None), Err(ParserError::BodyTooLarge { .. })));
+    }
+
+    #[test]
+    fn chunked_request() {
+        let request = format!(
+            "POST /upload HTTP/1.1\r\nhost: example.com\r\ncontent-type: text/plain\r\ntransfer-encoding: chunked\r\n\r\n{}",
+            std::str::from_utf8(BODY).unwrap()
+        );
+
+        let request = Request::from_str(&request).unwrap();
+
+        assert_eq!(request.method, Method::Post);
+        assert_eq!(request.path, "/upload");
+
+        let body = request.body.expect("Missing body");
+        assert_eq!(body.content_type.to_string(), "text/plain");
+        assert_eq!(body.content, b"chunked body parts");
+
+        assert_eq!(
+            request.headers,
+            Headers::from_iter([
+                ("host", "example.com"),
+                ("content-type", "text/plain"),
+                ("transfer-encoding", "chunked"),
+                ("expires", "never"),
+            ])
+        );
+    }
+
+    #[test]
+    fn trailers_dont_replace_headers() {
+        let request = "POST / HTTP/1.1\r\nhost: example.com\r\ntransfer-encoding: chunked\r\n\r\n0\r\nhost: evil.com\r\ncontent-length: 5\r\n\r\n";
+
+        let request = Request::from_str(request).unwrap();
+
+        assert_eq!(
+            request.headers,
+            Headers::from_iter([("host", "example.com"), ("transfer-encoding", "chunked")])
+        );
+
+        let body = request.body.expect("Missing body");
+        assert_eq!(body.content_type.to_string(), "application/octet-stream");
+        assert!(body.content.is_empty());
+    }
+
+    #[test]
+    fn unframed_or_truncated_bodies_are_rejected() {
+        for request in [
+            "POST / HTTP/1.1\r\ncontent-length: 5\r\ntransfer-encoding: chunked\r\n\r\n0\r\n\r\n",
+            "POST / HTTP/1.1\r\ntransfer-encoding: chunked\r\n\r\n5\r\nhel",
+        ] {
+            assert!(Request::from_str(request).is_err(), "{request}");
+        }
+    }
+}
+
+mod binary {
+    use crate::http::{method::Method, request::Request};
+
+    fn post_body(request: &[u8]) -> Vec<u8> {
+        let request = Request::try_from(request).unwrap();
+
+        assert_eq!(request.method, Method::Post);
+
+        request.body.expect("Missing body").content
+    }
+
+    #[test]
+    fn body_is_kept_byte_for_byte() {
+        // a PNG signature; neither valid UTF-8 nor free of line breaks
+        let content = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\n\r\n\n";
+        let request = [
+            format!("POST /upload HTTP/1.1\r\ncontent-type: image/png\r\ncontent-length: {}\r\n\r\n", content.len()).as_bytes(),
+            content,
+        ]
+        .concat();
+
+        assert_eq!(post_body(&request), content);
+    }
+
+    #[test]
+    fn chunked_body_is_kept_byte_for_byte() {
+        let request = b"POST / HTTP/1.1\r\ntransfer-encoding: chunked\r\n\r\n4\r\n\xff\r\n\0\r\n2\r\n\n\n\r\n0\r\n\r\n";
+
+        assert_eq!(post_body(request), b"\xff\r\n\0\n\n");
+    }
+
+    #[test]
+    fn header_values_are_latin1() {
+        let request = b"GET / HTTP/1.1\r\nhost: example.com\r\nx-name: caf\xe9\r\n\r\n";
+
+        let request = Request::try_from(&request[..]).unwrap();
+
+        assert_eq!(request.headers.get("x-name"), Some("caf\u{e9}"));
+    }
+}
+
+mod model {
+    use crate::http::{
+        headers::Headers,
+        method::Method,
+        request::{Query, Request},
+        version::Version,
+    };
+
+    #[test]
+    fn start_line_is_kept() {
+        let request = Request::try_from(&b"GET /docs/index.html HTTP/1.0\r\n\r\n"[..]).unwrap();
+
+        assert_eq!(request.method, Method::Get);
+        assert_eq!(request.target, "/docs/index.html");
+        assert_eq!(request.path, "/docs/index.html");
+        assert_eq!(request.version, Version::Http1_0);
+        assert!(request.query.is_empty());
+    }
+
+    #[test]
+    fn query_is_split_from_path() {
+        let request = Request::try_from(&b"GET /search%20results?q=rust+http&tag=a&tag=b%26c&flag#top HTTP/1.1\r\n\r\n"[..]).unwrap();
+
+        // the target is kept as sent; the path & query are decoded
+        assert_eq!(request.target, "/search%20results?q=rust+http&tag=a&tag=b%26c&flag#top");
+        assert_eq!(request.path, "/search results");
+
+        assert_eq!(request.query.get("q"), Some("rust http"));
+        assert_eq!(request.query.get_all("tag").collect::<Vec<_>>(), ["a", "b&c"]);
+        assert_eq!(request.query.get("flag"), Some(""));
+        assert!(!request.query.contains("top"));
+    }
+
+    #[test]
+    fn query_decoding() {
+        let query = Query::parse("a%3Db=c%2Bd&&%E2%9C%93=%zz&=empty");
+
+        assert_eq!(
+            query.iter().collect::<Vec<_>>(),
+            [("a=b", "c+d"), ("\u{2713}", "%zz"), ("", "empty")]
+        );
+    }
+
+    #[test]
+    fn absolute_form_target() {
+        let request = Request::new(Method::Get, "http://example.com:8080/a/b?c=d", Version::Http1_1);
+
+        assert_eq!(request.path, "/a/b");
+        assert_eq!(request.query.get("c"), Some("d"));
+
+        assert_eq!(Request::new(Method::Get, "https://example.com", Version::Http1_1).path, "/");
+    }
+
+    #[test]
+    fn repeated_headers_are_kept() {
+        let request = Request::try_from(
+            &b"GET / HTTP/1.1\r\nHost: example.com\r\nCookie: a=1\r\nAccept: text/html\r\ncookie: b=2\r\nACCEPT: application/json\r\n\r\n"[..],
+        )
+        .unwrap();
+
+        assert_eq!(request.headers.get("HOST"), Some("example.com"));
+        assert_eq!(request.headers.get_all("cookie").collect::<Vec<_>>(), ["a=1", "b=2"]);
+        assert_eq!(request.headers.get_all("Accept").collect::<Vec<_>>(), ["text/html", "application/json"]);
+        assert_eq!(request.headers.len(), 5);
+    }
+
+    #[test]
+    fn headers_insert_and_remove() {
+        let mut headers = Headers::from_iter([("Cookie", "a=1"), ("cookie", "b=2"), ("Host", "example.com")]);
+
+        headers.insert("COOKIE", "c=3");
+        assert_eq!(headers.get_all("cookie").collect::<Vec<_>>(), ["c=3"]);
+
+        assert_eq!(headers.remove("host"), ["example.com"]);
+        assert!(!headers.contains("host"));
+        assert!(headers.remove("host").is_empty());
+
+        assert_eq!(headers.to_string(), "cookie: c=3\r\n");
+    }
+
+    #[test]
+    fn methods_without_body() {
+        for method in ["GET", "HEAD", "DELETE", "OPTIONS", "TRACE"] {
+            assert!(!Method::new(method).unwrap().requires_body(), "{method}");
+        }
+
+        for method in ["POST", "PUT", "PATCH"] {
+            let request = format!("{method} / HTTP/1.1\r\n\r\n");
+
+            assert!(Method::new(method).unwrap().requires_body(), "{method}");
+            assert!(Request::try_from(request.as_bytes()).is_err(), "{method}");
+        }
+
+        assert!(Method::new("BREW").is_err());
+    }
+}
+
+mod field {
+    use crate::http::request::{
+        field::{field, fields, FieldLimits},
+        parser_error::ParserError,
+        Request,
+    };
+
+    fn parse(head: &str, limits: &FieldLimits) -> Result<Request, ParserError> {
+        Request::parse(format!("GET / HTTP/1.1\r\n{head}\r\n").as_bytes(), limits)
+    }
+
+    #[test]
+    fn values_may_contain_colons() {
+        let request = parse("Host: localhost:8080\r\nReferer: http://a/b\r\nx-empty:\r\n", &FieldLimits::default()).unwrap();
+
+        assert_eq!(request.headers.get("host"), Some("localhost:8080"));
+        assert_eq!(request.headers.get("referer"), Some("http://a/b"));
+        assert_eq!(request.headers.get("x-empty"), Some(""));
+    }
+
+    #[test]
+    fn whitespace_around_value_is_stripped() {
+        assert_eq!(field("x-name: \t value with  spaces \t").unwrap(), ("x-name", "value with  spaces"));
+        assert_eq!(field("x-tab:\tinner\ttab").unwrap(), ("x-tab", "inner\ttab"));
+    }
+
+    #[test]
+    fn malformed_fields_are_rejected() {
+        for line in [
+            "no colon",
+            ": no name",
+            "host : whitespace before colon",
+            "x name: space in name",
+            "x(name): delimiter in name",
+            "x-name: bare\rcarriage return",
+            "x-name: nul\0byte",
+            "x-name: delete\x7f",
+        ] {
+            assert!(matches!(field(line), Err(ParserError::InvalidHeader { .. })), "{line:?}");
+        }
+    }
+
+    #[test]
+    fn obs_fold_is_rejected() {
+        for head in ["x-folded: first\r\n second\r\n", "x-folded: first\r\n\tsecond\r\n", " host: localhost\r\n"] {
+            assert!(
+                matches!(parse(head, &FieldLimits::default()), Err(ParserError::InvalidHeader { .. })),
+                "{head:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn obs_fold_can_be_unfolded() {
+        let limits = FieldLimits::default().set_unfold(true);
+
+        let request = parse("x-folded: first  \r\n   second\r\n\tthird\r\nhost: localhost\r\n", &limits).unwrap();
+
+        assert_eq!(request.headers.get("x-folded"), Some("first second third"));
+        assert_eq!(request.headers.get("host"), Some("localhost"));
+
+        // there is no field to continue
+        assert!(parse(" host: localhost\r\n", &limits).is_err());
+    }
+
+    #[test]
+    fn field_count_limit() {
+        let limits = FieldLimits::default().set_max_fields(3);
+
+        assert_eq!(fields(["a: 1", "b: 2", "c: 3"], &limits).unwrap().len(), 3);
+        assert!(matches!(fields(["a: 1", "b: 2", "c: 3", "d: 4"], &limits), Err(ParserError::HeaderFieldsTooLarge { .. })));
+
+        // fields after the end of the section aren't counted
+        assert_eq!(fields(["a: 1", "b: 2", "c: 3", "", "d: 4"], &limits).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn field_size_limit() {
+        let limits = FieldLimits::default().set_max_field_size(16).set_unfold(true);
+
+        assert!(fields(["x-name: 12345678"], &limits).is_ok());
+        assert!(matches!(fields(["x-name: 123456789"], &limits), Err(ParserError::HeaderFieldsTooLarge { .. })));
+
+        // folded lines count towards the field they continue
+        assert!(matches!(fields(["x-name: 1234", " 56789"], &limits), Err(ParserError::HeaderFieldsTooLarge { .. })));
+    }
+
+    #[test]
+    fn default_limits() {
+        let head: String = (0..100).map(|index| format!("x-{index}: value\r\n")).collect();
+        assert!(parse(&head, &FieldLimits::default()).is_ok());
+
+        let head = format!("{head}x-100: value\r\n");
+        assert!(matches!(parse(&head, &FieldLimits::default()), Err(ParserError::HeaderFieldsTooLarge { .. })));
+
+        let head = format!("x-long: {}\r\n", "a".repeat(8192));
+        assert!(matches!(parse(&head, &FieldLimits::default()), Err(ParserError::HeaderFieldsTooLarge { .. })));
+    }
+}
+
+mod errors {
+    use crate::http::request::{parser_error::ParserError, Request, MAX_TARGET_SIZE};
+
+    fn parse(request: &str) -> ParserError {
+        Request::try_from(request.as_bytes()).unwrap_err()
+    }
+
+    /// at returns the offset of the first occurrence of part in request
+    fn at(request: &str, part: &str) -> usize {
+        request.find(part).unwrap()
+    }
+
+    #[test]
+    fn invalid_start_line() {
+        for (request, offset) in [
+            ("", 0),
+            ("\r\nhost: localhost\r\n\r\n", 0),
+            ("GET\r\n\r\n", 3),
+            ("GET /index.html \r\n\r\n", 15),
+            ("GET / HTTP/1.1 extra\r\n\r\n", 15),
+            ("OPTIONS https://example.org -i", 28),
+            ("GET / http/1.1\r\n\r\n", 6),
+            ("GET / HTTP/11\r\n\r\n", 6),
+        ] {
+            match parse(request) {
+                ParserError::InvalidStartLine { offset: at, .. } => assert_eq!(at, offset, "{request:?}"),
+                err => panic!("{request:?}: {err:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_method() {
+        assert_eq!(
+            parse("BREW /pot HTTP/1.1\r\n\r\n"),
+            ParserError::UnknownMethod {
+                offset: 0,
+                method: String::from("BREW")
             }
-            _ => {
-                panic!("Incorrect variant. Got {} instead", method.to_string());
+        );
+
+        // offsets are counted before leading whitespace is skipped
+        assert_eq!(parse("  BREW /pot HTTP/1.1\r\n\r\n").offset(), 2);
+    }
+
+    #[test]
+    fn target_too_long() {
+        let target = format!("/{}", "a".repeat(MAX_TARGET_SIZE));
+        let request = format!("GET {target} HTTP/1.1\r\n\r\n");
+
+        assert_eq!(
+            parse(&request),
+            ParserError::TargetTooLong {
+                offset: 4,
+                length: MAX_TARGET_SIZE + 1
             }
+        );
+
+        let request = format!("GET {} HTTP/1.1\r\n\r\n", &target[..MAX_TARGET_SIZE]);
+        assert!(Request::try_from(request.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn unsupported_version() {
+        for version in ["HTTP/2.0", "HTTP/0.9", "HTTP/1.2"] {
+            let request = format!("GET / {version}\r\n\r\n");
+
+            assert_eq!(
+                parse(&request),
+                ParserError::UnsupportedVersion {
+                    offset: 6,
+                    version: version.to_string()
+                }
+            );
         }
     }
+
+    #[test]
+    fn invalid_header() {
+        let request = "GET / HTTP/1.1\r\nhost: localhost\r\nbad line\r\n\r\n";
+        assert_eq!(parse(request).offset(), at(request, "bad line"));
+
+        let request = "GET / HTTP/1.1\r\nhost: localhost\r\nx-name:  a\0b\r\n\r\n";
+        assert_eq!(parse(request).offset(), at(request, "\0"));
+
+        let request = "GET / HTTP/1.1\r\nhost: localhost\r\nx name: a\r\n\r\n";
+        assert_eq!(parse(request).offset(), at(request, " name"));
+
+        // offsets count bytes; not the UTF-8 encoding of the decoded head
+        let request = b"GET / HTTP/1.1\r\nx-name: caf\xe9\r\nbad line\r\n\r\n";
+        let bad_line = request.windows(8).position(|window| window == b"bad line").unwrap();
+        assert_eq!(Request::try_from(&request[..]).unwrap_err().offset(), bad_line);
+    }
+
+    #[test]
+    fn header_fields_too_large() {
+        let fields: String = (0..101).map(|index| format!("x-{index}: value\r\n")).collect();
+        let request = format!("GET / HTTP/1.1\r\n{fields}\r\n");
+
+        assert_eq!(
+            parse(&request),
+            ParserError::HeaderFieldsTooLarge {
+                offset: at(&request, "x-100")
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_content_type() {
+        let request = "POST / HTTP/1.1\r\nhost: localhost\r\nContent-Type: text/nope\r\ncontent-length: 1\r\n\r\nx";
+
+        match parse(request) {
+            ParserError::InvalidContentType { offset, .. } => assert_eq!(offset, at(request, "Content-Type")),
+            err => panic!("{err:?}"),
+        }
+    }
+
+    #[test]
+    fn body_length_mismatch() {
+        let request = "POST / HTTP/1.1\r\ncontent-length: 5\r\n\r\nabc";
+
+        assert_eq!(
+            parse(request),
+            ParserError::BodyLengthMismatch {
+                offset: request.len(),
+                expected: 5,
+                received: 3
+            }
+        );
+    }
+
+    #[test]
+    fn missing_body() {
+        let request = "POST / HTTP/1.1\r\nhost: localhost\r\n\r\n";
+
+        assert_eq!(parse(request), ParserError::MissingBody { offset: request.len() });
+    }
+
+    #[test]
+    fn framing_and_chunk_errors() {
+        let request = "POST / HTTP/1.1\r\ncontent-length: 1\r\ncontent-length: 2\r\n\r\nab";
+        match parse(request) {
+            ParserError::InvalidFraming { offset, .. } => assert_eq!(offset, at(request, "content-length")),
+            err => panic!("{err:?}"),
+        }
+
+        let request = "POST / HTTP/1.1\r\ntransfer-encoding: chunked\r\n\r\n1\r\nx\r\nz\r\n0\r\n\r\n";
+        match parse(request) {
+            ParserError::InvalidChunk { offset, .. } => assert_eq!(offset, at(request, "z\r\n") + 2),
+            err => panic!("{err:?}"),
+        }
+    }
+
+    #[test]
+    fn display_has_offset() {
+        assert_eq!(parse("BREW / HTTP/1.1").to_string(), "Unknown method: BREW at byte 0");
+        assert_eq!(parse("GET / HTTP/2.0").to_string(), "Unsupported version: HTTP/2.0 at byte 6");
+    }
 }
@@ -1,10 +1,10 @@
 //! response module is responsible for all structs, enums and methods related to formation of a HTTP response
 
-use std::{collections::HashMap, fmt::Display, str};
+use std::{collections::HashMap, fmt::Display};
 
 use self::response_status_code::ResponseStatusCode;
 
-use super::body::Body;
+use super::{body::Body, version::Version};
 pub mod response_status_code;
 
 #[cfg(test)]
@@ -32,36 +32,65 @@ macro_rules! append_to {
 }
 
 impl Response {
-    /// as_bytes provides a bytes required in order send response
+    /// as_bytes provides a bytes required in order send response; as an HTTP/1.1 response
     pub fn as_bytes(&self) -> Vec<u8> {
+        self.as_bytes_for(&Version::Http1_1)
+    }
+
+    /// as_bytes_for provides the bytes of the response to a request made with version
+    ///
+    /// A HTTP/1.0 request is answered with a HTTP/1.0 response; any other version with a HTTP/1.1 one, as the highest version the server speaks.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::HashMap;
+    /// use pipelined_server::http::{
+    ///     response::{response_status_code::ResponseStatusCode, Response},
+    ///     version::Version,
+    /// };
+    ///
+    /// let response = Response {
+    ///     status: ResponseStatusCode::Ok,
+    ///     header: HashMap::new(),
+    ///     body: None,
+    /// };
+    ///
+    /// assert_eq!(response.as_bytes_for(&Version::Http1_0), b"HTTP/1.0 200 Ok\r\n\r\n");
+    /// ```
+    pub fn as_bytes_for(&self, version: &Version) -> Vec<u8> {
         let mut output: Vec<u8> = Vec::new();
 
-        append_to!(output, format!("HTTP/1.1 {}\r\n", self.status.to_string()));
+        let version = match version {
+            Version::Http1_0 => Version::Http1_0,
+            _ => Version::Http1_1,
+        };
+
+        append_to!(output, format!("{} {}\r\n", version, self.status.to_string()));
 
-        self.header.keys().into_iter().for_each(|key| {
+        self.header.keys().for_each(|key| {
             append_to!(
                 output,
                 format!("{}: {}\r\n", key, &self.header.get(key).unwrap())
             );
         });
 
-        match &self.body {
-            Some(body) => {
-                append_to!(
-                    output,
-                    format!("Content-Length: {}\r\n", body.content.len())
-                );
+        if let Some(body) = &self.body {
+            append_to!(
+                output,
+                format!("Content-Length: {}\r\n", body.content.len())
+            );
 
-                append_to!(
-                    output,
-                    format!("Content-Type: {}\r\n", body.content_type.to_string())
-                );
+            append_to!(
+                output,
+                format!("Content-Type: {}\r\n", body.content_type.to_string())
+            );
+        }
 
-                append_to!(output, "\r\n");
+        // the header section is ended even without a body; so a kept-alive connection can tell where the response ends
+        append_to!(output, "\r\n");
 
-                output.append(&mut body.content.clone());
-            }
-            None => {}
+        if let Some(body) = &self.body {
+            output.append(&mut body.content.clone());
         }
 
         output
@@ -69,30 +98,8 @@ impl Response {
 }
 
 impl Display for Response {
+    /// fmt writes the response as it's sent to a HTTP/1.1 request; see [as_bytes](Response::as_bytes)
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut output: String = String::new();
-
-        output.push_str(&format!("HTTP/1.1 {}\r\n", self.status.to_string()));
-
-        self.header.keys().into_iter().for_each(|key| {
-            output.push_str(&format!("{}: {}\r\n", key, &self.header.get(key).unwrap()));
-        });
-
-        match &self.body {
-            Some(body) => {
-                output.push_str(&format!("Content-Length: {}\r\n", body.content.len()));
-                output.push_str(&format!(
-                    "Content-Type: {}\r\n",
-                    body.content_type.to_string()
-                ));
-
-                output.push_str(&format!("\r\n"));
-
-                output.push_str(&str::from_utf8(&body.content).unwrap());
-            }
-            None => {}
-        }
-
-        write!(f, "{}", output)
+        write!(f, "{}", String::from_utf8_lossy(&self.as_bytes()))
     }
 }
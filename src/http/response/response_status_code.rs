@@ -79,7 +79,7 @@ pub enum ResponseStatusCode {
 impl ResponseStatusCode {
     /// get_code method gets the code number of enum variant
     pub fn get_code(&self) -> u16 {
-        self.clone() as u16
+        *self as u16
     }
 }
 
@@ -35,7 +35,7 @@ mod response {
             body: None,
         };
 
-        let output: Vec<u8> = format!("HTTP/1.1 {}\r\n", ResponseStatusCode::Ok.to_string())
+        let output: Vec<u8> = format!("HTTP/1.1 {}\r\n\r\n", ResponseStatusCode::Ok)
             .as_bytes()
             .to_vec();
 
@@ -55,9 +55,9 @@ mod response {
 
         let output: Vec<u8> = format!(
             "HTTP/1.1 {}\r\nContent-Length: {}\r\nContent-Type: {}\r\n\n{}",
-            ResponseStatusCode::Ok.to_string(),
-            "<html></html>".as_bytes().len(),
-            ContentType::Text(Text::html).to_string(),
+            ResponseStatusCode::Ok,
+            "<html></html>".len(),
+            ContentType::Text(Text::html),
             "<html></html>"
         )
         .as_bytes()
@@ -78,12 +78,34 @@ mod response {
         };
 
         let output:Vec<u8> = format!("HTTP/1.1 {}\r\nCache-Control: private\r\nContent-Length: {}\r\nContent-Type: {}\r\n\n{}",
-            ResponseStatusCode::Ok.to_string(),
-            "<html></html>".as_bytes().len(),
-            ContentType::Text(Text::html).to_string(),
+            ResponseStatusCode::Ok,
+            "<html></html>".len(),
+            ContentType::Text(Text::html),
             "<html></html>"
         ).as_bytes().to_vec();
 
         assert_eq!(response.as_bytes(), output);
     }
 }
+
+mod version {
+    use std::collections::HashMap;
+
+    use super::super::super::version::Version;
+    use super::super::response_status_code::ResponseStatusCode;
+    use super::super::Response;
+
+    #[test]
+    fn response_is_written_in_request_version() {
+        let response = Response {
+            status: ResponseStatusCode::Ok,
+            header: HashMap::new(),
+            body: None,
+        };
+
+        assert_eq!(response.as_bytes_for(&Version::Http1_0), b"HTTP/1.0 200 Ok\r\n\r\n");
+        assert_eq!(response.as_bytes_for(&Version::Http1_1), b"HTTP/1.1 200 Ok\r\n\r\n");
+        assert_eq!(response.as_bytes_for(&Version::Unknown(String::from("HTTP/2.0"))), b"HTTP/1.1 200 Ok\r\n\r\n");
+        assert_eq!(response.as_bytes(), response.as_bytes_for(&Version::Http1_1));
+    }
+}
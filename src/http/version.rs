@@ -0,0 +1,72 @@
+//! version module defines the HTTP versions a request can be made with
+use std::fmt::Display;
+
+use super::request::parser_error::ParserError;
+
+/// Version enum defines the HTTP version of a request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Version {
+    Http1_0,
+    Http1_1,
+    /// version token that is not understood by the server
+    Unknown(String),
+}
+
+impl Version {
+    /// keep_alive returns true if connections are persistent by default for the version
+    pub fn keep_alive(&self) -> bool {
+        matches!(self, Version::Http1_1)
+    }
+
+    /// parse reads the version of a start line
+    ///
+    /// # Errors
+    /// - InvalidStartLine if version isn't `HTTP/` followed by a digit, a dot & a digit
+    /// - UnsupportedVersion if it is, but isn't HTTP/1.0 or HTTP/1.1
+    ///
+    /// # Example
+    /// ```
+    /// use pipelined_server::http::version::Version;
+    ///
+    /// assert_eq!(Version::parse("HTTP/1.1").unwrap(), Version::Http1_1);
+    /// assert!(Version::parse("HTTP/2.0").is_err());
+    /// ```
+    pub fn parse(version: &str) -> Result<Version, ParserError> {
+        let well_formed = match version.strip_prefix("HTTP/").map(str::as_bytes) {
+            Some([major, b'.', minor]) => major.is_ascii_digit() && minor.is_ascii_digit(),
+            _ => false,
+        };
+
+        if !well_formed {
+            return Err(ParserError::InvalidStartLine {
+                offset: 0,
+                reason: format!("Invalid HTTP version: {version}"),
+            });
+        }
+
+        match Version::from(version) {
+            Version::Unknown(version) => Err(ParserError::UnsupportedVersion { offset: 0, version }),
+            version => Ok(version),
+        }
+    }
+}
+
+impl From<&str> for Version {
+    fn from(version: &str) -> Self {
+        match version {
+            "HTTP/1.0" => Version::Http1_0,
+            "HTTP/1.1" => Version::Http1_1,
+            _ => Version::Unknown(version.to_string()),
+        }
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Version::Http1_0 => write!(f, "HTTP/1.0"),
+            Version::Http1_1 => write!(f, "HTTP/1.1"),
+            Version::Unknown(version) => write!(f, "{version}"),
+        }
+    }
+}
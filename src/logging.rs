@@ -1,11 +1,12 @@
 use log::LevelFilter;
 use log4rs::{
-    append::{console::ConsoleAppender, file::FileAppender},
+    append::console::ConsoleAppender,
     config::{Appender, Root},
-    encode::pattern::PatternEncoder,
     Config, Handle,
 };
 
+// used by the file appender; while it is disabled
+#[allow(dead_code)]
 const LOG_FILE_TAG: &str = "log_file";
 const STD_OUT: &str = "stdout";
 
@@ -28,5 +29,5 @@ pub fn logger_init() -> Handle {
         )
         .unwrap();
 
-    return log4rs::init_config(config).unwrap();
+    log4rs::init_config(config).unwrap()
 }
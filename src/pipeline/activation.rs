@@ -0,0 +1,151 @@
+//! activation module takes the listening sockets passed to the process by a supervisor, e.g. systemd socket activation, or by an upgrading server; and hands them over on upgrade
+use std::{
+    env, io,
+    ops::Range,
+    os::{
+        fd::{AsFd, AsRawFd, FromRawFd, OwnedFd, RawFd},
+        unix::process::{parent_id, CommandExt},
+    },
+    process::{self, Child, Command},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use super::listener::{Listener, Socket};
+
+/// first file descriptor passed through the LISTEN_FDS protocol; following stdin, stdout & stderr
+const LISTEN_FDS_START: RawFd = 3;
+
+/// variable naming the upgrading server that passed its sockets; set in place of `LISTEN_PID` as the pid of the new process isn't known before it is started
+pub const UPGRADE_PID: &str = "PIPELINED_UPGRADE_PID";
+
+/// set once the passed sockets were taken by [listen_fds]; so they aren't owned twice
+static TAKEN: AtomicBool = AtomicBool::new(false);
+
+/// listen_fds takes the sockets passed to the process through the `LISTEN_FDS` & `LISTEN_PID` protocol, or by an upgrading server; in the order they were passed
+///
+/// Sockets passed by a supervisor are announced by `LISTEN_PID` naming the process; sockets handed over by [upgrade](super::ShutdownHandle::upgrade) by [UPGRADE_PID] naming its parent. The sockets are only taken by the first call; later calls return none. The environment is left as is, as other threads may be reading it; child processes inheriting the variables ignore them as they name another process, and [upgrade](super::ShutdownHandle::upgrade) replaces them for the process it starts. No sockets are returned if the variables aren't set, or were set for another process.
+///
+/// # Errors
+/// An io::Error of kind InvalidData is returned if the variables are malformed
+///
+/// # Examples
+/// ```ignore
+/// let server = Server::new(settings, utility_thread, builder).set_inherited(listen_fds()?);
+/// ```
+pub fn listen_fds() -> io::Result<Vec<OwnedFd>> {
+    if TAKEN.swap(true, Ordering::SeqCst) {
+        return Ok(Vec::new());
+    }
+
+    let fds = passed_fds(
+        env::var("LISTEN_PID").ok().as_deref(),
+        env::var(UPGRADE_PID).ok().as_deref(),
+        env::var("LISTEN_FDS").ok().as_deref(),
+        (process::id(), parent_id()),
+    )?;
+
+    fds.map(|fd| {
+        // SAFETY: the protocol hands the descriptors to this process; TAKEN makes sure they are only taken once
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        // passed descriptors are open across exec; they are only passed on explicitly
+        socket2::SockRef::from(&fd).set_cloexec(true)?;
+
+        Ok(fd)
+    })
+    .collect()
+}
+
+/// passed_fds reads the descriptors passed to the process with (pid, parent pid) ids from the values of `LISTEN_PID`, [UPGRADE_PID] & `LISTEN_FDS`
+///
+/// # Errors
+/// An io::Error of kind InvalidData is returned if a value isn't a number
+pub(super) fn passed_fds(
+    listen_pid: Option<&str>,
+    upgrade_pid: Option<&str>,
+    listen_fds: Option<&str>,
+    (pid, parent): (u32, u32),
+) -> io::Result<Range<RawFd>> {
+    let (variable, owner, expected, listen_fds) = match (listen_pid, upgrade_pid, listen_fds) {
+        (Some(listen_pid), _, Some(listen_fds)) => ("LISTEN_PID", listen_pid, pid, listen_fds),
+        (None, Some(upgrade_pid), Some(listen_fds)) => (UPGRADE_PID, upgrade_pid, parent, listen_fds),
+        _ => return Ok(LISTEN_FDS_START..LISTEN_FDS_START),
+    };
+
+    let invalid = |variable: &str, value: &str| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("{variable} is not a number: {value}"))
+    };
+
+    let owner: u32 = owner.trim().parse().map_err(|_| invalid(variable, owner))?;
+
+    // the variables were inherited from the parent the sockets were passed to
+    if owner != expected {
+        return Ok(LISTEN_FDS_START..LISTEN_FDS_START);
+    }
+
+    let count: RawFd = listen_fds.trim().parse().map_err(|_| invalid("LISTEN_FDS", listen_fds))?;
+
+    Ok(LISTEN_FDS_START..LISTEN_FDS_START.saturating_add(count.max(0)))
+}
+
+/// handoff starts command with the sockets passed as the descriptors following stderr, in order; announced through `LISTEN_FDS` & [UPGRADE_PID]
+///
+/// The variables this process was started with are replaced for command only; the environment of this process isn't changed.
+///
+/// # Errors
+/// Any io::Error returned while copying the sockets or starting command is returned
+pub(super) fn handoff(mut command: Command, sockets: &[(Socket, Arc<Listener>)]) -> io::Result<Child> {
+    let count = RawFd::try_from(sockets.len()).map_err(io::Error::other)?;
+
+    // copied above the descriptors they are passed as; so no socket is overwritten before it is passed
+    let copies = sockets
+        .iter()
+        .map(|(socket, _)| copy_above(socket, LISTEN_FDS_START + count))
+        .collect::<io::Result<Vec<OwnedFd>>>()?;
+
+    let raw: Vec<RawFd> = copies.iter().map(AsRawFd::as_raw_fd).collect();
+
+    // LISTEN_PID would take precedence over UPGRADE_PID, so the supervisor's variables aren't passed on
+    command
+        .env("LISTEN_FDS", count.to_string())
+        .env(UPGRADE_PID, process::id().to_string())
+        .env_remove("LISTEN_PID")
+        .env_remove("LISTEN_FDNAMES");
+
+    // SAFETY: only dup2 is called between fork & exec; which is async-signal-safe & doesn't allocate
+    unsafe {
+        command.pre_exec(move || {
+            for (target, fd) in (LISTEN_FDS_START..).zip(&raw) {
+                // the copy is closed on exec; the descriptor it is duplicated to is kept open
+                if libc::dup2(*fd, target) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+
+            Ok(())
+        });
+    }
+
+    let child = command.spawn();
+
+    // the copies are closed once the new process has started; the sockets are left open in the server
+    drop(copies);
+
+    child
+}
+
+/// copy_above duplicates fd onto the lowest free descriptor from min; closed on exec
+fn copy_above<F: AsFd>(fd: &F, min: RawFd) -> io::Result<OwnedFd> {
+    // SAFETY: fcntl only reads fd, which is kept open by the borrow
+    let copy = unsafe { libc::fcntl(fd.as_fd().as_raw_fd(), libc::F_DUPFD_CLOEXEC, min) };
+
+    if copy == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // SAFETY: the copy is a new descriptor; owned by nothing else
+    Ok(unsafe { OwnedFd::from_raw_fd(copy) })
+}
@@ -23,40 +23,40 @@ macro_rules! ActionBuilder {
             pub fn [<$name>] (request: &Result<Request, ResponseStatusCode>, setting: &ServerSetting, utility_thread: &mut $utility) -> Result<Response, ResponseStatusCode> {
                 match request{
                     Ok(request) => {
-                        match &request.0 {
-                            Method::Get { .. } => {
+                        match request.method {
+                            Method::Get => {
                                 trace!("Get:{request:#?}");
                                 $get(request, &setting, utility_thread)
                             },
-                            Method::Head { .. } => {
+                            Method::Head => {
                                 trace!("Head:{request:#?}");
                                 $head(request, &setting, utility_thread)
                             },
-                            Method::Post { .. } => {
+                            Method::Post => {
                                 trace!("Post:{request:#?}");
                                 $post(request, &setting, utility_thread)
                             },
-                            Method::Put { .. } => {
+                            Method::Put => {
                                 trace!("Put:{request:#?}");
                                 $put(request, &setting, utility_thread)
                             },
-                            Method::Delete { .. } => {
+                            Method::Delete => {
                                 trace!("Delete:{request:#?}");
                                 $delete(request, &setting, utility_thread)
                             },
-                            Method::Connect { .. } => {
+                            Method::Connect => {
                                 trace!("Connect:{request:#?}");
                                 $connect(request, &setting, utility_thread)
                             },
-                            Method::Options { .. } => {
+                            Method::Options => {
                                 trace!("Options:{request:#?}");
                                 $options(request, &setting, utility_thread)
                             },
-                            Method::Trace { .. } => {
+                            Method::Trace => {
                                 trace!("Trace:{request:#?}");
                                 $trace(request, &setting, utility_thread)
                             },
-                            Method::Patch { .. } => {
+                            Method::Patch => {
                                 trace!("Patch:{request:#?}");
                                 $patch(request, &setting, utility_thread)
                             },
@@ -1,67 +1,73 @@
 use std::{
     io::Write,
-    net::TcpStream,
     sync::{
+        atomic::{AtomicUsize, Ordering},
         mpsc::{self, Sender, Receiver},
         Arc, Mutex, RwLock,
     },
     thread::{self, JoinHandle}, collections::HashMap,
+    time::{Duration, Instant},
 };
 
-use cyclic_data_types::{list::List, error::Error};
 use log::{error, trace};
 
-use crate::{
-    http::{
-        request::Request,
-        response::{response_status_code::ResponseStatusCode, Response},
-    },
-    setting::ServerSetting,
-};
+use crate::{http::response::Response, setting::ServerSetting};
 
 use super::super::{
-    component::Component,
+    component::{Component, Lifecycle},
+    error::BuildError,
+    keep_alive::{readiness, Connection, KeepAlive, Readiness},
+    metrics::{PipelineMetrics, WorkerMetrics},
+    overflow::{Overflow, OverflowPolicy},
+    queue::{BlockingQueue, PopError},
+    stage::{Action, Compressor, Parser, RequestStage, ResponseStage},
     pipeline::{
-        ActionComponent, ActionQueue, Bytes, CompressionComponent, CompressionQueue,
-        ParserComponent, Pipeline, SenderComponent, SenderQueue,
+        ActionComponent, ActionQueue, CompressionComponent, CompressionQueue, IdleComponent,
+        IdleQueue, ParserComponent, Pipeline, RequestQueue, RequestStageComponent, ResponseQueue,
+        ResponseStageComponent, SenderComponent, SenderQueue,
     },
 };
 
-type ParserFunc = fn(&mut TcpStream) -> Result<Request, ResponseStatusCode>;
-type ActionFunc<U> = fn(
-    &Result<Request, ResponseStatusCode>,
-    &ServerSetting,
-    utility_thread: &mut mpsc::Sender<U>,
-) -> Result<Response, ResponseStatusCode>;
-type CompressionFunc = fn(Response, Option<Request>, ServerSetting) -> Bytes;
+// how often idle connections are checked for a new request
+const IDLE_POLL: Duration = Duration::from_millis(10);
 
 
 #[derive(Clone)]
 pub struct Builder<U: Clone> {
-    pub parser: Option<ParserFunc>,
-    pub action: Option<ActionFunc<U>>,
-    pub compression: Option<CompressionFunc>,
+    pub parser: Option<Arc<dyn Parser>>,
+    pub action: Option<Arc<dyn Action<U>>>,
+    pub compression: Option<Arc<dyn Compressor>>,
     pub utility_sender: Option<mpsc::Sender<U>>,
     pub settings: Option<Arc<RwLock<ServerSetting>>>,
+    pub action_overflow: OverflowPolicy,
+    pub compression_overflow: OverflowPolicy,
+    pub sender_overflow: OverflowPolicy,
+    pub keep_alive: Option<KeepAlive>,
+    pub parser_workers: usize,
+    pub action_workers: usize,
+    pub compression_workers: usize,
+    pub sender_workers: usize,
+    pub request_stages: Vec<(String, Arc<dyn RequestStage>, usize)>,
+    pub response_stages: Vec<(String, Arc<dyn ResponseStage>, usize)>,
 }
 
 impl<U: Clone + Send + 'static> Builder<U> {
-    pub fn set_parser(mut self, new_func: ParserFunc) -> Self {
-        self.parser = Some(new_func);
+    pub fn set_parser<P: Parser>(mut self, parser: P) -> Self {
+        self.parser = Some(Arc::new(parser));
 
-        return self;
+        self
     }
 
-    pub fn set_action(mut self, new_func: ActionFunc<U>) -> Self {
-        self.action = Some(new_func);
+    pub fn set_action<A: Action<U>>(mut self, action: A) -> Self {
+        self.action = Some(Arc::new(action));
 
-        return self;
+        self
     }
 
-    pub fn set_compression(mut self, new_func: CompressionFunc) -> Self {
-        self.compression = Some(new_func);
+    pub fn set_compression<C: Compressor>(mut self, compressor: C) -> Self {
+        self.compression = Some(Arc::new(compressor));
 
-        return self;
+        self
     }
 
     pub fn set_utility_thread(mut self, sender: mpsc::Sender<U>) -> Self {
@@ -76,92 +82,280 @@ impl<U: Clone + Send + 'static> Builder<U> {
         self
     }
 
-    pub fn build(&self) -> (Sender<TcpStream>, Pipeline) {
+    /// set_action_overflow sets how the parser stage behaves when the action queue is full
+    pub fn set_action_overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.action_overflow = policy;
+
+        self
+    }
+
+    /// set_compression_overflow sets how the action stage behaves when the compression queue is full
+    pub fn set_compression_overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.compression_overflow = policy;
+
+        self
+    }
+
+    /// set_sender_overflow sets how the compression stage behaves when the sender queue is full
+    pub fn set_sender_overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.sender_overflow = policy;
+
+        self
+    }
+
+    /// set_keep_alive allows connections to be reused between requests
+    ///
+    /// Connections are closed after every response unless keep alive is set.
+    pub fn set_keep_alive(mut self, keep_alive: KeepAlive) -> Self {
+        self.keep_alive = Some(keep_alive);
+
+        self
+    }
+
+    /// set_parser_workers sets the number of threads parsing requests in each pipeline
+    pub fn set_parser_workers(mut self, workers: usize) -> Self {
+        self.parser_workers = workers;
+
+        self
+    }
+
+    /// set_action_workers sets the number of threads generating responses in each pipeline
+    pub fn set_action_workers(mut self, workers: usize) -> Self {
+        self.action_workers = workers;
+
+        self
+    }
+
+    /// set_compression_workers sets the number of threads compressing responses in each pipeline
+    pub fn set_compression_workers(mut self, workers: usize) -> Self {
+        self.compression_workers = workers;
+
+        self
+    }
+
+    /// set_sender_workers sets the number of threads writing responses in each pipeline
+    pub fn set_sender_workers(mut self, workers: usize) -> Self {
+        self.sender_workers = workers;
+
+        self
+    }
+
+    /// add_request_stage appends a stage, run by a single worker, between the parser and action stages
+    ///
+    /// Request stages are run in the order they are added.
+    pub fn add_request_stage<S: RequestStage>(self, name: &str, stage: S) -> Self {
+        self.add_request_stage_with_workers(name, 1, stage)
+    }
+
+    /// add_request_stage_with_workers appends a stage, run by the given number of workers, between the parser and action stages
+    pub fn add_request_stage_with_workers<S: RequestStage>(mut self, name: &str, workers: usize, stage: S) -> Self {
+        self.request_stages.push((name.to_string(), Arc::new(stage), workers));
+
+        self
+    }
+
+    /// add_response_stage appends a stage, run by a single worker, between the action and compression stages
+    ///
+    /// Response stages are run in the order they are added.
+    pub fn add_response_stage<S: ResponseStage>(self, name: &str, stage: S) -> Self {
+        self.add_response_stage_with_workers(name, 1, stage)
+    }
+
+    /// add_response_stage_with_workers appends a stage, run by the given number of workers, between the action and compression stages
+    pub fn add_response_stage_with_workers<S: ResponseStage>(mut self, name: &str, workers: usize, stage: S) -> Self {
+        self.response_stages.push((name.to_string(), Arc::new(stage), workers));
+
+        self
+    }
+
+    /// validate checks that every stage & resource required to build a pipeline has been set
+    ///
+    /// # Errors
+    /// A BuildError naming the first missing piece is returned
+    pub fn validate(&self) -> Result<(), BuildError> {
+        self.stages().map(|_| ())
+    }
+
+    /// build creates a pipeline & the channel used to send it connections
+    ///
+    /// The builder is validated before any component thread is spawned.
+    ///
+    /// # Errors
+    /// A BuildError is returned if a required stage or resource has not been set
+    pub fn build(&self) -> Result<(Sender<Connection>, Pipeline), BuildError> {
+        let stages = self.stages()?;
+
+        let metrics = Arc::new(PipelineMetrics {
+            stages: self
+                .request_stages
+                .iter()
+                .map(|(name, _, _)| name)
+                .chain(self.response_stages.iter().map(|(name, _, _)| name))
+                .map(|name| (name.clone(), Arc::default()))
+                .collect(),
+            workers: self
+                .worker_counts()
+                .into_iter()
+                .map(|(name, workers)| (name, (0..workers).map(|_| Arc::default()).collect()))
+                .collect(),
+            ..PipelineMetrics::default()
+        });
+        let (request_metrics, response_metrics) = metrics.stages.split_at(self.request_stages.len());
+
+        // handed out in the same back to front order the components are built in
+        let mut workers = metrics.workers.iter().rev().map(|(_, workers)| workers.clone());
+        let mut next_workers = || workers.next().expect("worker metrics are created for every stage");
+
+        let connections = Arc::new(AtomicUsize::new(0));
+
+        // the parser's channel is made first; idle connections are handed back to it
+        let (new_conn, rx) = mpsc::channel::<Connection>();
+
         //building components back to front to deal with input queue dependencies & ownership issues
-        let (sender_queue, sender) = build_sender_component();
+        let (idle_queue, idle) = build_idle_component(
+            new_conn.clone(),
+            connections.clone(),
+            self.idle_timeout(),
+        );
+
+        let (sender_queue, sender) = build_sender_component(idle_queue, next_workers());
 
         //build compressor
-        if self.compression.is_none() {
-            todo!()
-        }
         let (compressor_queue, compression) = build_compressor_component(
-            self.compression.unwrap(),
+            stages.compression,
             sender_queue,
-            &self.settings.clone().unwrap(),
+            Overflow::new(self.sender_overflow, metrics.sender.clone()),
+            &stages.settings,
+            next_workers(),
         );
 
-        //build action
-        if self.action.is_none() || self.utility_sender.is_none() || self.settings.is_none() {
-            todo!()
+        //build response stages; each pushing onto the queue of the stage after it
+        let mut output_queue = compressor_queue;
+        let mut output_metrics = metrics.compression.clone();
+        let mut response_stages = Vec::with_capacity(self.response_stages.len());
+
+        for ((name, stage, _), (_, stage_metrics)) in self.response_stages.iter().zip(response_metrics).rev() {
+            let (input_queue, component) = build_response_stage_component(
+                name,
+                stage.clone(),
+                output_queue,
+                Overflow::new(self.compression_overflow, output_metrics),
+                &stages.settings,
+                next_workers(),
+            );
+
+            response_stages.push(component);
+            output_queue = input_queue;
+            output_metrics = stage_metrics.clone();
         }
+        response_stages.reverse();
+
+        //build action
         let (action_queue, action) = build_action_component(
-            self.action.unwrap(),
-            compressor_queue,
-            self.utility_sender.clone().unwrap(),
-            &self.settings.clone().unwrap(),
+            stages.action,
+            output_queue,
+            Overflow::new(self.compression_overflow, output_metrics),
+            stages.utility_sender,
+            &stages.settings,
+            self.keep_alive,
+            next_workers(),
         );
 
-        //build parser
-        if self.parser.is_none() {
-            todo!()
+        //build request stages
+        let mut output_queue = action_queue;
+        let mut output_metrics = metrics.action.clone();
+        let mut request_stages = Vec::with_capacity(self.request_stages.len());
+
+        for ((name, stage, _), (_, stage_metrics)) in self.request_stages.iter().zip(request_metrics).rev() {
+            let (input_queue, component) = build_request_stage_component(
+                name,
+                stage.clone(),
+                output_queue,
+                Overflow::new(self.action_overflow, output_metrics),
+                &stages.settings,
+                next_workers(),
+            );
+
+            request_stages.push(component);
+            output_queue = input_queue;
+            output_metrics = stage_metrics.clone();
         }
-        let (new_conn, parser) = build_parser_component(self.parser.unwrap(), action_queue);
+        request_stages.reverse();
+
+        //build parser
+        let parser = build_parser_component(
+            stages.parser,
+            rx,
+            output_queue,
+            Overflow::new(self.action_overflow, output_metrics),
+            connections.clone(),
+            next_workers(),
+        );
 
         //construct pipeline
         let pipeline = Pipeline {
-            parser: parser,
-            action: action,
-            compression: compression,
-            sender: sender
+            parser,
+            request_stages,
+            action,
+            response_stages,
+            compression,
+            sender,
+            idle,
+            metrics,
+            connections,
         };
 
-        (new_conn, pipeline)
+        Ok((new_conn, pipeline))
     }
 
-    pub fn fix(&self, pipeline: &mut Pipeline) -> Result<(), ()> {
-        if !pipeline.sender.thread_state() {
-            error!("{pipeline} - Sender component panic");
-            let input_queue = pipeline.sender.input_queue.clone();
-
-            let new_thread = build_sender_thread(input_queue);
-
-            pipeline.sender.swap_out_thread(new_thread);
-        }
-
-        if !pipeline.compression.thread_state() {
-            error!("{pipeline} - Compression component panic");
-            let input_queue = pipeline.compression.input_queue.clone();
-            let output_queue = pipeline.sender.input_queue.clone();
-
-            let new_thread = build_compressor_thread(self.compression.unwrap(), input_queue, output_queue, self.settings.clone().unwrap());
+    /// fix replaces every worker thread in pipeline that has stopped; workers that are still running are left untouched
+    ///
+    /// Replacement threads are built from the same stages, queues, settings and worker metrics as the threads they replace.
+    pub fn fix(&self, pipeline: &mut Pipeline) {
+        pipeline.recover();
+    }
 
-            pipeline.compression.swap_out_thread(new_thread);
+    fn stages(&self) -> Result<Stages<U>, BuildError> {
+        if self.worker_counts().iter().any(|(_, workers)| *workers == 0) {
+            return Err(BuildError::NoWorkers);
         }
 
-        if !pipeline.action.thread_state() {
-            error!("{pipeline} - Action component panic");
-            let input_queue = pipeline.action.input_queue.clone();
-            let output_queue = pipeline.compression.input_queue.clone();
-
-            let new_thread = build_action_thread(self.action.unwrap(), input_queue, output_queue, self.utility_sender.clone().unwrap(), self.settings.clone().unwrap());
+        Ok(Stages {
+            parser: self.parser.clone().ok_or(BuildError::MissingParser)?,
+            action: self.action.clone().ok_or(BuildError::MissingAction)?,
+            compression: self.compression.clone().ok_or(BuildError::MissingCompression)?,
+            utility_sender: self.utility_sender.clone().ok_or(BuildError::MissingUtilitySender)?,
+            settings: self.settings.clone().ok_or(BuildError::MissingSettings)?,
+        })
+    }
 
-            pipeline.action.swap_out_thread(new_thread);
-        }
+    /// worker_counts returns the number of workers of each stage by name; in the order work flows through the pipeline
+    fn worker_counts(&self) -> Vec<(String, usize)> {
+        let mut counts = vec![(String::from("Parser"), self.parser_workers)];
 
-        if !pipeline.parser.thread_state() {
-            error!("{pipeline} - Parser component panic");
-            let input_queue = pipeline.parser.input_queue.clone();
-            let output_queue = pipeline.action.input_queue.clone();
+        counts.extend(self.request_stages.iter().map(|(name, _, workers)| (name.clone(), *workers)));
+        counts.push((String::from("Action"), self.action_workers));
+        counts.extend(self.response_stages.iter().map(|(name, _, workers)| (name.clone(), *workers)));
+        counts.push((String::from("Compression"), self.compression_workers));
+        counts.push((String::from("Sender"), self.sender_workers));
 
-            let new_thread = build_parser_thread(self.parser.unwrap(), input_queue, output_queue);
+        counts
+    }
 
-            pipeline.parser.swap_out_thread(new_thread);
-        }
-        
-        Ok(())
+    fn idle_timeout(&self) -> Duration {
+        self.keep_alive.unwrap_or_default().idle_timeout
     }
 }
 
+/// Stages holds the parts of a builder that are required to build a pipeline
+struct Stages<U> {
+    parser: Arc<dyn Parser>,
+    action: Arc<dyn Action<U>>,
+    compression: Arc<dyn Compressor>,
+    utility_sender: mpsc::Sender<U>,
+    settings: Arc<RwLock<ServerSetting>>,
+}
+
 impl<U: Clone + Send + 'static> Default for Builder<U> {
     fn default() -> Self {
         Self {
@@ -170,30 +364,44 @@ impl<U: Clone + Send + 'static> Default for Builder<U> {
             compression: None,
             utility_sender: None,
             settings: None,
+            action_overflow: OverflowPolicy::default(),
+            compression_overflow: OverflowPolicy::default(),
+            sender_overflow: OverflowPolicy::default(),
+            keep_alive: None,
+            parser_workers: 1,
+            action_workers: 1,
+            compression_workers: 1,
+            sender_workers: 1,
+            request_stages: Vec::new(),
+            response_stages: Vec::new(),
         }
     }
 }
 
 // look into generic implementations
 fn build_parser_component(
-    parser: ParserFunc,
-    output_queue: Arc<Mutex<ActionQueue>>,
-) -> (Sender<TcpStream>, ParserComponent) {
-    let (tx, rx) = mpsc::channel::<TcpStream>();
-
+    parser: Arc<dyn Parser>,
+    rx: Receiver<Connection>,
+    output_queue: Arc<RequestQueue>,
+    overflow: Overflow,
+    connections: Arc<AtomicUsize>,
+    workers: Vec<Arc<WorkerMetrics>>,
+) -> ParserComponent {
     let rx = Arc::new(Mutex::new(rx));
+    let input_queue = rx.clone();
 
-    let thread = build_parser_thread(parser, rx.clone(), output_queue);
-
-    let component = Component::new(rx, thread);
-
-    (tx, component)
+    Component::new("Parser", rx, Arc::new(Lifecycle::default()), workers, move |worker| {
+        build_parser_thread(parser.clone(), input_queue.clone(), output_queue.clone(), overflow.clone(), connections.clone(), worker)
+    })
 }
 
 fn build_parser_thread(
-    parser: ParserFunc,
-    input_queue: Arc<Mutex<Receiver<TcpStream>>>,
-    output_queue: Arc<Mutex<ActionQueue>>,
+    parser: Arc<dyn Parser>,
+    input_queue: Arc<Mutex<Receiver<Connection>>>,
+    output_queue: Arc<RequestQueue>,
+    overflow: Overflow,
+    connections: Arc<AtomicUsize>,
+    worker: Arc<WorkerMetrics>,
 ) -> JoinHandle<()> {
     thread::spawn(move || {
        // let rx = &*input_queue.lock().unwrap();
@@ -206,74 +414,91 @@ fn build_parser_thread(
                 rx.recv()
             };
 
-            if let Err(_err) = tcp_stream {
-                todo!();
-
-                //continue;
-            }
+            //every connection sender has been dropped; the server is shutting down
+            let mut connection = match tcp_stream {
+                Ok(connection) => connection,
+                Err(_) => break,
+            };
+            connections.fetch_sub(1, Ordering::AcqRel);
 
-            let mut tcp_stream = tcp_stream.unwrap();
+            let _busy = worker.start();
 
             //parse
-            match parser(&mut tcp_stream) {
-                Ok(val) => {
-                    //send data
-                    let _ = output_queue
-                        .lock()
-                        .unwrap()
-                        .push_back((tcp_stream, Ok(val)));
-                }
+            let request = match parser.parse(&mut *connection.stream) {
+                Ok(val) => Ok(val),
                 Err(err) => {
                     error!("failed to parse: {}",err);
 
-                    let _ = output_queue
-                        .lock()
-                        .unwrap()
-                        .push_back((tcp_stream, Err(err)));
+                    Err(err)
                 },
-            }
+            };
+
+            //apply the options of the listener that accepted the connection
+            let request = match &connection.listener {
+                Some(listener) => listener.route(request),
+                None => request,
+            };
+
+            //send data
+            overflow.push(&output_queue, (connection, request));
         }
     })
 }
 
 fn build_action_component<U: Send + 'static>(
-    func: ActionFunc<U>,
-    output_queue: Arc<Mutex<CompressionQueue>>,
+    func: Arc<dyn Action<U>>,
+    output_queue: Arc<ResponseQueue>,
+    overflow: Overflow,
     utility_access: mpsc::Sender<U>,
     settings: &Arc<RwLock<ServerSetting>>,
-) -> (Arc<Mutex<ActionQueue>>, ActionComponent) {
-    let input_queue = Arc::new(Mutex::new(ActionQueue::default()));
-
-    let thread = build_action_thread(func, input_queue.clone(), output_queue, utility_access, settings.clone());
-
-    let component = Component::new(input_queue.clone(), thread);
+    keep_alive: Option<KeepAlive>,
+    workers: Vec<Arc<WorkerMetrics>>,
+) -> (Arc<ActionQueue>, ActionComponent) {
+    let input_queue = Arc::new(ActionQueue::default());
+    let lifecycle = Arc::new(Lifecycle::default());
+
+    let component = {
+        let input_queue = input_queue.clone();
+        let lifecycle = lifecycle.clone();
+        let settings = settings.clone();
+
+        Component::new("Action", input_queue.clone(), lifecycle.clone(), workers, move |worker| {
+            build_action_thread(func.clone(), input_queue.clone(), output_queue.clone(), overflow.clone(), utility_access.clone(), settings.clone(), keep_alive, lifecycle.clone(), worker)
+        })
+    };
 
     (input_queue, component)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn build_action_thread<U: Send + 'static>(
-    func: ActionFunc<U>,
-    input_queue: Arc<Mutex<ActionQueue>>,
-    output_queue: Arc<Mutex<CompressionQueue>>,
+    func: Arc<dyn Action<U>>,
+    input_queue: Arc<ActionQueue>,
+    output_queue: Arc<ResponseQueue>,
+    overflow: Overflow,
     utility_access: mpsc::Sender<U>,
     server_settings: Arc<RwLock<ServerSetting>>,
+    keep_alive: Option<KeepAlive>,
+    lifecycle: Arc<Lifecycle>,
+    worker: Arc<WorkerMetrics>,
 ) -> JoinHandle<()> {
     thread::spawn(move || {
         let mut utility_access = utility_access;
         loop {
-
-            let (stream, action_cmd) = match dequeue(&input_queue){
+            let (mut connection, action_cmd) = match dequeue(&input_queue, &lifecycle) {
                 Some(val) => val,
-                None => continue
+                None => break,
             };
 
+            let _busy = worker.start();
+
             //action upon data
-            let server_settings = (&*server_settings.read().unwrap()).clone();
+            let server_settings = (*server_settings.read().unwrap()).clone();
 
-            let response = match func(&action_cmd, &server_settings, &mut utility_access) {
+            let mut response = match func.act(&action_cmd, &server_settings, &mut utility_access) {
                 Ok(val) => val,
                 Err(err) => {
-                    match func(&Err(err), &server_settings, &mut utility_access) {
+                    match func.act(&Err(err), &server_settings, &mut utility_access) {
                         Ok(val) => val,
                         Err(_) => {
                             error!("Failed get error response");
@@ -287,119 +512,305 @@ fn build_action_thread<U: Send + 'static>(
                 },
             };
 
-            match enqueue(output_queue.clone(), (stream, response, action_cmd.ok())) {
-                Ok(_) => trace!("successful response generation"),
-                Err(err) => error!("{err:?}"),
+            connection.requests += 1;
+
+            if let Some(keep_alive) = &keep_alive {
+                connection.keep_alive = keep_alive.negotiate(connection.requests, &action_cmd, &mut response);
             }
+
+            overflow.push(&output_queue, (connection, response, action_cmd.ok()));
+            trace!("successful response generation");
         }
     })
 }
 
-fn build_compressor_component(
-    func: CompressionFunc,
-    output_queue: Arc<Mutex<SenderQueue>>,
+fn build_request_stage_component(
+    name: &str,
+    stage: Arc<dyn RequestStage>,
+    output_queue: Arc<RequestQueue>,
+    overflow: Overflow,
     settings: &Arc<RwLock<ServerSetting>>,
-) -> (Arc<Mutex<CompressionQueue>>, CompressionComponent) {
-    let input_queue = Arc::new(Mutex::new(CompressionQueue::default()));
+    workers: Vec<Arc<WorkerMetrics>>,
+) -> (Arc<RequestQueue>, RequestStageComponent) {
+    let input_queue = Arc::new(RequestQueue::default());
+    let lifecycle = Arc::new(Lifecycle::default());
+
+    let component = {
+        let input_queue = input_queue.clone();
+        let lifecycle = lifecycle.clone();
+        let settings = settings.clone();
+
+        Component::new(name, input_queue.clone(), lifecycle.clone(), workers, move |worker| {
+            build_request_stage_thread(stage.clone(), input_queue.clone(), output_queue.clone(), overflow.clone(), settings.clone(), lifecycle.clone(), worker)
+        })
+    };
 
-    let thread = build_compressor_thread(func, input_queue.clone(), output_queue, settings.clone());
+    (input_queue, component)
+}
 
-    let component = Component::new(input_queue.clone(), thread);
+fn build_request_stage_thread(
+    stage: Arc<dyn RequestStage>,
+    input_queue: Arc<RequestQueue>,
+    output_queue: Arc<RequestQueue>,
+    overflow: Overflow,
+    server_settings: Arc<RwLock<ServerSetting>>,
+    lifecycle: Arc<Lifecycle>,
+    worker: Arc<WorkerMetrics>,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        loop {
+            let (connection, request) = match dequeue(&input_queue, &lifecycle) {
+                Some(value) => value,
+                None => break,
+            };
+
+            let _busy = worker.start();
+
+            let request = stage.process(&connection, request, &server_settings.read().unwrap());
+
+            overflow.push(&output_queue, (connection, request));
+        }
+    })
+}
+
+fn build_response_stage_component(
+    name: &str,
+    stage: Arc<dyn ResponseStage>,
+    output_queue: Arc<ResponseQueue>,
+    overflow: Overflow,
+    settings: &Arc<RwLock<ServerSetting>>,
+    workers: Vec<Arc<WorkerMetrics>>,
+) -> (Arc<ResponseQueue>, ResponseStageComponent) {
+    let input_queue = Arc::new(ResponseQueue::default());
+    let lifecycle = Arc::new(Lifecycle::default());
+
+    let component = {
+        let input_queue = input_queue.clone();
+        let lifecycle = lifecycle.clone();
+        let settings = settings.clone();
+
+        Component::new(name, input_queue.clone(), lifecycle.clone(), workers, move |worker| {
+            build_response_stage_thread(stage.clone(), input_queue.clone(), output_queue.clone(), overflow.clone(), settings.clone(), lifecycle.clone(), worker)
+        })
+    };
 
     (input_queue, component)
 }
 
-fn build_compressor_thread(
-    func: CompressionFunc,
-    input_queue: Arc<Mutex<CompressionQueue>>,
-    output_queue: Arc<Mutex<SenderQueue>>,
-    server_settings: Arc<RwLock<ServerSetting>>
+fn build_response_stage_thread(
+    stage: Arc<dyn ResponseStage>,
+    input_queue: Arc<ResponseQueue>,
+    output_queue: Arc<ResponseQueue>,
+    overflow: Overflow,
+    server_settings: Arc<RwLock<ServerSetting>>,
+    lifecycle: Arc<Lifecycle>,
+    worker: Arc<WorkerMetrics>,
 ) -> JoinHandle<()> {
     thread::spawn(move || {
-        let server_settings = (&*server_settings.read().unwrap()).clone();
+        loop {
+            let (connection, response, request) = match dequeue(&input_queue, &lifecycle) {
+                Some(value) => value,
+                None => break,
+            };
+
+            let _busy = worker.start();
+
+            let response = stage.process(&connection, response, request.as_ref(), &server_settings.read().unwrap());
+
+            overflow.push(&output_queue, (connection, response, request));
+        }
+    })
+}
+
+fn build_compressor_component(
+    func: Arc<dyn Compressor>,
+    output_queue: Arc<SenderQueue>,
+    overflow: Overflow,
+    settings: &Arc<RwLock<ServerSetting>>,
+    workers: Vec<Arc<WorkerMetrics>>,
+) -> (Arc<CompressionQueue>, CompressionComponent) {
+    let input_queue = Arc::new(CompressionQueue::default());
+    let lifecycle = Arc::new(Lifecycle::default());
+
+    let component = {
+        let input_queue = input_queue.clone();
+        let lifecycle = lifecycle.clone();
+        let settings = settings.clone();
+
+        Component::new("Compression", input_queue.clone(), lifecycle.clone(), workers, move |worker| {
+            build_compressor_thread(func.clone(), input_queue.clone(), output_queue.clone(), overflow.clone(), settings.clone(), lifecycle.clone(), worker)
+        })
+    };
 
+    (input_queue, component)
+}
+
+fn build_compressor_thread(
+    func: Arc<dyn Compressor>,
+    input_queue: Arc<CompressionQueue>,
+    output_queue: Arc<SenderQueue>,
+    overflow: Overflow,
+    server_settings: Arc<RwLock<ServerSetting>>,
+    lifecycle: Arc<Lifecycle>,
+    worker: Arc<WorkerMetrics>,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
         loop {
             //get first element in queue
-            let (stream, response, request) = match dequeue(&input_queue) {
+            let (connection, response, request) = match dequeue(&input_queue, &lifecycle) {
                 Some(value) => value,
-                None => continue,
+                None => break,
             };
 
+            let _busy = worker.start();
+
             trace!("Begin compression");
 
+            // settings are read per response so reloaded settings are picked up
+            let server_settings = server_settings.read().unwrap().clone();
+
             //compress data and push to next pipe
-            match enqueue(output_queue.clone(), (stream, func(response, request, server_settings.clone()))) {
-                Ok(_) => {trace!("End compression");},
-                Err(err) => error!("{err:?}"),
-            }
+            overflow.push(&output_queue, (connection, func.compress(response, request, server_settings)));
+            trace!("End compression");
         }
     })
 }
 
-fn build_sender_component() -> (Arc<Mutex<SenderQueue>>, SenderComponent) {
-    let input_queue = Arc::new(Mutex::new(SenderQueue::default()));
+fn build_sender_component(output_queue: Arc<IdleQueue>, workers: Vec<Arc<WorkerMetrics>>) -> (Arc<SenderQueue>, SenderComponent) {
+    let input_queue = Arc::new(SenderQueue::default());
+    let lifecycle = Arc::new(Lifecycle::default());
 
-    let thread = build_sender_thread(input_queue.clone());
+    let component = {
+        let input_queue = input_queue.clone();
+        let lifecycle = lifecycle.clone();
 
-    let component = Component::new(input_queue.clone(), thread);
+        Component::new("Sender", input_queue.clone(), lifecycle.clone(), workers, move |worker| {
+            build_sender_thread(input_queue.clone(), output_queue.clone(), lifecycle.clone(), worker)
+        })
+    };
 
     (input_queue, component)
 }
 
 fn build_sender_thread(
-    input_queue: Arc<Mutex<SenderQueue>>,
+    input_queue: Arc<SenderQueue>,
+    output_queue: Arc<IdleQueue>,
+    lifecycle: Arc<Lifecycle>,
+    worker: Arc<WorkerMetrics>,
 ) -> JoinHandle<()> {
     thread::spawn(move || {
         loop {
             //get first element in queue
-            let (mut stream, bytes) = match dequeue(&input_queue) {
+            let (mut connection, bytes) = match dequeue(&input_queue, &lifecycle) {
                 Some(value) => value,
-                None => continue,
+                None => break,
             };
 
+            let _busy = worker.start();
+
             trace!("sending bytes");
 
             //send data
-            if let Err(err) = stream.write_all(&bytes) {
-                error!("Failed to write: {err}");
-            }
-                
-            if let Err(err) = stream.flush() {
+            let sent = match connection.stream.write_all(&bytes) {
+                Ok(()) => true,
+                Err(err) => {
+                    error!("Failed to write: {err}");
+                    false
+                }
+            };
+
+            if let Err(err) = connection.stream.flush() {
                 error!("Failed to close: {err}");
             }
 
+            //the client was told the connection is kept alive, so wait for space rather than drop it
+            //a closed idle queue means the pipeline is shutting down; the connection is closed on drop
+            if sent && connection.keep_alive {
+                let _ = output_queue.push_wait(connection);
+            }
         }
     })
 }
 
-fn dequeue<const T: usize, U>(queue: &Arc<Mutex<List<T, U, false>>>) -> Option<U> {
-    // functions ensures locking is localized to function rather than lifetime of loop
-    // with the goal of minimizing chance of poisoning lock
-    let mut input_queue = match queue.try_lock() {
-        Ok(val) => val,
-        Err(_) => return None,
+fn build_idle_component(
+    parser_queue: Sender<Connection>,
+    connections: Arc<AtomicUsize>,
+    idle_timeout: Duration,
+) -> (Arc<IdleQueue>, IdleComponent) {
+    let input_queue = Arc::new(IdleQueue::default());
+    let lifecycle = Arc::new(Lifecycle::default());
+
+    let component = {
+        let input_queue = input_queue.clone();
+        let lifecycle = lifecycle.clone();
+
+        // idle connections are swept by a single worker that isn't reported in the pipeline's metrics
+        Component::new("Idle", input_queue.clone(), lifecycle.clone(), vec![Arc::default()], move |_| {
+            build_idle_thread(input_queue.clone(), parser_queue.clone(), connections.clone(), idle_timeout, lifecycle.clone())
+        })
     };
 
-    let input_queue = &mut *input_queue;
-
-    //get first element in queue
-    input_queue.remove_front()
+    (input_queue, component)
 }
 
-fn enqueue<const T: usize, U>(queue: Arc<Mutex<List<T, U, false>>>, value: U) -> Result<(), Error> {
-    // functions ensures locking is localized to function rather than lifetime of loop
-    // with the goal of minimizing chance of poisoning lock
-    let mut queue = queue.lock();
+fn build_idle_thread(
+    input_queue: Arc<IdleQueue>,
+    parser_queue: Sender<Connection>,
+    connections: Arc<AtomicUsize>,
+    idle_timeout: Duration,
+    lifecycle: Arc<Lifecycle>,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let mut idle: Vec<(Connection, Instant)> = Vec::new();
 
-    let result = match &mut queue {
-        Ok(queue) => {
-            queue.push_back(value)
-        },
-        Err(_) => todo!(),
-    };
+        loop {
+            if lifecycle.aborted() {
+                break;
+            }
+
+            //park until a connection arrives; only polling while there are connections to check
+            let timeout = match idle.is_empty() {
+                true => None,
+                false => Some(IDLE_POLL),
+            };
 
-    match result {
-        Ok(_) => Ok(()),
-        Err(err) => Err(err),
+            match input_queue.pop(timeout) {
+                Ok(connection) => idle.push((connection, Instant::now())),
+                Err(PopError::Timeout) => {}
+                Err(PopError::Closed) => break,
+            }
+
+            let mut waiting = Vec::with_capacity(idle.len());
+
+            for (mut connection, since) in idle.drain(..) {
+                match readiness(&mut *connection.stream) {
+                    Readiness::Readable => {
+                        connections.fetch_add(1, Ordering::AcqRel);
+
+                        if parser_queue.send(connection).is_err() {
+                            connections.fetch_sub(1, Ordering::AcqRel);
+                        }
+                    }
+                    Readiness::Idle if since.elapsed() < idle_timeout => waiting.push((connection, since)),
+                    Readiness::Idle => trace!("Closing idle connection"),
+                    Readiness::Closed => trace!("Connection closed by client"),
+                }
+            }
+
+            idle = waiting;
+        }
+    })
+}
+
+fn dequeue<T, const SIZE: usize>(queue: &BlockingQueue<T, SIZE>, lifecycle: &Lifecycle) -> Option<T> {
+    // parks the stage until work arrives
+    // None is returned once the queue has been closed & drained, or the stage has been aborted
+    if lifecycle.aborted() {
+        return None;
     }
-}
\ No newline at end of file
+
+    match queue.pop(None) {
+        Ok(value) if !lifecycle.aborted() => Some(value),
+        Ok(_) | Err(PopError::Closed) | Err(PopError::Timeout) => None,
+    }
+}
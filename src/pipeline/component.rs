@@ -1,32 +1,193 @@
-use std::{marker::PhantomData, thread::JoinHandle};
+use std::{
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Receiver,
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use super::{metrics::WorkerMetrics, queue::BlockingQueue};
+
+const JOIN_POLL: Duration = Duration::from_millis(5);
+
+/// Lifecycle is shared between a component and its thread in order to signal when the thread should stop
+///
+/// Draining is signalled by closing the component's input queue; the lifecycle is used to stop without draining.
+#[derive(Debug, Default)]
+pub struct Lifecycle {
+    aborted: AtomicBool,
+}
+
+impl Lifecycle {
+    /// aborted is true once the thread should exit without draining its input queue
+    pub fn aborted(&self) -> bool {
+        self.aborted.load(Ordering::Acquire)
+    }
+}
+
+/// Input is implemented by the queues components read from
+pub(super) trait Input {
+    /// close stops the queue from accepting work so the component can drain & exit
+    fn close(&self);
+}
+
+impl<T, const SIZE: usize> Input for Arc<BlockingQueue<T, SIZE>> {
+    fn close(&self) {
+        BlockingQueue::close(self);
+    }
+}
+
+impl<T> Input for Arc<Mutex<Receiver<T>>> {
+    // channels are closed by dropping every sender
+    fn close(&self) {}
+}
+
+/// Worker is one of the threads sharing a component's input queue
+struct Worker<E> {
+    thread: JoinHandle<E>,
+    metrics: Arc<WorkerMetrics>,
+}
 
 pub struct Component<IQ, OQ, E> {
+    pub(super) name: String,
+
     pub(super) input_queue: IQ,
 
-    thread: JoinHandle<E>,
+    pub(super) lifecycle: Arc<Lifecycle>,
+
+    workers: Vec<Worker<E>>,
+
+    // spawns a worker thread, both initially & on recovery; capturing everything the thread needs
+    spawn: Box<dyn Fn(Arc<WorkerMetrics>) -> JoinHandle<E> + Send>,
 
     output_queue: PhantomData<OQ>,
 }
 
 impl<IQ, OQ, E> Component<IQ, OQ, E> {
-    pub fn new(input_queue: IQ, thread: JoinHandle<E>) -> Self {
+    /// new creates a component & spawns a worker thread for each of the given worker metrics
+    pub fn new<F>(name: &str, input_queue: IQ, lifecycle: Arc<Lifecycle>, workers: Vec<Arc<WorkerMetrics>>, spawn: F) -> Self
+    where
+        F: Fn(Arc<WorkerMetrics>) -> JoinHandle<E> + Send + 'static,
+    {
+        let workers = workers
+            .into_iter()
+            .map(|metrics| Worker {
+                thread: spawn(metrics.clone()),
+                metrics,
+            })
+            .collect();
+
         Self {
+            name: name.to_string(),
             input_queue,
-            thread: thread,
+            lifecycle,
+            workers,
+            spawn: Box::new(spawn),
             output_queue: PhantomData,
         }
     }
 
+    /// thread_state is true while every worker thread is running
     pub fn thread_state(&self) -> bool {
-        !self.thread.is_finished()
+        self.workers.iter().all(|worker| !worker.thread.is_finished())
+    }
+
+    /// recover spawns a new thread for each worker whose thread has stopped; running workers are left untouched
+    ///
+    /// # return
+    /// the number of workers replaced
+    pub(super) fn recover(&mut self) -> usize {
+        let mut replaced = 0;
+
+        for worker in self.workers.iter_mut().filter(|worker| worker.thread.is_finished()) {
+            worker.metrics.record_restart();
+            worker.thread = (self.spawn)(worker.metrics.clone());
+
+            replaced += 1;
+        }
+
+        replaced
     }
 
-    //swap out thread on recovery
-    pub(super) fn swap_out_thread(&mut self, new_thread: JoinHandle<E>) {
-        if self.thread_state() {
-            return;
+    /// abort signals every worker to exit without finishing the rest of the input queue
+    pub(super) fn abort(&self) {
+        self.lifecycle.aborted.store(true, Ordering::Release);
+    }
+
+    /// wait blocks until every worker has finished or the deadline has passed
+    ///
+    /// # return
+    /// true if every worker finished before the deadline
+    pub(super) fn wait(&self, deadline: Instant) -> bool {
+        while self.workers.iter().any(|worker| !worker.thread.is_finished()) {
+            if Instant::now() >= deadline {
+                return false;
+            }
+
+            thread::sleep(JOIN_POLL);
         }
 
-        self.thread = new_thread;
+        true
+    }
+
+    /// join consumes the component and joins every worker thread
+    ///
+    /// # return
+    /// the result of each worker, in the order the workers were created
+    pub(super) fn join(self) -> Vec<thread::Result<E>> {
+        self.workers.into_iter().map(|worker| worker.thread.join()).collect()
+    }
+}
+
+/// Supervised is a type erased component; allowing a pipeline to check, recover & stop every stage without knowing its queue types
+pub(super) trait Supervised: Send {
+    fn name(&self) -> &str;
+
+    fn thread_state(&self) -> bool;
+
+    /// recover returns the number of workers replaced
+    fn recover(&mut self) -> usize;
+
+    /// close closes the component's input so it exits once drained
+    fn close(&self);
+
+    fn abort(&self);
+
+    fn wait(&self, deadline: Instant) -> bool;
+
+    /// join returns false if any worker panicked
+    fn join(self: Box<Self>) -> bool;
+}
+
+impl<IQ: Input + Send, OQ: Send, E: Send> Supervised for Component<IQ, OQ, E> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn thread_state(&self) -> bool {
+        Component::thread_state(self)
+    }
+
+    fn recover(&mut self) -> usize {
+        Component::recover(self)
+    }
+
+    fn close(&self) {
+        self.input_queue.close();
+    }
+
+    fn abort(&self) {
+        Component::abort(self);
+    }
+
+    fn wait(&self, deadline: Instant) -> bool {
+        Component::wait(self, deadline)
+    }
+
+    fn join(self: Box<Self>) -> bool {
+        Component::join(*self).iter().all(|result| result.is_ok())
     }
 }
@@ -18,6 +18,7 @@ use crate::{
         method::{Method},
         request::Request,
         response::{response_status_code::ResponseStatusCode, Response},
+        version::Version,
     },
     pipeline::pipeline::Bytes,
     setting::{ServerSetting}, ActionBuilder,
@@ -55,25 +56,27 @@ pub fn default_err_page<S>(
         header: HashMap::new(),
         body: Some(Body {
             content_type: ContentType::Text(Text::html),
-            content: format!("<H1>{}</H1>", err_code.to_string())
+            content: format!("<H1>{}</H1>", err_code)
                 .as_bytes()
                 .to_vec(),
         }),
         status: *err_code,
     }
 }
-pub type FileUtilitySender<E> = mpsc::Sender<(PathBuf, Sender<Result<Bytes, E>>)>;
+/// FileRequest is a path to read; with the channel its content or error is sent on
+pub type FileRequest<E> = (PathBuf, Sender<Result<Bytes, E>>);
+pub type FileUtilitySender<E> = mpsc::Sender<FileRequest<E>>;
 
 pub const NO_BOUND: usize = 0;
 pub fn generate_read_only_file_utility_thread<const MAX_READS: usize>(
 ) -> (FileUtilitySender<FileError>, JoinHandle<()>) {
     // todo!() fn should take into account reads(aka RWLock)
     // todo!() fn should have server wide caching
-    let (tx, rx) = mpsc::channel::<(PathBuf, Sender<Result<Vec<u8>, FileError>>)>();
+    let (tx, rx) = mpsc::channel::<FileRequest<FileError>>();
 
     let thread = thread::spawn(move || {
         let mut threads = Vec::new();
-        let mut cache: Option<(PathBuf, Sender<Result<Vec<u8>, FileError>>)> = Option::None;
+        let mut cache: Option<FileRequest<FileError>> = Option::None;
         loop {
             match cache {
                 Some(_) => {
@@ -99,11 +102,11 @@ pub fn generate_read_only_file_utility_thread<const MAX_READS: usize>(
                 }
             }
 
-            threads = threads.into_iter().filter(|t| !t.is_finished()).collect();
+            threads.retain(|t| !t.is_finished());
         }
     });
 
-    return (tx, thread);
+    (tx, thread)
 }
 
 pub fn default_get_logic(
@@ -111,24 +114,28 @@ pub fn default_get_logic(
     setting: &ServerSetting,
     utility_thread: &FileUtilitySender<FileError>,
 ) -> Result<Response, ResponseStatusCode> {
-    let Request(method, meta_data) = request;
-
-    info!("Request: {method:?}\n {meta_data:?}");
-
-    let host: &str = match meta_data.get("host") {
-        Some(host) => host,
-        None => return Err(ResponseStatusCode::ImATeapot),
+    info!("Request: {} {}\n {:?}", request.method, request.target, request.headers);
+
+    let host: &str = match (request.headers.get("host"), &request.version) {
+        (Some(host), _) => host,
+        // a HTTP/1.1 request must name the host it is made to
+        (None, Version::Http1_1) => return Err(ResponseStatusCode::BadRequest),
+        // Host is optional in HTTP/1.0; such requests are served from the domain of the server's address
+        (None, _) => &setting.address,
     };
 
     info!("Request Host:{}", host);
 
-    let domain_path = match setting.paths.get(host) {
+    let domain_path = match setting.domain(host) {
         Some(setting) => setting,
-        None => return Err(ResponseStatusCode::Forbidden),
+        None => {
+            info!("No domain for host");
+            return Err(ResponseStatusCode::NotFound);
+        }
     };
 
-    let file = match method {
-        Method::Get { file } => file::parse(&file, &domain_path.path, |ext| {
+    let file = match request.method {
+        Method::Get => file::parse(&request.path, &domain_path.path, |ext| {
             domain_path
                 .allow
                 .iter()
@@ -152,6 +159,10 @@ pub fn default_get_logic(
                     info!("Invalid file extension");
                     Err(ResponseStatusCode::Forbidden)
                 }
+                FileError::InvalidPath => {
+                    info!("Path leaves the domain's folder");
+                    Err(ResponseStatusCode::BadRequest)
+                }
             }
         }
     };
@@ -193,18 +204,19 @@ pub fn default_get_logic(
 
                 trace!("Action function completed");
 
-                return Ok(Response {
+                Ok(Response {
                     status: ResponseStatusCode::Ok,
                     header: HashMap::new(),
                     body: Some(Body {
                         content_type,
                         content,
                     }),
-                });
+                })
             }
             Err(err) => match err {
-                FileError::FileDoesNotExist => return Err(ResponseStatusCode::NotFound),
-                FileError::InaccessibleExtension => return Err(ResponseStatusCode::Forbidden),
+                FileError::FileDoesNotExist => Err(ResponseStatusCode::NotFound),
+                FileError::InaccessibleExtension => Err(ResponseStatusCode::Forbidden),
+                FileError::InvalidPath => Err(ResponseStatusCode::BadRequest),
             },
         },
         Err(err) => {
@@ -1,5 +1,5 @@
 use crate::{
-    http::{body::Body, request::Request, response::Response},
+    http::{body::Body, request::Request, response::Response, version::Version},
     setting::ServerSetting,
 };
 
@@ -19,47 +19,50 @@ use flate2::{
 use log::{trace, error};
 use std::io::Write;
 
-pub fn no_compression(response: Response, _: Option<Request>, _: ServerSetting) -> Bytes {
-    return response.as_bytes();
+/// no_compression sends the response as it is; with the version of the request it answers
+pub fn no_compression(response: Response, request: Option<Request>, _: ServerSetting) -> Bytes {
+    response.as_bytes_for(&version_of(&request))
 }
 
+/// compression encodes the body of the response with the first encoding the request accepts; with the version of the request it answers
 pub fn compression(mut response: Response, request: Option<Request>, _setting: ServerSetting) -> Bytes {
-    
+    let version = version_of(&request);
+
     let request = match request{
         Some(val) => val,
-        None => return response.as_bytes(),
+        None => return response.as_bytes_for(&version),
     };
 
-    if let None = response.body {
-        return response.as_bytes();
+    if response.body.is_none() {
+        return response.as_bytes_for(&version);
     }
 
-    let request_header = &request.1;
+    let request_header = &request.headers;
 
-    if !request_header.contains_key("accept-encoding") {
-        return response.as_bytes();
+    if !request_header.contains("accept-encoding") {
+        return response.as_bytes_for(&version);
     }
 
     let mut body_content = response.body.clone().unwrap().content;
 
     let accepted = request_header
-        .get("accept-encoding")
-        .unwrap()
-        .to_string()
+        .get_all("accept-encoding")
+        .collect::<Vec<&str>>()
+        .join(",")
         .replace(' ', "");
 
     trace!("accepted encoder:{:?}", accepted);
 
     let mut iter = accepted.split(',').peekable();
 
-    if let None = &iter.peek() {
-        return response.as_bytes();
+    if iter.peek().is_none() {
+        return response.as_bytes_for(&version);
     }
 
     for decoder in iter {
         match decoder {
             "gzip" => {
-                println!("d={:?}", body_content);
+                trace!("gzip input: {} bytes", body_content.len());
                 let mut encoder = GzEncoder::new(Vec::new(), Compression::none());
                 if let Err(err) = encoder.write_all(&body_content) {
                     error!("{err}");
@@ -68,8 +71,7 @@ pub fn compression(mut response: Response, request: Option<Request>, _setting: S
                 }
 
                 body_content = encoder.finish().unwrap();
-                println!("d'={:?}", body_content);
-                //println!("data:{body_content:?}");
+                trace!("gzip output: {} bytes", body_content.len());
                 response
                     .header
                     .insert(String::from("Content-Encoding"), String::from("gzip"));
@@ -114,6 +116,13 @@ pub fn compression(mut response: Response, request: Option<Request>, _setting: S
         content: body_content,
     });
 
-    return response.as_bytes();
+    response.as_bytes_for(&version)
 }
 
+
+/// version_of returns the version to answer request with; HTTP/1.1 if the request couldn't be parsed
+fn version_of(request: &Option<Request>) -> Version {
+    request
+        .as_ref()
+        .map_or(Version::Http1_1, |request| request.version.clone())
+}
@@ -1,113 +1,209 @@
-use std::{io::{Read, ErrorKind}, net::{TcpStream, Shutdown}, str::FromStr, time::{Duration, Instant}, thread, sync::mpsc};
+use std::{io::ErrorKind, time::Duration};
 
 use log::error;
 
-use crate::http::{request::Request, response::response_status_code::ResponseStatusCode};
-
+use crate::{
+    http::{
+        request::{
+            field::{fields, FieldLimits},
+            framing::{framing, ChunkedDecoder, Framing},
+            latin1,
+            parser_error::ParserError,
+            Request,
+        },
+        response::response_status_code::ResponseStatusCode,
+    },
+    pipeline::Stream,
+};
+
+/// parser reads a request from stream; returning as soon as the request is complete so the connection can be reused
+///
+/// The head is read up to the empty line ending it, followed by exactly as many bytes of body as its Content-Length declares, or by a chunked body decoded as it arrives; bytes read past the end of the request are handed back to stream for the next request. READ_TIMEOUT bounds the wait for the first packet, after which every packet must arrive within PACKET_TIMEOUT. A head cut short by the client closing the connection is parsed as is.
+///
+/// The header section is held to the default [FieldLimits]; see [parser_with]
 pub fn parser<const BUFFER_SIZE: usize, const MAX_SIZE: usize, const PACKET_TIMEOUT: u128, const READ_TIMEOUT: u64>(
-    stream: &mut TcpStream,
+    stream: &mut dyn Stream,
 ) -> Result<Request, ResponseStatusCode> {
-    let mut request_str = String::new();
+    parser_with::<BUFFER_SIZE, MAX_SIZE, PACKET_TIMEOUT, READ_TIMEOUT>(stream, &FieldLimits::default())
+}
 
-    let mut request_size = 0;
+/// parser_with reads a request from stream as [parser] does; holding its header section to limits
+///
+/// A header section exceeding limits is answered with 431 Request Header Fields Too Large, before any body is read.
+///
+/// # Example
+/// ```ignore
+/// let limits = FieldLimits::default().set_max_fields(32).set_unfold(true);
+///
+/// let builder = Builder::default()
+///     .set_parser(move |stream: &mut dyn Stream| parser_with::<1024, 65536, 200, 1000>(stream, &limits));
+/// ```
+pub fn parser_with<const BUFFER_SIZE: usize, const MAX_SIZE: usize, const PACKET_TIMEOUT: u128, const READ_TIMEOUT: u64>(
+    stream: &mut dyn Stream,
+    limits: &FieldLimits,
+) -> Result<Request, ResponseStatusCode> {
+    let mut request: Vec<u8> = Vec::new();
+    let mut state = State::Head { scanned: 0 };
 
-    if let Err(err) = stream.set_read_timeout(Some(Duration::from_millis(READ_TIMEOUT))) {
+    if let Err(err) = stream.set_read_timeout(Some(Duration::from_millis(READ_TIMEOUT.max(1)))) {
         error!("Failed to set read timeout: {err}");
         return Err(ResponseStatusCode::BadRequest)
     };//max read time
 
-    let mut stream_tmp = stream.try_clone().unwrap();
-
-    let (tx, rx) = mpsc::channel();
-    let read_thread = thread::spawn(move || {
-        let mut buffer: [u8; BUFFER_SIZE] = [0; BUFFER_SIZE];
-
-        loop {
-            match stream_tmp.read(&mut buffer) {
-                Ok(0) => {
-                    return Ok(());
-                },
-                Ok(read_size) => {
-                    tx.send((read_size, buffer)).unwrap();
-                },
-                Err(err) => {
-                    error!("{err}");
-                    return Err(err)
-                },
-            };
-        }
-    });
-
-    let mut initial_time = Instant::now();
+    let mut buffer: [u8; BUFFER_SIZE] = [0; BUFFER_SIZE];
+
     loop {
-        let elapsed_time = initial_time.elapsed();
-
-        if elapsed_time.as_millis() > PACKET_TIMEOUT {
-            break;
-            //error!("stream packet timeout: {} > {PACKET_TIMEOUT}",elapsed_time.as_millis());
-            //return Err(ResponseStatusCode::RequestTimeout);
-        }
-
-        if read_thread.is_finished() {
-            match read_thread.join() {
-                Ok(Ok(())) => break,
-                Ok(Err(ref err)) if err.kind() == ErrorKind::TimedOut => return Err(ResponseStatusCode::RequestTimeout),
-                Err(_) | Ok(Err(_)) => return Err(ResponseStatusCode::BadRequest),
-            }
-        }
-
-        if let Ok((read_size, buffer)) = rx.try_recv() {
-            request_size += read_size;
-
-            if MAX_SIZE < request_size {
-                return Err(ResponseStatusCode::PayloadTooLarge);
-            }
-
-            let slice = match String::from_utf8(buffer[..read_size].to_vec()) {
-                Ok(val) => val,
-                Err(_err) => return Err(ResponseStatusCode::BadRequest),
-            };
-
-            request_str.push_str(&slice);
-            initial_time = Instant::now();
-        }
+        // the body is read no further than its end; anything after it belongs to the next request
+        let wanted = match state {
+            State::Head { .. } => BUFFER_SIZE,
+            State::Body { end } => BUFFER_SIZE.min(end - request.len()),
+            State::Chunked { .. } => BUFFER_SIZE,
+        };
+
+        match stream.read(&mut buffer[..wanted]) {
+            Ok(0) => match (&state, request.is_empty()) {
+                (State::Head { .. }, false) => break,
+                _ => return Err(ResponseStatusCode::BadRequest),
+            },
+            Ok(read_size) => {
+                request.extend_from_slice(&buffer[..read_size]);
+
+                if let State::Head { scanned } = state {
+                    state = match head_end(&request, scanned) {
+                        Some(head_end) => body_state::<MAX_SIZE>(&request[..head_end], limits)?,
+                        None => State::Head { scanned: request.len() },
+                    };
+                }
+
+                let end = match &mut state {
+                    // a head that doesn't fit is a header section too large; as body_state answers one exceeding its limits
+                    State::Head { .. } if MAX_SIZE < request.len() => return Err(ResponseStatusCode::RequestHeaderFieldsTooLarge),
+                    State::Head { .. } => None,
+                    State::Body { end } if MAX_SIZE < *end => return Err(ResponseStatusCode::PayloadTooLarge),
+                    State::Body { end } => Some(*end).filter(|end| *end <= request.len()),
+                    State::Chunked { decoder, decoded } => {
+                        *decoded += decoder.decode(&request[*decoded..], None).map_err(status)?;
+
+                        // chunk framing counts towards the size; so a body can't be sent as endless extensions
+                        if MAX_SIZE < *decoded {
+                            return Err(ResponseStatusCode::PayloadTooLarge);
+                        }
+
+                        Some(*decoded).filter(|_| decoder.is_complete())
+                    }
+                };
+
+                if let Some(end) = end {
+                    stream.unread(&request.split_off(end));
+                    break;
+                }
+
+                if let Err(err) = stream.set_read_timeout(Some(Duration::from_millis((PACKET_TIMEOUT as u64).max(1)))) {
+                    error!("Failed to set packet timeout: {err}");
+                    return Err(ResponseStatusCode::BadRequest)
+                }
+            },
+            Err(ref err) if err.kind() == ErrorKind::WouldBlock || err.kind() == ErrorKind::TimedOut => {
+                return Err(ResponseStatusCode::RequestTimeout);
+            },
+            Err(err) => {
+                error!("{err}");
+                return Err(ResponseStatusCode::BadRequest)
+            },
+        };
+    }
+
+    Request::parse(&request, limits).map_err(status)
+}
+
+/// State is the part of a request the parser is reading
+#[derive(Debug)]
+enum State {
+    /// the head; whose first scanned bytes have been searched for its end
+    Head { scanned: usize },
+    /// a body framed by its Content-Length; the request is complete once end bytes have been read
+    Body { end: usize },
+    /// a chunked body; whose framing has been decoded up to decoded bytes into the request
+    Chunked { decoder: ChunkedDecoder, decoded: usize },
+}
+
+/// head_end finds the end of the head in request; searching from the last bytes before scanned, so a terminator split across reads is found
+///
+/// # return
+/// the length of the head, including the empty line ending it
+fn head_end(request: &[u8], scanned: usize) -> Option<usize> {
+    let from = scanned.saturating_sub(3);
+
+    [&b"\r\n\r\n"[..], b"\n\r\n", b"\n\n"]
+        .iter()
+        .filter_map(|terminator| {
+            request[from..]
+                .windows(terminator.len())
+                .position(|window| window == *terminator)
+                .map(|position| from + position + terminator.len())
+        })
+        .min()
+}
+
+/// body_state decides how the body following head is read
+///
+/// # Errors
+/// The status of a request whose header section is malformed or exceeds limits, or whose body can't be framed; see [fields] & [framing]
+fn body_state<const MAX_SIZE: usize>(head: &[u8], limits: &FieldLimits) -> Result<State, ResponseStatusCode> {
+    let headers = fields(latin1(head).split('\n').skip(1), limits).map_err(status)?;
+
+    Ok(match framing(headers.iter()).map_err(status)? {
+        Framing::None => State::Body { end: head.len() },
+        Framing::Length(length) => State::Body {
+            end: head.len().saturating_add(length),
+        },
+        Framing::Chunked => State::Chunked {
+            decoder: ChunkedDecoder::new(MAX_SIZE.saturating_sub(head.len())),
+            decoded: head.len(),
+        },
+    })
+}
+
+/// status maps a parsing error to the status it is answered with
+fn status(err: ParserError) -> ResponseStatusCode {
+    match err {
+        ParserError::InvalidStartLine { .. }
+        | ParserError::InvalidHeader { .. }
+        | ParserError::InvalidContentType { .. }
+        | ParserError::InvalidFraming { .. }
+        | ParserError::BodyLengthMismatch { .. }
+        | ParserError::InvalidChunk { .. } => ResponseStatusCode::BadRequest,
+        ParserError::UnknownMethod { .. } => ResponseStatusCode::MethodNotAllowed,
+        ParserError::TargetTooLong { .. } => ResponseStatusCode::URITooLong,
+        ParserError::UnsupportedVersion { .. } => ResponseStatusCode::HTTPVersionNotSupported,
+        ParserError::HeaderFieldsTooLarge { .. } => ResponseStatusCode::RequestHeaderFieldsTooLarge,
+        ParserError::MissingBody { .. } => ResponseStatusCode::LengthRequired,
+        ParserError::BodyTooLarge { .. } => ResponseStatusCode::PayloadTooLarge,
+        ParserError::UnsupportedEncoding { .. } => ResponseStatusCode::NotImplemented,
     }
-    let _ = stream.shutdown(Shutdown::Read);
-    Request::from_str(&request_str).map_err(|_| ResponseStatusCode::BadRequest)
 }
 
-pub fn single_read_parser<const MAX_SIZE: usize, const READ_TIMEOUT: u64>(stream: &mut TcpStream) -> Result<Request, ResponseStatusCode> {
+pub fn single_read_parser<const MAX_SIZE: usize, const READ_TIMEOUT: u64>(stream: &mut dyn Stream) -> Result<Request, ResponseStatusCode> {
     if let Err(err) = stream.set_read_timeout(Some(Duration::from_millis(READ_TIMEOUT))) {
         error!("Failed to set read timeout: {err:#?}");
         return Err(ResponseStatusCode::BadRequest)
     };//max read time
 
-    let mut buffer = Vec::with_capacity(MAX_SIZE);
+    let mut buffer = vec![0; MAX_SIZE];
 
-    let request_str = match stream.read(&mut buffer) {
-        Ok(read_size) => {
-            String::from_utf8(buffer[..read_size].to_vec())
-        },
+    let request = match stream.read(&mut buffer) {
+        Ok(read_size) => &buffer[..read_size],
         Err(err) => {
             error!("Failed to read: {err:#?}");
             return Err(ResponseStatusCode::BadRequest)
         },
     };
 
-    match request_str {
-        Ok(request_str) => {
-            let _ = stream.shutdown(Shutdown::Read);
-            match Request::from_str(&request_str) {
-                Ok(request) => Ok(request),
-                Err(err) => {
-                    error!("Failed to convert str to request:{err:#?}\t{request_str:#?}");
-                    Err(ResponseStatusCode::BadRequest)
-                },
-            }
-        },
+    match Request::try_from(request) {
+        Ok(request) => Ok(request),
         Err(err) => {
-            error!("failed to parse bytes into string: {err:#?}");
-            return Err(ResponseStatusCode::BadRequest)
+            error!("Failed to convert bytes to request:{err:#?}\t{:#?}", String::from_utf8_lossy(request));
+            Err(status(err))
         },
     }
-}
\ No newline at end of file
+}
@@ -7,8 +7,9 @@ mod compressor {
             method::Method,
             request::Request,
             response::{response_status_code::ResponseStatusCode, Response},
+            version::Version,
         },
-        pipeline::default::no_compression,
+        pipeline::default::{compression, no_compression},
         setting::ServerSetting,
     };
 
@@ -21,16 +22,13 @@ mod compressor {
             body: None,
         };
 
-        let request = Request(
-            Method::Get {
-                file: String::from(""),
-            },
-            HashMap::new(),
-        );
+        let request = Request::new(Method::Get, "", Version::Http1_1);
         let server = ServerSetting {
             address: String::from(""),
             port: 8080,
             paths: HashMap::new(),
+            pipelines: None,
+            listeners: Vec::new(),
         };
 
         println!("{}", data);
@@ -38,7 +36,7 @@ mod compressor {
         let actual = no_compression(data, Some(request), server);
         let expected = vec![
             72, 84, 84, 80, 47, 49, 46, 49, 32, 49, 48, 48, 32, 67, 111, 110, 116, 105, 110, 117,
-            101, 13, 10,
+            101, 13, 10, 13, 10,
         ];
 
         assert_eq!(actual, expected)
@@ -58,16 +56,13 @@ mod compressor {
             body: None,
         };
 
-        let request = Request(
-            Method::Get {
-                file: String::from(""),
-            },
-            HashMap::new(),
-        );
+        let request = Request::new(Method::Get, "", Version::Http1_1);
         let server = ServerSetting {
             address: String::from(""),
             port: 8080,
             paths: HashMap::new(),
+            pipelines: None,
+            listeners: Vec::new(),
         };
 
         println!("{}", data);
@@ -75,7 +70,7 @@ mod compressor {
         let actual = no_compression(data, Some(request), server);
         let expected = vec![
             72, 84, 84, 80, 47, 49, 46, 49, 32, 49, 48, 48, 32, 67, 111, 110, 116, 105, 110, 117,
-            101, 13, 10, 107, 101, 121, 58, 32, 118, 97, 108, 117, 101, 13, 10,
+            101, 13, 10, 107, 101, 121, 58, 32, 118, 97, 108, 117, 101, 13, 10, 13, 10,
         ];
 
         assert_eq!(actual, expected);
@@ -98,16 +93,13 @@ mod compressor {
             }),
         };
 
-        let request = Request(
-            Method::Get {
-                file: String::from(""),
-            },
-            HashMap::new(),
-        );
+        let request = Request::new(Method::Get, "", Version::Http1_1);
         let server = ServerSetting {
             address: String::from(""),
             port: 8080,
             paths: HashMap::new(),
+            pipelines: None,
+            listeners: Vec::new(),
         };
 
         let actual = no_compression(data, Some(request), server);
@@ -122,11 +114,65 @@ mod compressor {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn response_echoes_request_version() {
+        let server = ServerSetting {
+            address: String::from(""),
+            port: 8080,
+            paths: HashMap::new(),
+            pipelines: None,
+            listeners: Vec::new(),
+        };
+
+        let cases = [
+            (Some(Request::new(Method::Get, "", Version::Http1_0)), "HTTP/1.0 200 Ok\r\n\r\n"),
+            (Some(Request::new(Method::Get, "", Version::Http1_1)), "HTTP/1.1 200 Ok\r\n\r\n"),
+            (None, "HTTP/1.1 200 Ok\r\n\r\n"),
+        ];
+
+        for (request, expected) in cases {
+            let data = Response {
+                status: ResponseStatusCode::Ok,
+                header: HashMap::new(),
+                body: None,
+            };
+
+            assert_eq!(no_compression(data, request, server.clone()), expected.as_bytes());
+        }
+    }
+
+    #[test]
+    fn compressed_response_echoes_request_version() {
+        let data = Response {
+            status: ResponseStatusCode::Ok,
+            header: HashMap::new(),
+            body: Some(Body {
+                content_type: ContentType::Text(Text::plain),
+                content: String::from("hello world").as_bytes().to_vec(),
+            }),
+        };
+
+        let mut request = Request::new(Method::Get, "", Version::Http1_0);
+        request.headers.append("accept-encoding", "gzip");
+
+        let server = ServerSetting {
+            address: String::from(""),
+            port: 8080,
+            paths: HashMap::new(),
+            pipelines: None,
+            listeners: Vec::new(),
+        };
+
+        let actual = compression(data, Some(request), server);
+
+        assert!(actual.starts_with(b"HTTP/1.0 200 Ok\r\n"));
+        assert!(actual.windows(24).any(|window| window == b"Content-Encoding: gzip\r\n"));
+    }
+
     // different algo compression check
 }
 mod parser {
     use std::{
-        collections::HashMap,
         io::Write,
         net::{TcpListener, TcpStream},
         sync::{Arc, Condvar, Mutex},
@@ -138,6 +184,7 @@ mod parser {
     use crate::{
         http::{
             method::Method, request::Request, response::response_status_code::ResponseStatusCode,
+            version::Version,
         },
         pipeline::default::parser,
     };
@@ -179,12 +226,7 @@ mod parser {
             let actual = parser::parser::<100, 500, 20, 250>(&mut stream);
 
             assert_eq!(
-                Ok(Request(
-                    Method::Get {
-                        file: String::from("/index.html")
-                    },
-                    HashMap::new()
-                )),
+                Ok(Request::new(Method::Get, "/index.html", Version::Http1_1)),
                 actual
             );
         }
@@ -226,7 +268,7 @@ mod parser {
 
             let actual = parser::parser::<5, 5, 20, 250>(&mut stream);
 
-            assert_eq!(Err(ResponseStatusCode::PayloadTooLarge), actual);
+            assert_eq!(Err(ResponseStatusCode::RequestHeaderFieldsTooLarge), actual);
         }
     }
 
@@ -309,4 +351,443 @@ mod parser {
             assert_eq!(Err(ResponseStatusCode::BadRequest), actual);
         }
     }
+
+    mod framing {
+        use std::{
+            collections::VecDeque,
+            io::{self, ErrorKind, Read, Write},
+            net::SocketAddr,
+            str::FromStr,
+            time::Duration,
+        };
+
+        use crate::{
+            http::{
+                method::Method,
+                request::{field::FieldLimits, Request, MAX_TARGET_SIZE},
+                response::response_status_code::ResponseStatusCode,
+            },
+            pipeline::{default::parser, Stream},
+        };
+
+        const POST: &[u8] = b"POST /form HTTP/1.1\r\nhost:localhost\r\ncontent-type:text/plain\r\ncontent-length:11\r\n\r\nhello world";
+
+        /// Packets is a stream delivering one packet per read; once every packet is read, reads time out or the client closes the connection
+        #[derive(Debug)]
+        struct Packets {
+            packets: VecDeque<Vec<u8>>,
+            closed: bool,
+            unread: Vec<u8>,
+        }
+
+        impl Packets {
+            fn new(packets: Vec<&[u8]>, closed: bool) -> Self {
+                Self {
+                    packets: packets.into_iter().map(<[u8]>::to_vec).collect(),
+                    closed,
+                    unread: Vec::new(),
+                }
+            }
+        }
+
+        impl Read for Packets {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let mut packet = match self.packets.pop_front() {
+                    Some(packet) => packet,
+                    None if self.closed => return Ok(0),
+                    None => return Err(io::Error::from(ErrorKind::WouldBlock)),
+                };
+
+                let read = buf.len().min(packet.len());
+                buf[..read].copy_from_slice(&packet[..read]);
+
+                if read < packet.len() {
+                    self.packets.push_front(packet.split_off(read));
+                }
+
+                Ok(read)
+            }
+        }
+
+        impl Write for Packets {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        impl Stream for Packets {
+            fn peer_addr(&self) -> Option<SocketAddr> {
+                None
+            }
+
+            fn set_read_timeout(&self, _: Option<Duration>) -> io::Result<()> {
+                Ok(())
+            }
+
+            fn set_nonblocking(&self, _: bool) -> io::Result<()> {
+                Ok(())
+            }
+
+            fn peek_len(&mut self) -> io::Result<usize> {
+                Ok(self.packets.front().map_or(0, Vec::len))
+            }
+
+            fn unread(&mut self, bytes: &[u8]) {
+                self.unread.extend_from_slice(bytes);
+            }
+        }
+
+        fn parse(stream: &mut Packets) -> Result<Request, ResponseStatusCode> {
+            parser::parser::<16, 500, 20, 250>(stream)
+        }
+
+        fn expected() -> Result<Request, ResponseStatusCode> {
+            Ok(Request::from_str(std::str::from_utf8(POST).unwrap()).unwrap())
+        }
+
+        #[test]
+        fn split_at_any_byte() {
+            // the stream never closes, so the request is only returned if its end is found
+            for split in 1..POST.len() {
+                let mut stream = Packets::new(vec![&POST[..split], &POST[split..]], false);
+
+                assert_eq!(parse(&mut stream), expected(), "split at {split}");
+            }
+
+            let mut stream = Packets::new(POST.chunks(1).collect(), false);
+            assert_eq!(parse(&mut stream), expected());
+        }
+
+        #[test]
+        fn bytes_past_the_body_are_handed_back() {
+            let next = b"GET / HTTP/1.1\r\nhost:localhost\r\n\r\n";
+            let mut stream = Packets::new(vec![&[POST, next].concat()], false);
+
+            assert_eq!(parse(&mut stream), expected());
+
+            // only the start of the next request was read along with the end of the body
+            let rest: Vec<u8> = stream.packets.iter().flatten().copied().collect();
+            assert_eq!([stream.unread, rest].concat(), next);
+        }
+
+        #[test]
+        fn body_isnt_read_past_its_length() {
+            let next = b"GET / HTTP/1.1\r\n\r\n";
+            let (head, body) = POST.split_at(POST.len() - 11);
+            let mut stream = Packets::new(vec![head, &[body, next].concat()], false);
+
+            assert_eq!(parse(&mut stream), expected());
+            assert!(stream.unread.is_empty());
+            assert_eq!(stream.packets.pop_front().unwrap(), next);
+        }
+
+        #[test]
+        fn declared_body_larger_than_max_size() {
+            let mut stream = Packets::new(vec![b"POST / HTTP/1.1\r\ncontent-length:1000\r\n\r\n"], false);
+
+            assert_eq!(parse(&mut stream), Err(ResponseStatusCode::PayloadTooLarge));
+        }
+
+        #[test]
+        fn invalid_content_length() {
+            for head in [
+                &b"POST / HTTP/1.1\r\ncontent-length:five\r\n\r\n"[..],
+                b"POST / HTTP/1.1\r\ncontent-length:5\r\ncontent-length:6\r\n\r\nhello",
+            ] {
+                let mut stream = Packets::new(vec![head], false);
+
+                assert_eq!(parse(&mut stream), Err(ResponseStatusCode::BadRequest));
+            }
+        }
+
+        #[test]
+        fn truncated_body_is_rejected() {
+            let mut stream = Packets::new(vec![&POST[..POST.len() - 1]], true);
+
+            assert_eq!(parse(&mut stream), Err(ResponseStatusCode::BadRequest));
+        }
+
+        #[test]
+        fn stalled_request_times_out() {
+            let mut stream = Packets::new(vec![&POST[..POST.len() - 1]], false);
+
+            assert_eq!(parse(&mut stream), Err(ResponseStatusCode::RequestTimeout));
+        }
+
+        #[test]
+        fn header_fields_exceeding_limits() {
+            let limits = FieldLimits::default().set_max_fields(2);
+            let head = b"POST / HTTP/1.1\r\nhost:localhost\r\ncontent-type:text/plain\r\ncontent-length:100\r\n\r\n";
+
+            // answered once the head is read; without waiting for the body
+            let mut stream = Packets::new(vec![head], false);
+            assert_eq!(
+                parser::parser_with::<16, 500, 20, 250>(&mut stream, &limits),
+                Err(ResponseStatusCode::RequestHeaderFieldsTooLarge)
+            );
+
+            let mut stream = Packets::new(vec![POST], false);
+            assert_eq!(
+                parser::parser_with::<16, 500, 20, 250>(&mut stream, &FieldLimits::default().set_max_field_size(12)),
+                Err(ResponseStatusCode::RequestHeaderFieldsTooLarge)
+            );
+        }
+
+        #[test]
+        fn head_larger_than_max_size() {
+            let head = b"GET / HTTP/1.1\r\nhost:localhost\r\nx-padding:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\r\n\r\n";
+
+            // the header section is too large, not the body; as with a head exceeding its field limits
+            let mut stream = Packets::new(head.chunks(16).collect(), false);
+            assert_eq!(
+                parser::parser::<16, 64, 20, 250>(&mut stream),
+                Err(ResponseStatusCode::RequestHeaderFieldsTooLarge)
+            );
+
+            let mut stream = Packets::new(vec![POST], false);
+            assert_eq!(
+                parser::parser::<16, 90, 20, 250>(&mut stream),
+                Err(ResponseStatusCode::PayloadTooLarge)
+            );
+        }
+
+        #[test]
+        fn errors_are_answered_with_their_status() {
+            let long_target = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_TARGET_SIZE));
+
+            for (request, status) in [
+                (&b"GET /\r\n\r\n"[..], ResponseStatusCode::BadRequest),
+                (b"BREW /pot HTTP/1.1\r\n\r\n", ResponseStatusCode::MethodNotAllowed),
+                (long_target.as_bytes(), ResponseStatusCode::URITooLong),
+                (b"GET / HTTP/2.0\r\n\r\n", ResponseStatusCode::HTTPVersionNotSupported),
+                (b"GET / HTTP/1.1\r\nhost\r\n\r\n", ResponseStatusCode::BadRequest),
+                (b"POST / HTTP/1.1\r\ncontent-type:text/nope\r\ncontent-length:1\r\n\r\nx", ResponseStatusCode::BadRequest),
+                (b"POST / HTTP/1.1\r\nhost:localhost\r\n\r\n", ResponseStatusCode::LengthRequired),
+            ] {
+                let mut stream = Packets::new(vec![request], false);
+
+                assert_eq!(
+                    parser::parser::<64, 10000, 20, 250>(&mut stream),
+                    Err(status),
+                    "{}",
+                    String::from_utf8_lossy(request)
+                );
+            }
+        }
+
+        #[test]
+        fn malformed_or_folded_header_fields() {
+            let folded = b"GET / HTTP/1.1\r\nhost:localhost\r\nx-folded: a\r\n b\r\n\r\n";
+
+            for head in [&folded[..], b"GET / HTTP/1.1\r\nhost :localhost\r\n\r\n", b"GET / HTTP/1.1\r\nhost\r\n\r\n"] {
+                let mut stream = Packets::new(vec![head], false);
+
+                assert_eq!(parse(&mut stream), Err(ResponseStatusCode::BadRequest), "{}", String::from_utf8_lossy(head));
+            }
+
+            let mut stream = Packets::new(vec![folded], false);
+            let request = parser::parser_with::<16, 500, 20, 250>(&mut stream, &FieldLimits::default().set_unfold(true)).unwrap();
+
+            assert_eq!(request.headers.get("x-folded"), Some("a b"));
+        }
+
+        const CHUNKED: &[u8] = b"POST /form HTTP/1.1\r\nhost:localhost\r\ncontent-type:text/plain\r\ntransfer-encoding:chunked\r\n\r\n5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n";
+
+        fn expected_chunked() -> Result<Request, ResponseStatusCode> {
+            let request = Request::try_from(CHUNKED).unwrap();
+            assert_eq!(request.body.as_ref().unwrap().content, b"hello world");
+
+            Ok(request)
+        }
+
+        #[test]
+        fn chunked_body_split_at_any_byte() {
+            for split in 1..CHUNKED.len() {
+                let mut stream = Packets::new(vec![&CHUNKED[..split], &CHUNKED[split..]], false);
+
+                assert_eq!(parse(&mut stream), expected_chunked(), "split at {split}");
+            }
+        }
+
+        #[test]
+        fn bytes_past_the_last_chunk_are_handed_back() {
+            let next = b"GET / HTTP/1.1\r\n\r\n";
+            let mut stream = Packets::new(vec![&[CHUNKED, next].concat()], false);
+
+            assert_eq!(parse(&mut stream), expected_chunked());
+
+            let rest: Vec<u8> = stream.packets.iter().flatten().copied().collect();
+            assert_eq!([stream.unread, rest].concat(), next);
+        }
+
+        #[test]
+        fn chunked_body_larger_than_max_size() {
+            let head = b"POST / HTTP/1.1\r\ntransfer-encoding:chunked\r\n\r\n";
+
+            // rejected as soon as the size is read; before its data arrives
+            let mut stream = Packets::new(vec![head, b"1F4\r\n"], false);
+            assert_eq!(parse(&mut stream), Err(ResponseStatusCode::PayloadTooLarge));
+
+            let extended_chunks = b"1;a\r\nx\r\n".repeat(60);
+            let mut stream = Packets::new(vec![head, &extended_chunks], false);
+            assert_eq!(parse(&mut stream), Err(ResponseStatusCode::PayloadTooLarge));
+        }
+
+        #[test]
+        fn smuggling_and_malformed_chunks_are_rejected() {
+            for (request, status) in [
+                (
+                    &b"POST / HTTP/1.1\r\ncontent-length:4\r\ntransfer-encoding:chunked\r\n\r\n0\r\n\r\n"[..],
+                    ResponseStatusCode::BadRequest,
+                ),
+                (b"POST / HTTP/1.1\r\ntransfer-encoding:chunked\r\n\r\nfive\r\n", ResponseStatusCode::BadRequest),
+                (
+                    b"POST / HTTP/1.1\r\ntransfer-encoding:chunked\r\n\r\n1\r\nab\r\n",
+                    ResponseStatusCode::BadRequest,
+                ),
+                (
+                    b"POST / HTTP/1.1\r\ntransfer-encoding:gzip, chunked\r\n\r\n",
+                    ResponseStatusCode::NotImplemented,
+                ),
+            ] {
+                let mut stream = Packets::new(vec![request], true);
+
+                assert_eq!(parse(&mut stream), Err(status), "{}", String::from_utf8_lossy(request));
+            }
+        }
+
+        #[test]
+        fn truncated_chunked_body_is_rejected() {
+            let mut stream = Packets::new(vec![&CHUNKED[..CHUNKED.len() - 2]], true);
+
+            assert_eq!(parse(&mut stream), Err(ResponseStatusCode::BadRequest));
+        }
+
+        #[test]
+        fn binary_body_is_accepted() {
+            let content = b"\x89PNG\r\n\x1a\n\0\xff\r\n";
+            let request = [
+                format!("PUT /image.png HTTP/1.1\r\ncontent-type:image/png\r\ncontent-length:{}\r\n\r\n", content.len()).as_bytes(),
+                content,
+            ]
+            .concat();
+
+            let mut stream = Packets::new(request.chunks(7).collect(), false);
+
+            match parse(&mut stream) {
+                Ok(Request { method: Method::Put, body: Some(body), .. }) => assert_eq!(body.content, content),
+                other => panic!("{other:?}"),
+            }
+        }
+    }
+}
+
+mod action {
+    use std::{collections::HashMap, fs, path::PathBuf, sync::mpsc, thread};
+
+    use crate::{
+        http::{method::Method, request::Request, response::response_status_code::ResponseStatusCode, version::Version},
+        file::FileError,
+        pipeline::default::action::default_get_logic,
+        setting::{DomainPath, ServerSetting},
+        test_tools::{dir_env::DirEnv, file_env::FileEnv},
+    };
+
+    fn setting() -> ServerSetting {
+        ServerSetting {
+            address: String::from("localhost"),
+            port: 8080,
+            paths: HashMap::new(),
+            pipelines: None,
+            listeners: Vec::new(),
+        }
+    }
+
+    fn domain() -> DomainPath {
+        DomainPath {
+            path: String::from("localhost"),
+            allow: vec![String::from("html"), String::from("txt")],
+            tls: None,
+        }
+    }
+
+    #[test]
+    fn missing_host_is_rejected() {
+        let (utility_thread, _rx) = mpsc::channel();
+
+        let request = Request::new(Method::Get, "/", Version::Http1_1);
+        let response = default_get_logic(&request, &setting(), &utility_thread);
+
+        assert_eq!(response.unwrap_err(), ResponseStatusCode::BadRequest);
+    }
+
+    #[test]
+    fn http_1_0_without_host_is_served_from_address() {
+        let _folder = DirEnv::new("source/action_http_1_0");
+        let _file = FileEnv::new("source/action_http_1_0/index.html", "served");
+
+        let (utility_thread, rx) = mpsc::channel::<(PathBuf, mpsc::Sender<Result<Vec<u8>, FileError>>)>();
+
+        // reads every file requested; until the sender is dropped
+        thread::spawn(move || {
+            for (path, tx) in rx {
+                let _ = tx.send(fs::read(path).map_err(|_| FileError::FileDoesNotExist));
+            }
+        });
+
+        // HTTP/1.0 doesn't require a host; the domain of the server's address is used instead
+        let request = Request::new(Method::Get, "/", Version::Http1_0);
+
+        let response = default_get_logic(&request, &setting(), &utility_thread);
+        assert_eq!(response.unwrap_err(), ResponseStatusCode::NotFound);
+
+        let mut setting = setting();
+        setting.paths.insert(
+            String::from("localhost"),
+            DomainPath {
+                path: String::from("action_http_1_0"),
+                ..domain()
+            },
+        );
+
+        let response = default_get_logic(&request, &setting, &utility_thread).unwrap();
+        assert_eq!(response.body.unwrap().content, b"served");
+    }
+
+    #[test]
+    fn unknown_host_is_not_found() {
+        let (utility_thread, _rx) = mpsc::channel();
+
+        let mut setting = setting();
+        setting.paths.insert(String::from("localhost"), domain());
+
+        for version in [Version::Http1_0, Version::Http1_1] {
+            let mut request = Request::new(Method::Get, "/", version);
+            request.headers.append("host", "example.com");
+
+            let response = default_get_logic(&request, &setting, &utility_thread);
+            assert_eq!(response.unwrap_err(), ResponseStatusCode::NotFound);
+        }
+    }
+
+    #[test]
+    fn encoded_traversal_is_rejected() {
+        let (utility_thread, _rx) = mpsc::channel();
+
+        let mut setting = setting();
+        setting.paths.insert(String::from("localhost"), domain());
+
+        for target in ["/%2e%2e/%2e%2e/etc/passwd", "/..%2F..%2Fetc%2Fpasswd", "/%2E%2E%5C%2E%2E%5Cetc%5Cpasswd", "/index.html%00.txt"] {
+            let mut request = Request::new(Method::Get, target, Version::Http1_1);
+            request.headers.append("host", "localhost");
+
+            let response = default_get_logic(&request, &setting, &utility_thread);
+
+            assert_eq!(response.unwrap_err(), ResponseStatusCode::BadRequest, "{target}");
+        }
+    }
 }
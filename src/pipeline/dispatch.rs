@@ -0,0 +1,119 @@
+//! dispatch module defines how the server picks the pipeline that handles a new connection
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::queue::Queued;
+
+/// DispatchStrategy defines the variants of how connections are distributed between pipelines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DispatchStrategy {
+    /// pipelines take turns receiving connections
+    #[default]
+    RoundRobin,
+    /// the pipeline with the least queued work across all of its stages receives the connection
+    LeastLoaded,
+    /// two pipelines are picked at random and the less loaded one receives the connection
+    PowerOfTwoChoices,
+    /// connections from the same client IP are always sent to the same pipeline
+    ClientIpHash,
+}
+
+/// PipelineLoad is a live view of the amount of work queued in a pipeline
+///
+/// Reading the load does not take any stage locks.
+#[derive(Clone)]
+pub struct PipelineLoad {
+    pub(super) connections: Arc<AtomicUsize>,
+    pub(super) queues: Vec<Arc<dyn Queued>>,
+}
+
+impl PipelineLoad {
+    /// load returns the number of connections waiting in any of the pipeline's stage queues
+    pub fn load(&self) -> usize {
+        self.connections.load(Ordering::Acquire)
+            + self.queues.iter().map(|queue| queue.queued()).sum::<usize>()
+    }
+}
+
+/// Dispatcher selects pipelines following a DispatchStrategy
+pub(super) struct Dispatcher {
+    strategy: DispatchStrategy,
+    next: usize,
+    seed: u64,
+}
+
+impl Dispatcher {
+    pub fn new(strategy: DispatchStrategy) -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_nanos() as u64)
+            .unwrap_or_default();
+
+        Self {
+            strategy,
+            next: 0,
+            // xorshift state must be non-zero
+            seed: seed | 1,
+        }
+    }
+
+    /// select returns the index of the pipeline that should receive a connection from peer
+    pub fn select(&mut self, loads: &[PipelineLoad], peer: Option<IpAddr>) -> usize {
+        let pipelines = loads.len();
+
+        if pipelines <= 1 {
+            return 0;
+        }
+
+        match self.strategy {
+            DispatchStrategy::RoundRobin => self.round_robin(pipelines),
+            DispatchStrategy::LeastLoaded => loads
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, load)| load.load())
+                .map(|(index, _)| index)
+                .unwrap_or(0),
+            DispatchStrategy::PowerOfTwoChoices => {
+                let first = self.random(pipelines);
+                let second = (first + 1 + self.random(pipelines - 1)) % pipelines;
+
+                match loads[second].load() < loads[first].load() {
+                    true => second,
+                    false => first,
+                }
+            }
+            DispatchStrategy::ClientIpHash => match peer {
+                Some(peer) => {
+                    let mut hasher = DefaultHasher::new();
+                    peer.hash(&mut hasher);
+
+                    (hasher.finish() % pipelines as u64) as usize
+                }
+                None => self.round_robin(pipelines),
+            },
+        }
+    }
+
+    fn round_robin(&mut self, pipelines: usize) -> usize {
+        self.next = (self.next + 1) % pipelines;
+
+        self.next
+    }
+
+    fn random(&mut self, bound: usize) -> usize {
+        // xorshift64; only needs to be cheap & roughly uniform
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+
+        (self.seed % bound as u64) as usize
+    }
+}
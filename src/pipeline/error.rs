@@ -0,0 +1,77 @@
+//! error module defines the errors returned while building & running a server
+use std::{error::Error, fmt::Display, io};
+
+/// BuildError enum defines the ways a [Builder](super::builder::pipeline::Builder) can be misconfigured; preventing a pipeline from being built
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BuildError {
+    MissingParser,
+    MissingAction,
+    MissingCompression,
+    MissingUtilitySender,
+    MissingSettings,
+    /// a stage has been configured with zero workers
+    NoWorkers,
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let missing = match self {
+            BuildError::MissingParser => "parser",
+            BuildError::MissingAction => "action",
+            BuildError::MissingCompression => "compression",
+            BuildError::MissingUtilitySender => "utility sender",
+            BuildError::MissingSettings => "settings",
+            BuildError::NoWorkers => {
+                return write!(f, "Pipeline builder has a stage without workers")
+            }
+        };
+
+        write!(f, "Pipeline builder is missing {missing}")
+    }
+}
+
+impl Error for BuildError {}
+
+/// ServerError enum defines the failures that stop a [Server](super::Server) from running
+#[derive(Debug)]
+pub enum ServerError {
+    /// the server's builder is misconfigured
+    Build(BuildError),
+    /// the listener could not be bound to the configured address
+    Bind { address: String, source: io::Error },
+    /// the certificates of a TLS listener could not be loaded
+    Tls { address: String, reason: String },
+    /// the server's settings ask for zero pipelines
+    NoPipelines,
+}
+
+impl Display for ServerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServerError::Build(err) => write!(f, "{err}"),
+            ServerError::Bind { address, source } => {
+                write!(f, "Failed to bind listener to {address}: {source}")
+            }
+            ServerError::Tls { address, reason } => {
+                write!(f, "Failed to load certificates of listener {address}: {reason}")
+            }
+            ServerError::NoPipelines => write!(f, "Server settings must allow at least one pipeline"),
+        }
+    }
+}
+
+impl Error for ServerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ServerError::Build(err) => Some(err),
+            ServerError::Bind { source, .. } => Some(source),
+            ServerError::Tls { .. } | ServerError::NoPipelines => None,
+        }
+    }
+}
+
+impl From<BuildError> for ServerError {
+    fn from(err: BuildError) -> Self {
+        ServerError::Build(err)
+    }
+}
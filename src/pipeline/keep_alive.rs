@@ -0,0 +1,226 @@
+//! keep_alive module defines how connections are reused between requests
+use std::{
+    io::ErrorKind,
+    net::{SocketAddr, TcpStream},
+    sync::Arc,
+    time::Duration,
+};
+
+use crate::http::{
+    method::Method,
+    request::Request,
+    response::{response_status_code::ResponseStatusCode, Response},
+};
+
+use super::{
+    listener::Listener,
+    stream::{Buffered, Stream},
+};
+
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+
+/// KeepAlive defines how long & how often a connection can be reused after a response has been sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepAlive {
+    /// time an idle connection is kept open waiting for its next request
+    pub idle_timeout: Duration,
+    /// number of requests that can be served over a single connection
+    pub max_requests: usize,
+}
+
+impl Default for KeepAlive {
+    fn default() -> Self {
+        Self {
+            idle_timeout: Duration::from_secs(5),
+            max_requests: 100,
+        }
+    }
+}
+
+impl KeepAlive {
+    /// negotiate decides if a connection stays open after response is sent; and sets the response's Connection header to match
+    ///
+    /// The request's Connection header takes priority over the default of its HTTP version. The connection is only closed for a response the client can't tell the end of, see [framed].
+    ///
+    /// # return
+    /// true if the connection should be kept alive
+    pub(super) fn negotiate(
+        &self,
+        requests: usize,
+        request: &Result<Request, ResponseStatusCode>,
+        response: &mut Response,
+    ) -> bool {
+        let keep_alive = match request {
+            Ok(Request { method, headers, version, .. }) => {
+                let connection: Vec<&str> = headers
+                    .get_all("connection")
+                    .flat_map(|value| value.split(',').map(str::trim))
+                    .collect();
+
+                let requested = match (
+                    connection.iter().any(|token| token.eq_ignore_ascii_case("close")),
+                    connection.iter().any(|token| token.eq_ignore_ascii_case("keep-alive")),
+                ) {
+                    (true, _) => false,
+                    (false, true) => true,
+                    (false, false) => version.keep_alive(),
+                };
+
+                requested && requests < self.max_requests && framed(method, response)
+            }
+            // the rest of a malformed request may still be unread
+            Err(_) => false,
+        };
+
+        match keep_alive {
+            true => {
+                response
+                    .header
+                    .insert(String::from("Connection"), String::from("keep-alive"));
+                response.header.insert(
+                    String::from("Keep-Alive"),
+                    format!(
+                        "timeout={}, max={}",
+                        self.idle_timeout.as_secs(),
+                        self.max_requests - requests
+                    ),
+                );
+            }
+            false => {
+                response
+                    .header
+                    .insert(String::from("Connection"), String::from("close"));
+            }
+        }
+
+        keep_alive
+    }
+}
+
+/// framed checks if the client can tell where response ends without the connection being closed
+///
+/// A response with a body is sent with its Content-Length. Responses to HEAD requests, & 1xx, 204 & 304 responses, end with their header section. Any other response without a body is given a Content-Length of 0; unless it declares a Transfer-Encoding, as the sender writes no body for it to end.
+fn framed(method: &Method, response: &mut Response) -> bool {
+    if response.body.is_some() || *method == Method::Head {
+        return true;
+    }
+
+    let informational = (100..200).contains(&response.status.get_code());
+
+    if informational || matches!(response.status, ResponseStatusCode::NoContent | ResponseStatusCode::NotModified) {
+        return true;
+    }
+
+    let declared = |name: &str| response.header.keys().any(|key| key.eq_ignore_ascii_case(name));
+
+    if declared("transfer-encoding") {
+        return false;
+    }
+
+    if !declared("content-length") {
+        response
+            .header
+            .insert(String::from("Content-Length"), String::from("0"));
+    }
+
+    true
+}
+
+/// Connection is a client connection passed between the stages of a pipeline
+#[derive(Debug)]
+pub struct Connection {
+    pub(super) stream: Box<dyn Stream>,
+    /// number of responses generated for the connection
+    pub(super) requests: usize,
+    /// true if the connection should be handed back to the parser once its response is sent
+    pub(super) keep_alive: bool,
+    /// listener that accepted the connection
+    pub(super) listener: Option<Arc<Listener>>,
+}
+
+impl Connection {
+    /// accepted creates a connection accepted through listener
+    pub(super) fn accepted(stream: Box<dyn Stream>, listener: Arc<Listener>) -> Self {
+        Self {
+            listener: Some(listener),
+            ..Self::from(stream)
+        }
+    }
+
+    /// listener returns the listener that accepted the connection; None if the connection was passed to a pipeline directly
+    pub fn listener(&self) -> Option<&Listener> {
+        self.listener.as_deref()
+    }
+
+    /// peer_addr returns the address of the client; None for clients without an ip address, e.g. over a unix socket
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.stream.peer_addr()
+    }
+
+    /// requests returns the number of requests received over the connection, including the current one once it has reached the action stage
+    pub fn requests(&self) -> usize {
+        self.requests
+    }
+}
+
+impl From<Box<dyn Stream>> for Connection {
+    fn from(stream: Box<dyn Stream>) -> Self {
+        Self {
+            // holds the start of a pipelined request read along with the one before it
+            stream: Box::new(Buffered::new(stream)),
+            requests: 0,
+            keep_alive: false,
+            listener: None,
+        }
+    }
+}
+
+impl From<TcpStream> for Connection {
+    fn from(stream: TcpStream) -> Self {
+        Self::from(Box::new(stream) as Box<dyn Stream>)
+    }
+}
+
+#[cfg(unix)]
+impl From<UnixStream> for Connection {
+    fn from(stream: UnixStream) -> Self {
+        Self::from(Box::new(stream) as Box<dyn Stream>)
+    }
+}
+
+impl From<Connection> for Box<dyn Stream> {
+    fn from(connection: Connection) -> Self {
+        connection.stream
+    }
+}
+
+/// Readiness is the state of an idle connection
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum Readiness {
+    /// the client has sent data
+    Readable,
+    /// the client has not sent anything
+    Idle,
+    /// the client has closed the connection or the connection failed
+    Closed,
+}
+
+/// readiness checks if an idle connection has data waiting without consuming any of it
+pub(super) fn readiness(stream: &mut dyn Stream) -> Readiness {
+    if stream.set_nonblocking(true).is_err() {
+        return Readiness::Closed;
+    }
+
+    let readiness = match stream.peek_len() {
+        Ok(0) => Readiness::Closed,
+        Ok(_) => Readiness::Readable,
+        Err(err) if err.kind() == ErrorKind::WouldBlock => Readiness::Idle,
+        Err(_) => Readiness::Closed,
+    };
+
+    match stream.set_nonblocking(false) {
+        Ok(()) => readiness,
+        Err(_) => Readiness::Closed,
+    }
+}
@@ -0,0 +1,494 @@
+//! listener module defines the sockets a server accepts connections on
+use std::{
+    collections::HashMap,
+    io,
+    net::{SocketAddr, TcpListener, ToSocketAddrs},
+    path::Path,
+    sync::{
+        atomic::Ordering,
+        mpsc::{self, SyncSender, TrySendError},
+        Arc, Mutex, RwLock,
+    },
+    thread,
+    time::Duration,
+};
+
+#[cfg(unix)]
+use std::{
+    fs::{self, Permissions},
+    os::unix::{
+        fs::{FileTypeExt, PermissionsExt},
+        io::{AsFd, AsRawFd, BorrowedFd, OwnedFd},
+        net::{UnixListener, UnixStream},
+    },
+    path::PathBuf,
+};
+
+use log::{error, warn};
+use rustls::ServerConfig;
+use socket2::{Domain, Type};
+
+use crate::{
+    http::{request::Request, response::response_status_code::ResponseStatusCode},
+    setting::{DomainPath, ListenerSetting},
+};
+
+use super::{
+    dispatch::Dispatcher,
+    error::ServerError,
+    keep_alive::Connection,
+    pool::Routes,
+    shutdown::ShutdownHandle,
+    stream::Stream,
+    tls::{self, TlsStream},
+};
+
+// number of connections the OS queues before they are accepted
+const BACKLOG: i32 = 128;
+
+// time waited before accepting again after a failed accept
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+// number of TLS handshakes a listener runs at once
+const HANDSHAKE_WORKERS: usize = 8;
+
+// number of accepted connections waiting for a handshake; further connections are dropped
+const HANDSHAKE_QUEUE: usize = BACKLOG as usize;
+
+/// Listener is a socket a server accepts connections on; shared by every [Connection] accepted through it
+#[derive(Debug)]
+pub struct Listener {
+    index: usize,
+    setting: ListenerSetting,
+    local_addr: Option<SocketAddr>,
+    tls: Option<Arc<ServerConfig>>,
+}
+
+impl Listener {
+    /// new creates a listener that is not bound to a socket; e.g. to pass connections directly to a pipeline. TLS is never terminated by an unbound listener
+    pub fn new(index: usize, setting: ListenerSetting) -> Self {
+        Self {
+            index,
+            setting,
+            local_addr: None,
+            tls: None,
+        }
+    }
+
+    /// index returns the position of the listener in the server's listeners
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// setting returns the options the listener was bound with
+    pub fn setting(&self) -> &ListenerSetting {
+        &self.setting
+    }
+
+    /// local_addr returns the address the listener is bound to; None for unix sockets
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    /// path returns the path of the unix socket the listener is bound to
+    pub fn path(&self) -> Option<&Path> {
+        self.setting.path.as_deref()
+    }
+
+    /// is_tls checks if the listener terminates TLS on the connections it accepts
+    pub fn is_tls(&self) -> bool {
+        self.tls.is_some()
+    }
+
+    /// route applies the listener's options to a parsed request
+    ///
+    /// Requests without a Host header are addressed to the default host. Requests addressed to a host the listener doesn't allow are rejected with 421 Misdirected Request.
+    pub(super) fn route(
+        &self,
+        request: Result<Request, ResponseStatusCode>,
+    ) -> Result<Request, ResponseStatusCode> {
+        let mut request = request?;
+
+        if let (false, Some(host)) = (request.headers.contains("host"), &self.setting.default_host) {
+            request.headers.insert("host", host);
+        }
+
+        match request.headers.get("host") {
+            Some(host) if !self.setting.allows_host(host) => Err(ResponseStatusCode::MisdirectedRequest),
+            _ => Ok(request),
+        }
+    }
+}
+
+/// Socket is a bound socket connections are accepted from
+pub(super) enum Socket {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixSocket),
+}
+
+impl Socket {
+    fn accept(&self) -> io::Result<Box<dyn Stream>> {
+        match self {
+            Socket::Tcp(socket) => socket.accept().map(|(stream, _)| stream.into()),
+            #[cfg(unix)]
+            Socket::Unix(socket) => socket.listener.accept().map(|(stream, _)| stream.into()),
+        }
+    }
+
+    /// handed_over leaves the socket's file in place once closed; as the socket is served by another process
+    #[cfg(unix)]
+    pub(super) fn handed_over(&mut self) {
+        if let Socket::Unix(socket) = self {
+            socket.path = None;
+        }
+    }
+}
+
+#[cfg(unix)]
+impl AsFd for Socket {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        match self {
+            Socket::Tcp(socket) => socket.as_fd(),
+            Socket::Unix(socket) => socket.listener.as_fd(),
+        }
+    }
+}
+
+/// UnixSocket is a bound unix domain socket; its file is removed once the socket is closed
+#[cfg(unix)]
+pub(super) struct UnixSocket {
+    listener: UnixListener,
+    /// None for inherited sockets; whose file belongs to the process that bound them
+    path: Option<PathBuf>,
+}
+
+#[cfg(unix)]
+impl Drop for UnixSocket {
+    fn drop(&mut self) {
+        if let Some(path) = &self.path {
+            if let Err(err) = fs::remove_file(path) {
+                warn!("Failed to remove unix socket {}: {err}", path.display());
+            }
+        }
+    }
+}
+
+/// bind opens the socket described by setting; loading the certificates of the listener & of the domains in paths if it terminates TLS
+///
+/// # Errors
+/// A ServerError::Tls is returned if a certificate can't be loaded; and a ServerError::Bind if the socket can't be bound
+pub(super) fn bind(
+    index: usize,
+    setting: ListenerSetting,
+    paths: &HashMap<String, DomainPath>,
+) -> Result<(Socket, Arc<Listener>), ServerError> {
+    // loaded first so a bad certificate doesn't leave a unix socket's file behind
+    let tls = tls_config(&setting, paths)?;
+
+    let bound = match &setting.path {
+        Some(path) => bind_unix(path, setting.mode),
+        None => bind_tcp(&setting),
+    };
+
+    let (socket, local_addr) = bound.map_err(|source| ServerError::Bind {
+        address: setting.name(),
+        source,
+    })?;
+
+    let listener = Listener {
+        index,
+        setting,
+        local_addr,
+        tls,
+    };
+
+    Ok((socket, Arc::new(listener)))
+}
+
+/// adopt serves a listening socket bound by the process that started the server; with the options of setting, or none if None
+///
+/// The address, port & path of setting are replaced by the ones the socket is bound to.
+///
+/// # Errors
+/// A ServerError::Tls is returned if a certificate can't be loaded; and a ServerError::Bind if fd isn't a listening stream socket
+#[cfg(unix)]
+pub(super) fn adopt(
+    index: usize,
+    setting: Option<ListenerSetting>,
+    fd: OwnedFd,
+    paths: &HashMap<String, DomainPath>,
+) -> Result<(Socket, Arc<Listener>), ServerError> {
+    let mut setting = setting.unwrap_or_else(|| ListenerSetting::new(String::new(), 0));
+
+    let name = format!("inherited fd {}", fd.as_raw_fd());
+    let (socket, local_addr, path) = inherit(fd).map_err(|source| ServerError::Bind {
+        address: name,
+        source,
+    })?;
+
+    match local_addr {
+        Some(addr) => {
+            setting.address = addr.ip().to_string();
+            setting.port = addr.port();
+            setting.path = None;
+        }
+        None => setting.path = path,
+    }
+
+    let listener = Listener {
+        index,
+        tls: tls_config(&setting, paths)?,
+        setting,
+        local_addr,
+    };
+
+    Ok((socket, Arc::new(listener)))
+}
+
+/// inherit checks fd is a listening stream socket that can be woken on shutdown
+#[cfg(unix)]
+fn inherit(fd: OwnedFd) -> io::Result<(Socket, Option<SocketAddr>, Option<PathBuf>)> {
+    let socket = socket2::Socket::from(fd);
+
+    if socket.r#type()? != Type::STREAM {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "not a stream socket"));
+    }
+
+    #[cfg(any(target_os = "android", target_os = "freebsd", target_os = "fuchsia", target_os = "linux"))]
+    if !socket.is_listener()? {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "not a listening socket"));
+    }
+
+    // supervisors may pass nonblocking sockets; accept has to block until a connection arrives
+    socket.set_nonblocking(false)?;
+
+    let address = socket.local_addr()?;
+
+    if let Some(addr) = address.as_socket() {
+        return Ok((Socket::Tcp(socket.into()), Some(addr), None));
+    }
+
+    match address.as_pathname().map(Path::to_path_buf) {
+        Some(path) => {
+            let socket = UnixSocket {
+                listener: UnixListener::from(OwnedFd::from(socket)),
+                path: None,
+            };
+
+            Ok((Socket::Unix(socket), None, Some(path)))
+        }
+        // the listener couldn't be connected to on shutdown
+        None => Err(io::Error::new(io::ErrorKind::Unsupported, "unnamed unix sockets aren't supported")),
+    }
+}
+
+/// tls_config loads the TLS configuration of setting; None if the listener doesn't terminate TLS
+fn tls_config(
+    setting: &ListenerSetting,
+    paths: &HashMap<String, DomainPath>,
+) -> Result<Option<Arc<ServerConfig>>, ServerError> {
+    match &setting.tls {
+        Some(certificate) => tls::server_config(certificate, paths)
+            .map(Some)
+            .map_err(|reason| ServerError::Tls {
+                address: setting.name(),
+                reason,
+            }),
+        None => Ok(None),
+    }
+}
+
+/// bind_tcp binds the first address setting's address resolves to that can be bound
+fn bind_tcp(setting: &ListenerSetting) -> io::Result<(Socket, Option<SocketAddr>)> {
+    // IPv6 addresses may be written in brackets, as they are in urls
+    let host = setting.address.trim_start_matches('[').trim_end_matches(']');
+
+    let mut last_error = io::Error::new(io::ErrorKind::AddrNotAvailable, "address did not resolve");
+
+    for addr in (host, setting.port).to_socket_addrs()? {
+        match bind_socket(addr, setting.ipv6_only) {
+            Ok(socket) => {
+                let local_addr = socket.local_addr().ok();
+
+                return Ok((Socket::Tcp(socket), local_addr));
+            }
+            Err(err) => last_error = err,
+        }
+    }
+
+    Err(last_error)
+}
+
+fn bind_socket(addr: SocketAddr, ipv6_only: bool) -> io::Result<TcpListener> {
+    let socket = socket2::Socket::new(Domain::for_address(addr), Type::STREAM, None)?;
+
+    // matches std; lets a restarted server bind while old connections are in TIME_WAIT
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+
+    // set explicitly as the OS default for dual-stack sockets varies
+    if addr.is_ipv6() {
+        socket.set_only_v6(ipv6_only)?;
+    }
+
+    socket.bind(&addr.into())?;
+    socket.listen(BACKLOG)?;
+
+    Ok(socket.into())
+}
+
+/// bind_unix binds a unix domain socket at path; replacing the file of a socket no longer in use
+#[cfg(unix)]
+fn bind_unix(path: &Path, mode: Option<u32>) -> io::Result<(Socket, Option<SocketAddr>)> {
+    if let Ok(metadata) = fs::symlink_metadata(path) {
+        // a server that didn't shut down cleanly leaves its socket's file behind
+        match metadata.file_type().is_socket() && UnixStream::connect(path).is_err() {
+            true => fs::remove_file(path)?,
+            false => return Err(io::Error::new(io::ErrorKind::AddrInUse, "path is in use")),
+        }
+    }
+
+    let socket = UnixSocket {
+        listener: UnixListener::bind(path)?,
+        path: Some(path.to_path_buf()),
+    };
+
+    // the socket is dropped & its file removed if the permissions can't be set
+    if let Some(mode) = mode {
+        fs::set_permissions(path, Permissions::from_mode(mode))?;
+    }
+
+    Ok((Socket::Unix(socket), None))
+}
+
+#[cfg(not(unix))]
+fn bind_unix(_: &Path, _: Option<u32>) -> io::Result<(Socket, Option<SocketAddr>)> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "unix sockets are only supported on unix"))
+}
+
+/// accept sends the connections accepted by socket to the server's pipelines, until the server stops accepting connections
+///
+/// The handshakes of a TLS listener are completed by its [Handshakes] before a connection is sent; so a slow client holds up neither the listener nor a parser.
+///
+/// # return
+/// socket; still open so it can be handed over to a new process
+pub(super) fn accept(
+    socket: Socket,
+    listener: Arc<Listener>,
+    routes: Arc<RwLock<Routes>>,
+    dispatcher: Arc<Mutex<Dispatcher>>,
+    shutdown: ShutdownHandle,
+) -> Socket {
+    let handshakes = listener
+        .tls
+        .as_ref()
+        .map(|config| Handshakes::new(config.clone(), listener.clone(), routes.clone(), dispatcher.clone()));
+
+    loop {
+        match socket.accept() {
+            Ok(stream) => {
+                if shutdown.is_wake(listener.local_addr, &*stream) {
+                    // closed before the socket is handed back; a client that stopped the listener isn't left waiting
+                    drop(stream);
+                    break;
+                }
+
+                match &handshakes {
+                    Some(handshakes) => handshakes.push(stream),
+                    None => dispatch(stream, &listener, &routes, &dispatcher),
+                }
+            }
+            // accept failures (e.g. running out of file descriptors) only affect the connection being accepted
+            Err(err) => {
+                error!("Failed to accept connection: {err}");
+
+                // a failure that persists would otherwise be retried in a busy loop; stopping still interrupts the wait
+                if shutdown.wait_for_shutdown(ACCEPT_BACKOFF) || shutdown.is_stopping() {
+                    break;
+                }
+            }
+        }
+    }
+
+    socket
+}
+
+/// dispatch sends stream to the pipeline selected by dispatcher
+fn dispatch(stream: Box<dyn Stream>, listener: &Arc<Listener>, routes: &RwLock<Routes>, dispatcher: &Mutex<Dispatcher>) {
+    let peer = stream.peer_addr().map(|addr| addr.ip());
+    let routes = routes.read().unwrap();
+
+    let i1 = dispatcher.lock().unwrap().select(&routes.loads, peer);
+
+    routes.loads[i1].connections.fetch_add(1, Ordering::AcqRel);
+
+    if routes.senders[i1].send(Connection::accepted(stream, listener.clone())).is_err() {
+        routes.loads[i1].connections.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Handshakes is the pool of threads completing the TLS handshakes of the connections accepted by a listener; each connection is dispatched once its handshake is complete
+///
+/// The threads stop once the pool is dropped & the connections queued have been handled.
+struct Handshakes {
+    queue: SyncSender<Box<dyn Stream>>,
+}
+
+impl Handshakes {
+    fn new(
+        config: Arc<ServerConfig>,
+        listener: Arc<Listener>,
+        routes: Arc<RwLock<Routes>>,
+        dispatcher: Arc<Mutex<Dispatcher>>,
+    ) -> Self {
+        let (queue, rx) = mpsc::sync_channel::<Box<dyn Stream>>(HANDSHAKE_QUEUE);
+        let rx = Arc::new(Mutex::new(rx));
+
+        for _ in 0..HANDSHAKE_WORKERS {
+            let rx = rx.clone();
+            let config = config.clone();
+            let listener = listener.clone();
+            let routes = routes.clone();
+            let dispatcher = dispatcher.clone();
+
+            thread::spawn(move || loop {
+                let stream = {
+                    let rx = rx.lock().unwrap();
+
+                    rx.recv()
+                };
+
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => break,
+                };
+
+                let mut stream = match TlsStream::new(stream, config.clone()) {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        error!("Failed to start TLS: {err}");
+                        continue;
+                    }
+                };
+
+                // the whole handshake has a deadline; so a client stalling it only holds the thread until then
+                if let Err(err) = stream.handshake() {
+                    warn!("Handshake failed: {err}");
+                    continue;
+                }
+
+                dispatch(Box::new(stream), &listener, &routes, &dispatcher);
+            });
+        }
+
+        Self { queue }
+    }
+
+    /// push queues stream for its handshake; the connection is dropped if the queue is full
+    fn push(&self, stream: Box<dyn Stream>) {
+        if let Err(TrySendError::Full(_)) = self.queue.try_send(stream) {
+            warn!("Handshake queue is full; connection dropped");
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! metrics module defines counters that are shared between pipeline threads and the server
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// StageMetrics counts how often a stage's input queue overflowed, by the outcome of its overflow policy
+#[derive(Debug, Default)]
+pub struct StageMetrics {
+    blocked: AtomicU64,
+    rejected: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl StageMetrics {
+    /// blocked returns the number of times a producer waited for space in the queue
+    pub fn blocked(&self) -> u64 {
+        self.blocked.load(Ordering::Relaxed)
+    }
+
+    /// rejected returns the number of connections refused with a 503 because the queue was full
+    pub fn rejected(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+
+    /// dropped returns the number of queued connections evicted to make space for newer ones
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn record_blocked(&self) {
+        self.blocked.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn record_rejected(&self) {
+        self.rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn record_dropped(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// WorkerMetrics tracks how much of its lifetime a single worker of a stage has spent handling work
+///
+/// A worker's metrics are kept when it is replaced during recovery; so utilisation covers every thread that filled the worker's place.
+#[derive(Debug)]
+pub struct WorkerMetrics {
+    created: Instant,
+    busy: AtomicU64,
+    processed: AtomicU64,
+    restarts: AtomicU64,
+}
+
+impl Default for WorkerMetrics {
+    fn default() -> Self {
+        Self {
+            created: Instant::now(),
+            busy: AtomicU64::new(0),
+            processed: AtomicU64::new(0),
+            restarts: AtomicU64::new(0),
+        }
+    }
+}
+
+impl WorkerMetrics {
+    /// busy returns the total time the worker has spent handling work, including waiting on a full output queue
+    pub fn busy(&self) -> Duration {
+        Duration::from_nanos(self.busy.load(Ordering::Relaxed))
+    }
+
+    /// processed returns the number of connections the worker has handled
+    pub fn processed(&self) -> u64 {
+        self.processed.load(Ordering::Relaxed)
+    }
+
+    /// restarts returns the number of times the worker's thread has been replaced after stopping
+    pub fn restarts(&self) -> u64 {
+        self.restarts.load(Ordering::Relaxed)
+    }
+
+    /// utilisation returns the fraction of time, between 0 and 1, the worker has been busy since it was created
+    pub fn utilisation(&self) -> f64 {
+        let elapsed = self.created.elapsed().as_secs_f64();
+
+        if elapsed == 0.0 {
+            return 0.0;
+        }
+
+        (self.busy().as_secs_f64() / elapsed).min(1.0)
+    }
+
+    /// start marks the worker as busy until the returned guard is dropped
+    pub(super) fn start(&self) -> Busy<'_> {
+        Busy {
+            metrics: self,
+            since: Instant::now(),
+        }
+    }
+
+    pub(super) fn record_restart(&self) {
+        self.restarts.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Busy records the time a worker spent on one connection when dropped; including when the worker panics
+pub(super) struct Busy<'a> {
+    metrics: &'a WorkerMetrics,
+    since: Instant,
+}
+
+impl Drop for Busy<'_> {
+    fn drop(&mut self) {
+        let busy = u64::try_from(self.since.elapsed().as_nanos()).unwrap_or(u64::MAX);
+
+        self.metrics.busy.fetch_add(busy, Ordering::Relaxed);
+        self.metrics.processed.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// PipelineMetrics groups the metrics of each stage in a pipeline
+#[derive(Debug, Default)]
+pub struct PipelineMetrics {
+    pub action: Arc<StageMetrics>,
+    pub compression: Arc<StageMetrics>,
+    pub sender: Arc<StageMetrics>,
+    /// metrics of each custom stage's input queue by stage name; request stages followed by response stages
+    pub stages: Vec<(String, Arc<StageMetrics>)>,
+    /// metrics of each worker by stage name; in the order work flows through the pipeline
+    pub workers: Vec<(String, Vec<Arc<WorkerMetrics>>)>,
+}
@@ -1,28 +1,86 @@
 use std::{
-    net::{TcpListener, TcpStream},
-    sync::{mpsc::Sender},
+    collections::HashMap,
+    sync::{mpsc::Sender, Arc, Mutex, RwLock},
     thread::{JoinHandle, self},
+    time::{Duration, Instant},
 };
 
-use log::{trace, error};
+use log::{error, info, warn};
 
-use crate::setting::ServerSetting;
+use crate::setting::{DomainPath, ListenerSetting, ServerSetting};
 
-use self::{builder::pipeline::Builder, pipeline::Pipeline};
+#[cfg(unix)]
+use std::{
+    io,
+    os::fd::{AsRawFd, OwnedFd},
+};
+
+use self::{
+    builder::pipeline::Builder,
+    dispatch::Dispatcher,
+    listener::Socket,
+    pool::{PipelinePool, Routes},
+    scale::Scaler,
+};
+
+#[cfg(unix)]
+pub use self::activation::{listen_fds, UPGRADE_PID};
+
+pub use self::{
+    dispatch::{DispatchStrategy, PipelineLoad},
+    error::{BuildError, ServerError},
+    keep_alive::{Connection, KeepAlive},
+    listener::Listener,
+    metrics::{PipelineMetrics, StageMetrics, WorkerMetrics},
+    overflow::OverflowPolicy,
+    reload::Reload,
+    scale::{AutoScale, ScaleHandle},
+    shutdown::ShutdownHandle,
+    stage::{Action, Compressor, Parser, RequestStage, ResponseStage},
+    stream::Stream,
+};
+
+const RECOVERY_INTERVAL: Duration = Duration::from_millis(25);
 
 #[cfg(test)]
 //#[cfg(all(feature = "default_impl", test))]
 mod tests;
 
+#[cfg(unix)]
+mod activation;
 pub mod builder;
 mod component;
+mod dispatch;
+mod error;
+mod keep_alive;
+mod listener;
+mod metrics;
+mod overflow;
+#[allow(clippy::module_inception)]
 mod pipeline;
+mod pool;
+mod queue;
+mod reload;
+mod scale;
+mod shutdown;
+mod stage;
+mod stream;
+mod tls;
 
 //#[cfg(feature = "default_impl")]
 pub mod default;
 
 pub struct Server<U: Clone + Send + 'static> {
     builder: Builder<U>,
+    shutdown: ShutdownHandle,
+    metrics: Arc<RwLock<Vec<Arc<PipelineMetrics>>>>,
+    dispatch: DispatchStrategy,
+    scale: ScaleHandle,
+    autoscale: Option<AutoScale>,
+    reload: Option<Reload>,
+    // listening sockets served in place of binding the first listeners
+    #[cfg(unix)]
+    inherited: Vec<OwnedFd>,
     _utility_thread: (Sender<U>, JoinHandle<()>),
 }
 
@@ -36,82 +94,307 @@ impl<U: Clone + Send + 'static> Server<U> {
         let builder = builder.set_settings(settings);
         Server {
             builder,
+            shutdown: ShutdownHandle::default(),
+            metrics: Arc::new(RwLock::new(Vec::new())),
+            dispatch: DispatchStrategy::default(),
+            scale: ScaleHandle::default(),
+            autoscale: None,
+            reload: None,
+            #[cfg(unix)]
+            inherited: Vec::new(),
             _utility_thread: utility_thread,
         }
     }
 
-    pub fn run<const PIPELINES: usize>(&self) {
-        //build pipeline
-        let (senders, mut pipes) = {
+    /// try_new creates a new server; checking the builder can build pipelines before the server is run
+    ///
+    /// # Errors
+    /// A BuildError naming the missing stage or resource is returned
+    pub fn try_new(
+        settings: ServerSetting,
+        utility_thread: (Sender<U>, JoinHandle<()>),
+        builder: Builder<U>,
+    ) -> Result<Server<U>, BuildError> {
+        let server = Server::new(settings, utility_thread, builder);
+
+        server.builder.validate()?;
+
+        Ok(server)
+    }
+
+    /// set_dispatch sets the strategy used to pick which pipeline handles a new connection
+    pub fn set_dispatch(mut self, strategy: DispatchStrategy) -> Self {
+        self.dispatch = strategy;
 
-            let mut sender: Vec<Sender<TcpStream>> = Vec::new();
-            let mut pipes: Vec<Pipeline> = Vec::new();
+        self
+    }
 
-            (0..PIPELINES).map(|_| self.builder.build())
-                .for_each(|(s,p)| {
-                    sender.push(s.clone());
-                    pipes.push(p);
-                });
+    /// shutdown_handle returns a handle that can stop a running server from another thread
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
 
-            let sender : [Sender<TcpStream>; PIPELINES] =  sender.try_into().unwrap();
-            let pipes : [Pipeline; PIPELINES] =  pipes.try_into().unwrap();
+    /// set_autoscale grows & shrinks the number of pipelines of the running server following policy
+    pub fn set_autoscale(mut self, policy: AutoScale) -> Self {
+        self.autoscale = Some(policy);
 
-            (sender, pipes)
-        };
-        let mut i1: usize = 0; //should be replaced with min heap and used to select the least busy pipeline
-
-        // initialize tcp listener
-        let listener = {
-            let listener = TcpListener::bind({
-                let host = &(*self.builder.settings.as_ref().unwrap())
-                    .read()
-                    .unwrap()
-                    .address;
-                let port = &(*self.builder.settings.as_ref().unwrap())
-                    .read()
-                    .unwrap()
-                    .port;
-
-                format!("{}:{}", host, port)
-            });
-
-            let listener = match listener {
-                Ok(listener) => listener,
-                Err(err) => {
-                    panic!("Failed to start server: {err}");
-                },
-            };
-
-            listener
+        self
+    }
+
+    /// set_reload reloads the server's settings while running; see [Reload]
+    pub fn set_reload(mut self, reload: Reload) -> Self {
+        self.reload = Some(reload);
+
+        self
+    }
+
+    /// set_inherited serves listening sockets bound by another process; e.g. taken from a supervisor with [listen_fds], or from a descriptor with `OwnedFd::from_raw_fd`
+    ///
+    /// The sockets are served in place of binding the listeners in the settings, in order; keeping the options of the listener they replace. Sockets beyond the listeners in the settings are served without options. Every socket stays open until the server is dropped.
+    #[cfg(unix)]
+    pub fn set_inherited(mut self, sockets: Vec<OwnedFd>) -> Self {
+        self.inherited = sockets;
+
+        self
+    }
+
+    /// scale_handle returns a handle that can change the number of pipelines of a running server from another thread
+    pub fn scale_handle(&self) -> ScaleHandle {
+        self.scale.clone()
+    }
+
+    /// metrics returns the metrics of every pipeline in the running server
+    pub fn metrics(&self) -> Vec<Arc<PipelineMetrics>> {
+        self.metrics.read().unwrap().clone()
+    }
+
+    /// run serves connections until a shutdown is requested through the server's [ShutdownHandle]
+    ///
+    /// The server starts with the number of pipelines set in its settings, or one per available core if unset. The number of pipelines can be changed while running through a [ScaleHandle] or an [AutoScale] policy.
+    ///
+    /// Every listener in the settings is accepted on its own thread; all of them feed the same pipelines. TLS handshakes are completed on threads of the listener before a connection is sent to a pipeline, with the certificates read when the listeners are bound.
+    ///
+    /// # Errors
+    /// A ServerError is returned if the pipelines cannot be built or a listener cannot be bound. No pipeline threads are left running on failure.
+    pub fn run(&self) -> Result<(), ServerError> {
+        let result = self.serve();
+
+        // waiters are released even if the server failed to start
+        self.shutdown.stopped();
+
+        result
+    }
+
+    fn serve(&self) -> Result<(), ServerError> {
+        // checked up front so a misconfigured server fails before any thread is spawned
+        self.builder.validate()?;
+
+        let (listeners, paths, pipelines) = {
+            let settings = self.builder.settings.as_ref().ok_or(BuildError::MissingSettings)?;
+            let settings = settings.read().unwrap();
+
+            (
+                settings.effective_listeners(),
+                settings.paths.clone(),
+                scale::pipeline_count(settings.pipelines),
+            )
         };
 
-        let builder = self.builder.clone();
-        let _recovery_thread = {
+        if pipelines == 0 {
+            return Err(ServerError::NoPipelines);
+        }
+
+        // bind every listener before any thread is spawned; listeners bound before a failure are closed on return
+        let mut sockets = Vec::with_capacity(listeners.len());
+
+        for (socket, listener) in self.open_listeners(listeners, &paths)? {
+            let index = listener.index();
+
+            match (listener.local_addr(), listener.path()) {
+                (Some(addr), _) => self.shutdown.listening_on(addr),
+                (None, Some(path)) => self.shutdown.listening_on_path(path.to_path_buf()),
+                (None, None) => warn!("Failed to get address of listener {index}"),
+            }
+
+            sockets.push((socket, listener));
+        }
+
+        //build pipelines
+        let routes = Arc::new(RwLock::new(Routes::default()));
+        let mut pool = PipelinePool::new(self.builder.clone(), pipelines, routes.clone(), self.metrics.clone())?;
+        self.scale.set_pipelines(pool.len());
+
+        let dispatcher = Arc::new(Mutex::new(Dispatcher::new(self.dispatch)));
+
+        let supervisor_thread = {
+            let shutdown = self.shutdown.clone();
+            let scale = self.scale.clone();
+            let mut scaler = Scaler::new(self.scale.clone(), self.autoscale);
+
             thread::spawn(move || {
-                loop {
-                    for pipe in &mut pipes{
-                        if !pipe.pipeline_state() {
-                            error!("{pipe} failure");
-                            let _ = builder.fix(pipe);
+                // health check & resize pipelines periodically; sleeping on the shutdown signal so exit is immediate
+                while !shutdown.wait_for_shutdown(RECOVERY_INTERVAL) {
+                    pool.recover();
+
+                    if let Some(target) = scaler.target(pool.len(), pool.load()) {
+                        info!("Resizing from {} to {target} pipelines", pool.len());
+
+                        if let Err(err) = pool.resize(target) {
+                            error!("Failed to resize pipelines: {err}");
                         }
+
+                        scale.set_pipelines(pool.len());
                     }
                 }
+
+                pool
             })
         };
 
-        for stream in listener.incoming() {
-            i1 = (i1 + 1) % PIPELINES;
+        let reload_thread = match (&self.reload, &self.builder.settings) {
+            (Some(reload), Some(settings)) => {
+                let reload = reload.clone();
+                let settings = settings.clone();
+                let shutdown = self.shutdown.clone();
+                let scale = self.scale.clone();
 
-            match stream {
-                Ok(stream) => {
-                    let _ = senders[i1].send(stream);
-                }
-                Err(err) => {
-                    trace!("{}", err);
-                    todo!()
+                Some(thread::spawn(move || reload::watch(reload, settings, shutdown, scale)))
+            }
+            _ => None,
+        };
+
+        // accepted until the server shuts down, or hands its sockets over on upgrade
+        let mut sockets = sockets;
+
+        loop {
+            let accept_threads: Vec<(JoinHandle<Socket>, Arc<Listener>)> = sockets
+                .into_iter()
+                .map(|(socket, listener)| {
+                    let routes = routes.clone();
+                    let dispatcher = dispatcher.clone();
+                    let shutdown = self.shutdown.clone();
+                    let accepting = listener.clone();
+
+                    let thread =
+                        thread::spawn(move || listener::accept(socket, accepting, routes, dispatcher, shutdown));
+
+                    (thread, listener)
+                })
+                .collect();
+
+            // the socket of a panicked thread has been closed along with it
+            sockets = accept_threads
+                .into_iter()
+                .filter_map(|(accept_thread, listener)| match accept_thread.join() {
+                    Ok(socket) => Some((socket, listener)),
+                    Err(_) => {
+                        error!("Accept thread panicked");
+                        None
+                    }
+                })
+                .collect();
+
+            if !self.upgrade(&mut sockets) {
+                break;
+            }
+        }
+
+        // every listener is closed before the pipelines drain
+        drop(sockets);
+
+        info!("Server shutting down");
+
+        // the supervisor is joined before the pipelines are disconnected so none are added during shutdown
+        if let Some(Err(_)) = reload_thread.map(JoinHandle::join) {
+            error!("Reload thread panicked");
+        }
+
+        let pool = match supervisor_thread.join() {
+            Ok(pool) => pool,
+            Err(_) => {
+                error!("Supervisor thread panicked");
+
+                // the pipelines were owned by the supervisor & have been dropped along with it
+                return Ok(());
+            }
+        };
+
+        let deadline = self.shutdown.deadline().unwrap_or_else(Instant::now);
+
+        pool.shutdown(deadline);
+        self.scale.set_pipelines(0);
+
+        info!("Server stopped");
+
+        Ok(())
+    }
+
+    /// upgrade hands sockets over to the process of a requested upgrade; see [ShutdownHandle::upgrade]
+    ///
+    /// # return
+    /// true if the upgrade failed & the server should resume accepting connections on sockets
+    #[cfg(unix)]
+    fn upgrade(&self, sockets: &mut [(Socket, Arc<Listener>)]) -> bool {
+        let (command, grace) = match self.shutdown.take_upgrade() {
+            Some(upgrade) => upgrade,
+            None => return false,
+        };
+
+        if self.shutdown.is_shutdown() {
+            self.shutdown
+                .finish_upgrade(Err(io::Error::other("server is shutting down")), grace);
+
+            return false;
+        }
+
+        let result = activation::handoff(command, sockets);
+
+        match &result {
+            Ok(child) => {
+                info!("Handed listeners over to process {}", child.id());
+
+                for (socket, _) in sockets.iter_mut() {
+                    socket.handed_over();
                 }
             }
-            //pipeline check
+            Err(err) => error!("Failed to start upgraded server: {err}"),
         }
+
+        let resume = result.is_err();
+        self.shutdown.finish_upgrade(result, grace);
+
+        resume
+    }
+
+    #[cfg(not(unix))]
+    fn upgrade(&self, _: &mut [(Socket, Arc<Listener>)]) -> bool {
+        false
+    }
+
+    /// open_listeners binds every listener in listeners; serving the inherited sockets in place of the first ones
+    fn open_listeners(
+        &self,
+        listeners: Vec<ListenerSetting>,
+        paths: &HashMap<String, DomainPath>,
+    ) -> Result<Vec<(Socket, Arc<Listener>)>, ServerError> {
+        let mut listeners = listeners.into_iter();
+        let mut sockets = Vec::with_capacity(listeners.len());
+
+        #[cfg(unix)]
+        for (index, fd) in self.inherited.iter().enumerate() {
+            // duplicated so the server can be run again
+            let fd = fd.try_clone().map_err(|source| ServerError::Bind {
+                address: format!("inherited fd {}", fd.as_raw_fd()),
+                source,
+            })?;
+
+            sockets.push(listener::adopt(index, listeners.next(), fd, paths)?);
+        }
+
+        for setting in listeners {
+            sockets.push(listener::bind(sockets.len(), setting, paths)?);
+        }
+
+        Ok(sockets)
     }
 }
@@ -0,0 +1,147 @@
+//! overflow module defines how a stage handles work when the next stage's queue is full
+use std::{collections::HashMap, io::Write, sync::Arc};
+
+use log::{error, warn};
+
+use crate::http::{
+    body::{Body, ContentType, Text},
+    response::{response_status_code::ResponseStatusCode, Response},
+};
+
+use super::{
+    metrics::StageMetrics,
+    pipeline::Bytes,
+    queue::{BlockingQueue, PushError},
+    stream::Stream,
+};
+
+/// OverflowPolicy defines what happens when a stage's input queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// the upstream stage waits until there is space in the queue
+    Block,
+    /// the new connection is answered with 503 Service Unavailable and a Retry-After header (in seconds)
+    Reject { retry_after: u64 },
+    /// the oldest queued connection is answered with 503 Service Unavailable to make space for the new one
+    DropOldest { retry_after: u64 },
+}
+
+impl Default for OverflowPolicy {
+    fn default() -> Self {
+        OverflowPolicy::Reject { retry_after: 1 }
+    }
+}
+
+/// QueueEntry is implemented by values passed between stages so a rejected entry's client can be answered
+pub(super) trait QueueEntry {
+    fn into_stream(self) -> Box<dyn Stream>;
+}
+
+impl<S: Into<Box<dyn Stream>>, A> QueueEntry for (S, A) {
+    fn into_stream(self) -> Box<dyn Stream> {
+        self.0.into()
+    }
+}
+
+impl<S: Into<Box<dyn Stream>>, A, B> QueueEntry for (S, A, B) {
+    fn into_stream(self) -> Box<dyn Stream> {
+        self.0.into()
+    }
+}
+
+/// Overflow applies an OverflowPolicy when pushing onto a stage's input queue
+#[derive(Debug, Clone)]
+pub(super) struct Overflow {
+    policy: OverflowPolicy,
+    rejection: Arc<Bytes>,
+    metrics: Arc<StageMetrics>,
+}
+
+impl Overflow {
+    pub fn new(policy: OverflowPolicy, metrics: Arc<StageMetrics>) -> Self {
+        let retry_after = match policy {
+            OverflowPolicy::Block => None,
+            OverflowPolicy::Reject { retry_after } | OverflowPolicy::DropOldest { retry_after } => {
+                Some(retry_after)
+            }
+        };
+
+        // rendered once; a full queue is the worst time to be building responses
+        let rejection = Response {
+            status: ResponseStatusCode::ServiceUnavailable,
+            header: {
+                let mut header = HashMap::new();
+
+                // the connection is closed once the rejection is written; so a client can't try to reuse it
+                header.insert(String::from("Connection"), String::from("close"));
+
+                if let Some(retry_after) = retry_after {
+                    header.insert(String::from("Retry-After"), retry_after.to_string());
+                }
+
+                header
+            },
+            body: Some(Body {
+                content_type: ContentType::Text(Text::plain),
+                content: ResponseStatusCode::ServiceUnavailable.to_string().into_bytes(),
+            }),
+        }
+        .as_bytes();
+
+        Self {
+            policy,
+            rejection: Arc::new(rejection),
+            metrics,
+        }
+    }
+
+    /// push adds value to the queue following the overflow policy
+    pub fn push<T: QueueEntry, const SIZE: usize>(&self, queue: &BlockingQueue<T, SIZE>, value: T) {
+        let result = match self.policy {
+            OverflowPolicy::Block => match queue.push_wait(value) {
+                Ok(true) => {
+                    self.metrics.record_blocked();
+                    Ok(())
+                }
+                Ok(false) => Ok(()),
+                Err(err) => Err(err),
+            },
+            OverflowPolicy::Reject { .. } => match queue.push(value) {
+                Err(PushError::Full(value)) => {
+                    warn!("Queue full; rejecting connection");
+                    self.metrics.record_rejected();
+                    self.reject(value);
+                    Ok(())
+                }
+                result => result,
+            },
+            OverflowPolicy::DropOldest { .. } => match queue.push_evict(value) {
+                Ok(Some(evicted)) => {
+                    warn!("Queue full; dropping oldest connection");
+                    self.metrics.record_dropped();
+                    self.reject(evicted);
+                    Ok(())
+                }
+                Ok(None) => Ok(()),
+                Err(err) => Err(err),
+            },
+        };
+
+        if let Err(PushError::Closed(value)) = result {
+            error!("Queue closed; rejecting connection");
+            self.reject(value);
+        }
+    }
+
+    fn reject<T: QueueEntry>(&self, value: T) {
+        let mut stream = value.into_stream();
+
+        if let Err(err) = stream.write_all(&self.rejection) {
+            error!("Failed to write rejection: {err}");
+        }
+
+        if let Err(err) = stream.flush() {
+            error!("Failed to close: {err}");
+        }
+    }
+}
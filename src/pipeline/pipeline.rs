@@ -1,59 +1,193 @@
 use std::{
-    net::TcpStream,
+    collections::VecDeque,
     sync::{
-        mpsc::{Receiver},
+        mpsc::{Receiver, Sender},
+        atomic::AtomicUsize,
         Arc, Mutex,
-    }, fmt::{Display, Debug}, ptr,
+    }, fmt::{Display, Debug}, ptr, time::Instant,
 };
 
-use cyclic_data_types::list::List;
+use log::{error, warn};
 
 use crate::http::{
     request::Request,
     response::{response_status_code::ResponseStatusCode, Response},
 };
 
-use super::component::Component;
+use super::{
+    component::{Component, Supervised}, dispatch::PipelineLoad, keep_alive::Connection,
+    metrics::PipelineMetrics, queue::{BlockingQueue, Queued},
+};
 
 const QUEUE_SIZE: usize = 264;
 
 pub(super) type Bytes = Vec<u8>;
 
-pub(super) type ConnectionQueue = Arc<Mutex<Receiver<TcpStream>>>;
-pub(super) type ActionQueue =
-    List<QUEUE_SIZE, (TcpStream, Result<Request, ResponseStatusCode>), false>;
-pub(super) type CompressionQueue = List<QUEUE_SIZE, (TcpStream, Response, Option<Request>), false>;
-pub(super) type SenderQueue = List<QUEUE_SIZE, (TcpStream, Bytes), false>;
-
-type MutexWrapper<E> = Arc<Mutex<E>>;
+pub(super) type ConnectionQueue = Arc<Mutex<Receiver<Connection>>>;
+pub(super) type RequestQueue =
+    BlockingQueue<(Connection, Result<Request, ResponseStatusCode>), QUEUE_SIZE>;
+pub(super) type ResponseQueue = BlockingQueue<(Connection, Response, Option<Request>), QUEUE_SIZE>;
+pub(super) type ActionQueue = RequestQueue;
+pub(super) type CompressionQueue = ResponseQueue;
+pub(super) type SenderQueue = BlockingQueue<(Connection, Bytes), QUEUE_SIZE>;
+pub(super) type IdleQueue = BlockingQueue<Connection, QUEUE_SIZE>;
 
-pub(super) type ParserComponent = Component<ConnectionQueue, MutexWrapper<ActionQueue>, ()>;
-pub(super) type ActionComponent =
-    Component<MutexWrapper<ActionQueue>, MutexWrapper<CompressionQueue>, ()>;
-pub(super) type CompressionComponent =
-    Component<MutexWrapper<CompressionQueue>, MutexWrapper<SenderQueue>, ()>;
-pub(super) type SenderComponent = Component<MutexWrapper<SenderQueue>, (), ()>;
+pub(super) type ParserComponent = Component<ConnectionQueue, Arc<RequestQueue>, ()>;
+pub(super) type RequestStageComponent = Component<Arc<RequestQueue>, Arc<RequestQueue>, ()>;
+pub(super) type ActionComponent = Component<Arc<ActionQueue>, Arc<ResponseQueue>, ()>;
+pub(super) type ResponseStageComponent = Component<Arc<ResponseQueue>, Arc<ResponseQueue>, ()>;
+pub(super) type CompressionComponent = Component<Arc<CompressionQueue>, Arc<SenderQueue>, ()>;
+pub(super) type SenderComponent = Component<Arc<SenderQueue>, Arc<IdleQueue>, ()>;
+pub(super) type IdleComponent = Component<Arc<IdleQueue>, Sender<Connection>, ()>;
 
 pub struct Pipeline {
     //get connection
     pub(super) parser: ParserComponent,
 
+    //custom stages between parser & action
+    pub(super) request_stages: Vec<RequestStageComponent>,
+
     //action
     pub(super) action: ActionComponent,
 
+    //custom stages between action & compression
+    pub(super) response_stages: Vec<ResponseStageComponent>,
+
     //compression
     pub(super) compression: CompressionComponent,
 
     //sender
     pub(super) sender: SenderComponent,
+
+    //kept alive connections waiting for their next request
+    pub(super) idle: IdleComponent,
+
+    pub(super) metrics: Arc<PipelineMetrics>,
+
+    //connections sent to the parser that have not been received yet
+    pub(super) connections: Arc<AtomicUsize>,
 }
 
 impl Pipeline {
+    /// load_handle returns a view of the pipeline's queued work that can be read without locking any stage
+    pub fn load_handle(&self) -> PipelineLoad {
+        let mut queues: Vec<Arc<dyn Queued>> = Vec::new();
+
+        queues.extend(self.request_stages.iter().map(|stage| stage.input_queue.clone() as Arc<dyn Queued>));
+        queues.push(self.action.input_queue.clone());
+        queues.extend(self.response_stages.iter().map(|stage| stage.input_queue.clone() as Arc<dyn Queued>));
+        queues.push(self.compression.input_queue.clone());
+        queues.push(self.sender.input_queue.clone());
+
+        PipelineLoad {
+            connections: self.connections.clone(),
+            queues,
+        }
+    }
+
+    /// load returns the number of connections queued across every stage of the pipeline
+    pub fn load(&self) -> usize {
+        self.load_handle().load()
+    }
+
     pub fn pipeline_state(&self) -> bool {
-        self.parser.thread_state()
-            && self.action.thread_state()
-            && self.compression.thread_state()
-            && self.sender.thread_state()
+        self.components().iter().all(|component| component.thread_state())
+    }
+
+    /// recover replaces every worker thread that has stopped
+    pub(super) fn recover(&mut self) {
+        let pipeline = self.to_string();
+
+        for component in self.components_mut() {
+            let replaced = component.recover();
+
+            if replaced > 0 {
+                error!("{pipeline} - {} component panic; replaced {replaced} workers", component.name());
+            }
+        }
+    }
+
+    /// components returns every component in the order work flows through them; starting with idle connections being handed back to the parser
+    fn components(&self) -> Vec<&dyn Supervised> {
+        let mut components: Vec<&dyn Supervised> = vec![&self.idle, &self.parser];
+
+        components.extend(self.request_stages.iter().map(|stage| stage as &dyn Supervised));
+        components.push(&self.action);
+        components.extend(self.response_stages.iter().map(|stage| stage as &dyn Supervised));
+        components.push(&self.compression);
+        components.push(&self.sender);
+
+        components
+    }
+
+    fn components_mut(&mut self) -> Vec<&mut dyn Supervised> {
+        let mut components: Vec<&mut dyn Supervised> = vec![&mut self.idle, &mut self.parser];
+
+        components.extend(self.request_stages.iter_mut().map(|stage| stage as &mut dyn Supervised));
+        components.push(&mut self.action);
+        components.extend(self.response_stages.iter_mut().map(|stage| stage as &mut dyn Supervised));
+        components.push(&mut self.compression);
+        components.push(&mut self.sender);
+
+        components
+    }
+
+    fn into_components(self) -> VecDeque<Box<dyn Supervised>> {
+        let mut components: VecDeque<Box<dyn Supervised>> = VecDeque::new();
+
+        components.push_back(Box::new(self.idle));
+        components.push_back(Box::new(self.parser));
+        components.extend(self.request_stages.into_iter().map(|stage| Box::new(stage) as Box<dyn Supervised>));
+        components.push_back(Box::new(self.action));
+        components.extend(self.response_stages.into_iter().map(|stage| Box::new(stage) as Box<dyn Supervised>));
+        components.push_back(Box::new(self.compression));
+        components.push_back(Box::new(self.sender));
+
+        components
+    }
+
+    /// shutdown drains each stage front to back and joins every component thread
+    ///
+    /// Idle connections are closed first; once the idle component is joined the parser stage is left without any connection senders, and is expected to stop once its connection channel has been disconnected. Each following stage has its input queue closed once the stage before it has finished; and exits after the queue is empty. Stages that are still running at the deadline are aborted, dropping any queued work.
+    ///
+    /// # return
+    /// true if every stage drained before the deadline
+    pub(super) fn shutdown(self, deadline: Instant) -> bool {
+        let mut components = self.into_components();
+        let mut joined = true;
+
+        while let Some(component) = components.front() {
+            component.close();
+
+            if !component.wait(deadline) {
+                break;
+            }
+
+            if let Some(component) = components.pop_front() {
+                joined &= component.join();
+            }
+        }
+
+        let drained = components.is_empty();
+
+        if !drained {
+            warn!("Pipeline failed to drain before deadline");
+
+            for component in &components {
+                component.abort();
+                component.close();
+            }
+
+            for component in components {
+                joined &= component.join();
+            }
+        }
+
+        if !joined {
+            error!("Pipeline component panicked during shutdown");
+        }
+
+        drained
     }
 }
 
@@ -66,11 +200,12 @@ impl Display for Pipeline {
 
 impl Debug for Pipeline {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        fmt.debug_struct("Pipeline")
-            .field("parser component state", &self.parser.thread_state())
-            .field("action component state", &self.action.thread_state())
-            .field("compression component state", &self.compression.thread_state())
-            .field("sender component state", &self.sender.thread_state())
-            .finish()
+        let mut debug = fmt.debug_struct("Pipeline");
+
+        for component in self.components() {
+            debug.field(&format!("{} component state", component.name()), &component.thread_state());
+        }
+
+        debug.field("load", &self.load()).finish()
     }
 }
\ No newline at end of file
@@ -0,0 +1,151 @@
+//! pool module defines the set of pipelines owned by a running server
+use std::{
+    sync::{mpsc::Sender, Arc, RwLock},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use log::{error, info, warn};
+
+use super::{
+    builder::pipeline::Builder, dispatch::PipelineLoad, error::BuildError, keep_alive::Connection,
+    metrics::PipelineMetrics, pipeline::Pipeline,
+};
+
+// time given to a removed pipeline to finish its queued work
+const RETIRE_GRACE: Duration = Duration::from_secs(5);
+
+/// Routes are used by the listener to send connections to each pipeline
+#[derive(Default)]
+pub(super) struct Routes {
+    pub senders: Vec<Sender<Connection>>,
+    pub loads: Vec<PipelineLoad>,
+}
+
+/// PipelinePool owns the pipelines of a server, and keeps the listener's routes & the server's metrics in step with them
+pub(super) struct PipelinePool<U: Clone + Send + 'static> {
+    builder: Builder<U>,
+    pipes: Vec<Pipeline>,
+    routes: Arc<RwLock<Routes>>,
+    metrics: Arc<RwLock<Vec<Arc<PipelineMetrics>>>>,
+    retiring: Vec<JoinHandle<bool>>,
+}
+
+impl<U: Clone + Send + 'static> PipelinePool<U> {
+    /// new builds the given number of pipelines
+    ///
+    /// # Errors
+    /// A BuildError is returned if the builder is misconfigured; no pipeline is left running
+    pub fn new(
+        builder: Builder<U>,
+        pipelines: usize,
+        routes: Arc<RwLock<Routes>>,
+        metrics: Arc<RwLock<Vec<Arc<PipelineMetrics>>>>,
+    ) -> Result<Self, BuildError> {
+        let mut pool = Self {
+            builder,
+            pipes: Vec::with_capacity(pipelines),
+            routes,
+            metrics,
+            retiring: Vec::new(),
+        };
+
+        if let Err(err) = pool.resize(pipelines) {
+            pool.shutdown(Instant::now());
+
+            return Err(err);
+        }
+
+        Ok(pool)
+    }
+
+    pub fn len(&self) -> usize {
+        self.pipes.len()
+    }
+
+    /// load returns the number of connections queued across every pipeline
+    pub fn load(&self) -> usize {
+        self.pipes.iter().map(|pipe| pipe.load()).sum()
+    }
+
+    /// recover fixes every pipeline with a stopped worker
+    pub fn recover(&mut self) {
+        for pipe in self.pipes.iter_mut() {
+            if !pipe.pipeline_state() {
+                error!("{pipe} failure");
+                self.builder.fix(pipe);
+            }
+        }
+    }
+
+    /// resize grows or shrinks the pool to the given number of pipelines
+    ///
+    /// New pipelines are routed connections once built. Removed pipelines stop receiving connections immediately and are drained in the background; closing any idle keep alive connections.
+    ///
+    /// # Errors
+    /// A BuildError is returned if a new pipeline could not be built; pipelines built before the error are kept
+    pub fn resize(&mut self, pipelines: usize) -> Result<(), BuildError> {
+        while self.pipes.len() < pipelines {
+            let (sender, pipe) = self.builder.build()?;
+
+            {
+                let mut routes = self.routes.write().unwrap();
+
+                routes.senders.push(sender);
+                routes.loads.push(pipe.load_handle());
+            }
+
+            self.pipes.push(pipe);
+            self.update_metrics();
+        }
+
+        while self.pipes.len() > pipelines {
+            {
+                let mut routes = self.routes.write().unwrap();
+
+                // dropping the sender disconnects the pipeline's parser stage
+                routes.senders.pop();
+                routes.loads.pop();
+            }
+
+            if let Some(pipe) = self.pipes.pop() {
+                self.update_metrics();
+
+                info!("Retiring {pipe}");
+                self.retiring.push(thread::spawn(move || pipe.shutdown(Instant::now() + RETIRE_GRACE)));
+            }
+        }
+
+        self.retiring.retain(|retiring| !retiring.is_finished());
+
+        Ok(())
+    }
+
+    /// shutdown disconnects every pipeline from the listener, then drains & joins them
+    pub fn shutdown(self, deadline: Instant) {
+        {
+            let mut routes = self.routes.write().unwrap();
+
+            routes.senders.clear();
+            routes.loads.clear();
+        }
+
+        for pipe in self.pipes {
+            if !pipe.shutdown(deadline) {
+                warn!("Pipeline dropped queued work on shutdown");
+            }
+        }
+
+        for retiring in self.retiring {
+            match retiring.join() {
+                Ok(true) => {}
+                Ok(false) => warn!("Retired pipeline dropped queued work"),
+                Err(_) => error!("Retired pipeline panicked"),
+            }
+        }
+    }
+
+    fn update_metrics(&self) {
+        *self.metrics.write().unwrap() = self.pipes.iter().map(|pipe| pipe.metrics.clone()).collect();
+    }
+}
@@ -0,0 +1,231 @@
+//! queue module defines the bounded queues that connect the stages of a pipeline
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Condvar, Mutex, MutexGuard,
+    },
+    time::{Duration, Instant},
+};
+
+use cyclic_data_types::list::List;
+
+#[cfg(test)]
+mod tests;
+
+/// PushError is returned when a value cannot be added to a queue; the rejected value is handed back
+pub enum PushError<T> {
+    Full(T),
+    Closed(T),
+}
+
+impl<T> Debug for PushError<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PushError::Full(_) => write!(f, "PushError::Full"),
+            PushError::Closed(_) => write!(f, "PushError::Closed"),
+        }
+    }
+}
+
+/// PopError is returned when no value could be taken from a queue
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PopError {
+    /// no value arrived before the timeout
+    Timeout,
+    /// the queue has been closed and every value has been taken
+    Closed,
+}
+
+struct Inner<T, const SIZE: usize> {
+    list: List<SIZE, T, false>,
+    closed: bool,
+}
+
+/// BlockingQueue is a bounded FIFO queue that parks consumers until work arrives
+///
+/// Values are stored in a fixed size [List]; consumers wait on a condition variable rather than polling the lock.
+pub struct BlockingQueue<T, const SIZE: usize> {
+    inner: Mutex<Inner<T, SIZE>>,
+    // only written while inner is locked; kept outside the lock so load can be read without contention
+    len: AtomicUsize,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+impl<T, const SIZE: usize> Default for BlockingQueue<T, SIZE> {
+    fn default() -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                list: List::default(),
+                closed: false,
+            }),
+            len: AtomicUsize::new(0),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+        }
+    }
+}
+
+impl<T, const SIZE: usize> BlockingQueue<T, SIZE> {
+    /// push appends value to the back of the queue and wakes a waiting consumer
+    ///
+    /// # Errors
+    /// The value is returned if the queue is full or closed
+    pub fn push(&self, value: T) -> Result<(), PushError<T>> {
+        let inner = self.lock();
+
+        if inner.closed {
+            return Err(PushError::Closed(value));
+        }
+
+        if self.len() >= SIZE {
+            return Err(PushError::Full(value));
+        }
+
+        self.insert(inner, value);
+
+        Ok(())
+    }
+
+    /// push_wait appends value to the back of the queue; blocking until there is space
+    ///
+    /// # return
+    /// true if the producer had to wait for space
+    ///
+    /// # Errors
+    /// The value is returned if the queue is closed
+    pub fn push_wait(&self, value: T) -> Result<bool, PushError<T>> {
+        let mut inner = self.lock();
+        let mut waited = false;
+
+        while !inner.closed && self.len() >= SIZE {
+            waited = true;
+
+            inner = self
+                .not_full
+                .wait(inner)
+                .unwrap_or_else(|err| err.into_inner());
+        }
+
+        if inner.closed {
+            return Err(PushError::Closed(value));
+        }
+
+        self.insert(inner, value);
+
+        Ok(waited)
+    }
+
+    /// push_evict appends value to the back of the queue; removing the front value if the queue is full
+    ///
+    /// # return
+    /// the value that was evicted to make space
+    ///
+    /// # Errors
+    /// The value is returned if the queue is closed
+    pub fn push_evict(&self, value: T) -> Result<Option<T>, PushError<T>> {
+        let mut inner = self.lock();
+
+        if inner.closed {
+            return Err(PushError::Closed(value));
+        }
+
+        let evicted = match self.len() >= SIZE {
+            true => {
+                let evicted = inner.list.remove_front();
+                self.len.fetch_sub(1, Ordering::Release);
+                evicted
+            }
+            false => None,
+        };
+
+        self.insert(inner, value);
+
+        Ok(evicted)
+    }
+
+    /// pop removes the front of the queue; blocking until a value arrives, the queue is closed or the timeout elapses
+    ///
+    /// A timeout of None waits indefinitely.
+    pub fn pop(&self, timeout: Option<Duration>) -> Result<T, PopError> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+        let mut inner = self.lock();
+
+        loop {
+            if let Some(value) = inner.list.remove_front() {
+                self.len.fetch_sub(1, Ordering::Release);
+
+                drop(inner);
+
+                self.not_full.notify_one();
+
+                return Ok(value);
+            }
+
+            if inner.closed {
+                return Err(PopError::Closed);
+            }
+
+            inner = match deadline {
+                None => self
+                    .not_empty
+                    .wait(inner)
+                    .unwrap_or_else(|err| err.into_inner()),
+                Some(deadline) => {
+                    let now = Instant::now();
+
+                    if now >= deadline {
+                        return Err(PopError::Timeout);
+                    }
+
+                    self.not_empty
+                        .wait_timeout(inner, deadline - now)
+                        .unwrap_or_else(|err| err.into_inner())
+                        .0
+                }
+            };
+        }
+    }
+
+    /// close stops the queue from accepting values; consumers are woken and can take whatever remains
+    pub fn close(&self) {
+        self.lock().closed = true;
+
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+
+    /// len returns the number of queued values without taking the queue lock
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    fn insert(&self, mut inner: MutexGuard<'_, Inner<T, SIZE>>, value: T) {
+        if inner.list.push_back(value).is_err() {
+            unreachable!("queue length is tracked alongside list");
+        }
+        self.len.fetch_add(1, Ordering::Release);
+
+        drop(inner);
+
+        self.not_empty.notify_one();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner<T, SIZE>> {
+        // values are only moved in and out under the lock, so a poisoned queue is still consistent
+        self.inner.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// Queued is implemented by queues whose length can be read without locking; used to measure the load of a pipeline
+pub(super) trait Queued: Send + Sync {
+    fn queued(&self) -> usize;
+}
+
+impl<T: Send, const SIZE: usize> Queued for BlockingQueue<T, SIZE> {
+    fn queued(&self) -> usize {
+        self.len()
+    }
+}
@@ -0,0 +1,152 @@
+mod blocking_queue {
+    use std::{
+        sync::Arc,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use crate::pipeline::queue::{BlockingQueue, PopError, PushError};
+
+    #[test]
+    fn fifo_order() {
+        let queue: BlockingQueue<u8, 4> = BlockingQueue::default();
+
+        queue.push(1).unwrap();
+        queue.push(2).unwrap();
+        queue.push(3).unwrap();
+
+        assert_eq!(queue.pop(None), Ok(1));
+        assert_eq!(queue.pop(None), Ok(2));
+        assert_eq!(queue.pop(None), Ok(3));
+        assert_eq!(queue.pop(Some(Duration::ZERO)), Err(PopError::Timeout));
+    }
+
+    #[test]
+    fn full_queue_returns_value() {
+        let queue: BlockingQueue<u8, 2> = BlockingQueue::default();
+
+        queue.push(1).unwrap();
+        queue.push(2).unwrap();
+
+        match queue.push(3) {
+            Err(PushError::Full(value)) => assert_eq!(value, 3),
+            _ => panic!("expected full queue"),
+        }
+
+        assert_eq!(queue.pop(None), Ok(1));
+        queue.push(3).unwrap();
+    }
+
+    #[test]
+    fn pop_timeout() {
+        let queue: BlockingQueue<u8, 2> = BlockingQueue::default();
+
+        let start = Instant::now();
+
+        assert_eq!(queue.pop(Some(Duration::from_millis(50))), Err(PopError::Timeout));
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn pop_wakes_on_push() {
+        let queue: Arc<BlockingQueue<u8, 2>> = Arc::new(BlockingQueue::default());
+
+        let consumer = {
+            let queue = queue.clone();
+            thread::spawn(move || queue.pop(None))
+        };
+
+        thread::sleep(Duration::from_millis(20));
+        queue.push(7).unwrap();
+
+        assert_eq!(consumer.join().unwrap(), Ok(7));
+    }
+
+    #[test]
+    fn close_drains_then_stops() {
+        let queue: Arc<BlockingQueue<u8, 2>> = Arc::new(BlockingQueue::default());
+
+        queue.push(1).unwrap();
+        queue.close();
+
+        match queue.push(2) {
+            Err(PushError::Closed(value)) => assert_eq!(value, 2),
+            _ => panic!("expected closed queue"),
+        }
+
+        assert_eq!(queue.pop(None), Ok(1));
+        assert_eq!(queue.pop(None), Err(PopError::Closed));
+    }
+
+    #[test]
+    fn close_wakes_consumer() {
+        let queue: Arc<BlockingQueue<u8, 2>> = Arc::new(BlockingQueue::default());
+
+        let consumer = {
+            let queue = queue.clone();
+            thread::spawn(move || queue.pop(None))
+        };
+
+        thread::sleep(Duration::from_millis(20));
+        queue.close();
+
+        assert_eq!(consumer.join().unwrap(), Err(PopError::Closed));
+    }
+}
+
+mod overflow {
+    use std::{sync::Arc, thread, time::Duration};
+
+    use crate::pipeline::queue::{BlockingQueue, PushError};
+
+    #[test]
+    fn push_evict_removes_oldest() {
+        let queue: BlockingQueue<u8, 2> = BlockingQueue::default();
+
+        assert_eq!(queue.push_evict(1).unwrap(), None);
+        assert_eq!(queue.push_evict(2).unwrap(), None);
+        assert_eq!(queue.push_evict(3).unwrap(), Some(1));
+
+        assert_eq!(queue.pop(None), Ok(2));
+        assert_eq!(queue.pop(None), Ok(3));
+    }
+
+    #[test]
+    fn push_wait_blocks_until_space() {
+        let queue: Arc<BlockingQueue<u8, 1>> = Arc::new(BlockingQueue::default());
+
+        assert!(!queue.push_wait(1).unwrap());
+
+        let producer = {
+            let queue = queue.clone();
+            thread::spawn(move || queue.push_wait(2).unwrap())
+        };
+
+        thread::sleep(Duration::from_millis(20));
+        assert!(!producer.is_finished());
+
+        assert_eq!(queue.pop(None), Ok(1));
+        assert!(producer.join().unwrap());
+        assert_eq!(queue.pop(None), Ok(2));
+    }
+
+    #[test]
+    fn push_wait_returns_on_close() {
+        let queue: Arc<BlockingQueue<u8, 1>> = Arc::new(BlockingQueue::default());
+
+        queue.push(1).unwrap();
+
+        let producer = {
+            let queue = queue.clone();
+            thread::spawn(move || queue.push_wait(2))
+        };
+
+        thread::sleep(Duration::from_millis(20));
+        queue.close();
+
+        match producer.join().unwrap() {
+            Err(PushError::Closed(value)) => assert_eq!(value, 2),
+            _ => panic!("expected closed queue"),
+        }
+    }
+}
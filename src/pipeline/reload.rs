@@ -0,0 +1,145 @@
+//! reload module defines how the settings of a running server are reloaded without a restart
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, SystemTime},
+};
+
+use log::{error, info, warn};
+
+use crate::setting::{ServerSetting, SettingsError, SettingsLoader};
+
+use super::{scale::ScaleHandle, shutdown::ShutdownHandle};
+
+// how often a pending SIGHUP is checked for when the file isn't watched
+const HANGUP_POLL: Duration = Duration::from_millis(100);
+
+/// Reload defines where a server's settings are reloaded from, and what triggers a reload
+///
+/// Every layer of the loader is reloaded; so environment variables & explicit overrides keep taking precedence over the file. Reloaded settings are validated, then swapped in as a whole; so every stage sees the new settings on its next request. If loading or validation fails the current settings are kept. Listeners are only bound on start up, so changes to them are ignored until the server is restarted; a change to the number of pipelines resizes the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reload {
+    /// loads the settings; its file is the one watched for changes
+    pub loader: SettingsLoader,
+    /// how often the file is checked for changes; the file is not watched if unset
+    pub poll: Option<Duration>,
+    /// reload when the process receives SIGHUP; only supported on unix
+    pub hangup: bool,
+}
+
+impl Reload {
+    /// new watches the file at path every second & reloads on SIGHUP; layering the file the same way as [ServerSetting::load_from]
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            loader: SettingsLoader::default().set_file(path),
+            poll: Some(Duration::from_secs(1)),
+            hangup: true,
+        }
+    }
+}
+
+/// watch reloads settings whenever reload is triggered, until the server is shut down
+pub(super) fn watch(reload: Reload, settings: Arc<RwLock<ServerSetting>>, shutdown: ShutdownHandle, scale: ScaleHandle) {
+    let hangup = Arc::new(AtomicBool::new(false));
+    let registration = register_hangup(&reload, &hangup);
+
+    let file = reload.loader.file.clone();
+    let mut modified = file.as_deref().and_then(modified_at);
+
+    while !shutdown.wait_for_shutdown(reload.poll.unwrap_or(HANGUP_POLL)) {
+        let mut triggered = hangup.swap(false, Ordering::AcqRel);
+
+        if let (Some(file), Some(_)) = (&file, reload.poll) {
+            let current = modified_at(file);
+
+            // a missing file is treated as unchanged; editors may briefly remove it while saving
+            if current.is_some() && current != modified {
+                modified = current;
+                triggered = true;
+            }
+        }
+
+        if !triggered {
+            continue;
+        }
+
+        match apply(&reload.loader, &settings, &scale) {
+            Ok(()) => info!("Reloaded settings"),
+            Err(err) => error!("Failed to reload settings; keeping current settings: {err}"),
+        }
+    }
+
+    unregister_hangup(registration);
+}
+
+/// apply loads & validates settings using loader, then swaps them into settings
+///
+/// # Errors
+/// A SettingsError is returned if the settings could not be loaded or are invalid; settings is left unchanged
+pub(super) fn apply(loader: &SettingsLoader, settings: &RwLock<ServerSetting>, scale: &ScaleHandle) -> Result<(), SettingsError> {
+    let mut reloaded = loader.load()?;
+
+    let mut current = settings.write().unwrap();
+
+    if reloaded.effective_listeners() != current.effective_listeners() {
+        warn!("Listener changes are applied after a restart");
+
+        reloaded.address = current.address.clone();
+        reloaded.port = current.port;
+        reloaded.listeners = current.listeners.clone();
+    }
+
+    if let Some(pipelines) = reloaded.pipelines.filter(|pipelines| Some(*pipelines) != current.pipelines) {
+        scale.scale_to(pipelines);
+    }
+
+    *current = reloaded;
+
+    Ok(())
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+#[cfg(unix)]
+type Registration = Option<signal_hook::SigId>;
+
+#[cfg(not(unix))]
+type Registration = ();
+
+#[cfg(unix)]
+fn register_hangup(reload: &Reload, hangup: &Arc<AtomicBool>) -> Registration {
+    if !reload.hangup {
+        return None;
+    }
+
+    match signal_hook::flag::register(signal_hook::consts::SIGHUP, hangup.clone()) {
+        Ok(id) => Some(id),
+        Err(err) => {
+            warn!("Failed to register SIGHUP handler: {err}");
+            None
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn register_hangup(reload: &Reload, _: &Arc<AtomicBool>) -> Registration {
+    if reload.hangup {
+        warn!("Reloading settings on SIGHUP is only supported on unix");
+    }
+}
+
+#[cfg(unix)]
+fn unregister_hangup(registration: Registration) {
+    if let Some(id) = registration {
+        signal_hook::low_level::unregister(id);
+    }
+}
+
+#[cfg(not(unix))]
+fn unregister_hangup(_: Registration) {}
@@ -0,0 +1,153 @@
+//! scale module defines how many pipelines a server runs, and how that number is changed while the server is running
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::warn;
+
+/// AutoScale is a load based policy for growing & shrinking the number of pipelines of a running server
+///
+/// The load of a pipeline is the number of connections queued across its stages; see [PipelineLoad](super::PipelineLoad).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoScale {
+    /// fewest pipelines the server is shrunk to
+    pub min: usize,
+    /// most pipelines the server is grown to
+    pub max: usize,
+    /// a pipeline is added while the average load per pipeline is above this value
+    pub grow_above: usize,
+    /// a pipeline is removed while the average load per pipeline is below this value
+    pub shrink_below: usize,
+    /// minimum time between two changes in the number of pipelines
+    pub cooldown: Duration,
+}
+
+impl Default for AutoScale {
+    fn default() -> Self {
+        Self {
+            min: 1,
+            max: available_parallelism(),
+            grow_above: 8,
+            shrink_below: 1,
+            cooldown: Duration::from_secs(5),
+        }
+    }
+}
+
+impl AutoScale {
+    /// target returns the number of pipelines wanted given the current number of pipelines & their total load
+    pub fn target(&self, pipelines: usize, load: usize) -> usize {
+        let average = load / pipelines.max(1);
+
+        let target = if average > self.grow_above {
+            pipelines + 1
+        } else if average < self.shrink_below {
+            pipelines.saturating_sub(1)
+        } else {
+            pipelines
+        };
+
+        target.clamp(self.min.max(1), self.max.max(1))
+    }
+}
+
+#[derive(Debug, Default)]
+struct ScaleState {
+    requested: Mutex<Option<usize>>,
+    pipelines: AtomicUsize,
+}
+
+/// ScaleHandle is a cloneable handle that changes the number of pipelines of a running [Server](super::Server) from another thread
+///
+/// # Example
+/// ```ignore
+/// let handle = server.scale_handle();
+///
+/// thread::spawn(move || server.run().unwrap());
+///
+/// handle.scale_to(8);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ScaleHandle(Arc<ScaleState>);
+
+impl ScaleHandle {
+    /// scale_to requests the server to run the given number of pipelines
+    ///
+    /// The request is applied by the server's supervisor thread; a request for zero pipelines is treated as one.
+    pub fn scale_to(&self, pipelines: usize) {
+        *self.0.requested.lock().unwrap() = Some(pipelines.max(1));
+    }
+
+    /// pipelines returns the number of pipelines currently running
+    pub fn pipelines(&self) -> usize {
+        self.0.pipelines.load(Ordering::Acquire)
+    }
+
+    pub(super) fn set_pipelines(&self, pipelines: usize) {
+        self.0.pipelines.store(pipelines, Ordering::Release);
+    }
+
+    fn take_request(&self) -> Option<usize> {
+        self.0.requested.lock().unwrap().take()
+    }
+}
+
+/// Scaler decides when the number of pipelines should change; combining admin requests with an optional autoscaling policy
+pub(super) struct Scaler {
+    handle: ScaleHandle,
+    policy: Option<AutoScale>,
+    last_change: Instant,
+}
+
+impl Scaler {
+    pub fn new(handle: ScaleHandle, policy: Option<AutoScale>) -> Self {
+        Self {
+            handle,
+            policy,
+            last_change: Instant::now(),
+        }
+    }
+
+    /// target returns the number of pipelines the server should be resized to; if it should change
+    ///
+    /// Admin requests are applied immediately, while the autoscaling policy waits for its cooldown.
+    pub fn target(&mut self, pipelines: usize, load: usize) -> Option<usize> {
+        let target = match (self.handle.take_request(), self.policy) {
+            (Some(requested), _) => requested,
+            (None, Some(policy)) if self.last_change.elapsed() >= policy.cooldown => {
+                policy.target(pipelines, load)
+            }
+            (None, _) => pipelines,
+        };
+
+        if target == pipelines {
+            return None;
+        }
+
+        self.last_change = Instant::now();
+
+        Some(target)
+    }
+}
+
+/// pipeline_count returns the number of pipelines to start with; defaulting to the available parallelism when unset
+pub(super) fn pipeline_count(pipelines: Option<usize>) -> usize {
+    pipelines.unwrap_or_else(available_parallelism)
+}
+
+fn available_parallelism() -> usize {
+    match thread::available_parallelism() {
+        Ok(parallelism) => parallelism.get(),
+        Err(err) => {
+            warn!("Failed to get available parallelism: {err}");
+
+            NonZeroUsize::MIN.get()
+        }
+    }
+}
@@ -0,0 +1,331 @@
+//! shutdown module defines the handle used to gracefully stop a running server
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use log::{trace, warn};
+
+#[cfg(unix)]
+use std::{
+    io, mem,
+    process::{Child, Command},
+};
+
+use super::stream::Stream;
+
+#[derive(Debug, Default)]
+struct ShutdownState {
+    requested: AtomicBool,
+    deadline: Mutex<Option<Instant>>,
+    requested_signal: Condvar,
+    addresses: Mutex<Vec<SocketAddr>>,
+    // unix sockets can't tell the wake connection apart from clients; so they stop on the first connection after a shutdown
+    paths: Mutex<Vec<PathBuf>>,
+    // local address of the connection used to wake each listener
+    wake: Mutex<HashMap<SocketAddr, Option<SocketAddr>>>,
+    // set while the listeners are stopped to hand their sockets over to a new process
+    paused: AtomicBool,
+    #[cfg(unix)]
+    upgrade: (Mutex<Upgrade>, Condvar),
+    stopped: (Mutex<bool>, Condvar),
+}
+
+/// Upgrade is the state of a request to hand the server's sockets over to a new process
+#[cfg(unix)]
+#[derive(Debug, Default)]
+enum Upgrade {
+    #[default]
+    Idle,
+    Requested { command: Command, grace: Duration },
+    Finished(io::Result<Child>),
+}
+
+/// ShutdownHandle is a cloneable handle that stops a [Server](super::Server) from another thread
+///
+/// Once a shutdown is requested the server stops accepting connections, lets queued work drain until the grace period runs out, joins every pipeline thread and then returns from `run`.
+///
+/// # Example
+/// ```ignore
+/// let handle = server.shutdown_handle();
+///
+/// thread::spawn(move || server.run().unwrap());
+///
+/// handle.shutdown(Duration::from_secs(5));
+/// handle.wait();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownHandle(Arc<ShutdownState>);
+
+impl ShutdownHandle {
+    /// shutdown requests the server to stop; queued work is given the grace period to drain before being dropped
+    pub fn shutdown(&self, grace: Duration) {
+        self.set_deadline(grace);
+        self.stop_accepting(&self.0.requested);
+    }
+
+    /// upgrade starts command with the server's listening sockets, then shuts the server down; queued work is given the grace period to drain before being dropped
+    ///
+    /// The server stops accepting connections while command is started; connections arriving meanwhile wait in the sockets' backlog for the new process, which takes the sockets with [listen_fds](super::listen_fds). Blocks until the sockets have been handed over. The server keeps running if command can't be started.
+    ///
+    /// # Errors
+    /// An io::Error is returned if command can't be started, or the server is shutting down or already upgrading
+    ///
+    /// # Example
+    /// ```ignore
+    /// let new_process = handle.upgrade(Command::new(env::current_exe()?), Duration::from_secs(5))?;
+    /// ```
+    #[cfg(unix)]
+    pub fn upgrade(&self, command: Command, grace: Duration) -> io::Result<Child> {
+        let (lock, cvar) = &self.0.upgrade;
+
+        {
+            let mut upgrade = lock.lock().unwrap();
+
+            if self.is_shutdown() || *self.0.stopped.0.lock().unwrap() {
+                return Err(io::Error::other("server is shutting down"));
+            }
+
+            if !matches!(*upgrade, Upgrade::Idle) {
+                return Err(io::Error::other("server is already upgrading"));
+            }
+
+            *upgrade = Upgrade::Requested { command, grace };
+        }
+
+        self.stop_accepting(&self.0.paused);
+
+        let mut upgrade = cvar
+            .wait_while(lock.lock().unwrap(), |upgrade| !matches!(upgrade, Upgrade::Finished(_)))
+            .unwrap();
+
+        match mem::take(&mut *upgrade) {
+            Upgrade::Finished(result) => result,
+            _ => unreachable!("waited for the upgrade to finish"),
+        }
+    }
+
+    /// take_upgrade returns the command & grace period of a requested upgrade; once every listener has stopped accepting
+    #[cfg(unix)]
+    pub(super) fn take_upgrade(&self) -> Option<(Command, Duration)> {
+        let mut upgrade = self.0.upgrade.0.lock().unwrap();
+
+        match mem::take(&mut *upgrade) {
+            Upgrade::Requested { command, grace } => Some((command, grace)),
+            other => {
+                *upgrade = other;
+                None
+            }
+        }
+    }
+
+    /// finish_upgrade reports the result of an upgrade to the thread that requested it
+    ///
+    /// The server shuts down once its sockets have been handed over; without waking the listeners, as connections to them are accepted by the new process. Otherwise it resumes accepting connections.
+    #[cfg(unix)]
+    pub(super) fn finish_upgrade(&self, result: io::Result<Child>, grace: Duration) {
+        {
+            let mut wake = self.0.wake.lock().unwrap();
+
+            match result {
+                Ok(_) => {
+                    self.0.addresses.lock().unwrap().clear();
+                    self.0.paths.lock().unwrap().clear();
+
+                    self.0.requested.store(true, Ordering::Release);
+                }
+                Err(_) => wake.clear(),
+            }
+
+            self.0.paused.store(false, Ordering::Release);
+        }
+
+        if result.is_ok() {
+            self.set_deadline(grace);
+        }
+
+        let (lock, cvar) = &self.0.upgrade;
+
+        *lock.lock().unwrap() = Upgrade::Finished(result);
+        cvar.notify_all();
+    }
+
+    fn set_deadline(&self, grace: Duration) {
+        let mut deadline = self.0.deadline.lock().unwrap();
+
+        if deadline.is_none() {
+            *deadline = Some(Instant::now() + grace);
+        }
+
+        self.0.requested_signal.notify_all();
+    }
+
+    /// stop_accepting sets flag & wakes every listener so they stop accepting connections
+    fn stop_accepting(&self, flag: &AtomicBool) {
+        // wake is held until the wake connections are recorded so the listeners can tell them apart from clients
+        let mut wake = self.0.wake.lock().unwrap();
+
+        flag.store(true, Ordering::Release);
+
+        let addresses = self.0.addresses.lock().unwrap().clone();
+
+        for address in addresses {
+            wake.insert(address, wake_listener(address));
+        }
+
+        for path in self.0.paths.lock().unwrap().iter() {
+            wake_unix_listener(path);
+        }
+    }
+
+    /// is_shutdown returns true once a shutdown has been requested
+    pub fn is_shutdown(&self) -> bool {
+        self.0.requested.load(Ordering::Acquire)
+    }
+
+    /// is_stopping returns true while the listeners should stop accepting connections
+    pub(super) fn is_stopping(&self) -> bool {
+        self.is_shutdown() || self.0.paused.load(Ordering::Acquire)
+    }
+
+    /// wait blocks until the server has finished shutting down
+    pub fn wait(&self) {
+        let (lock, cvar) = &self.0.stopped;
+
+        let mut stopped = lock.lock().unwrap();
+
+        while !*stopped {
+            stopped = cvar.wait(stopped).unwrap();
+        }
+    }
+
+    /// wait_for_shutdown blocks until a shutdown is requested or the timeout elapses
+    ///
+    /// # return
+    /// true if a shutdown has been requested
+    pub(super) fn wait_for_shutdown(&self, timeout: Duration) -> bool {
+        let deadline = self.0.deadline.lock().unwrap();
+
+        let (deadline, _) = self
+            .0
+            .requested_signal
+            .wait_timeout_while(deadline, timeout, |deadline| deadline.is_none())
+            .unwrap();
+
+        deadline.is_some()
+    }
+
+    pub(super) fn deadline(&self) -> Option<Instant> {
+        *self.0.deadline.lock().unwrap()
+    }
+
+    pub(super) fn listening_on(&self, address: SocketAddr) {
+        let mut wake = self.0.wake.lock().unwrap();
+
+        self.0.addresses.lock().unwrap().push(address);
+
+        // shutdown may have been requested before the listener was bound
+        if self.is_stopping() {
+            wake.insert(address, wake_listener(address));
+        }
+    }
+
+    /// listening_on_path records a unix socket listener so it can be woken on shutdown
+    pub(super) fn listening_on_path(&self, path: PathBuf) {
+        // held so a shutdown can't be requested between recording the path & checking for one
+        let _wake = self.0.wake.lock().unwrap();
+
+        if self.is_stopping() {
+            wake_unix_listener(&path);
+        }
+
+        self.0.paths.lock().unwrap().push(path);
+    }
+
+    /// is_wake checks if a stream accepted by the listener bound to listener is the connection used to wake it on shutdown
+    ///
+    /// If the wake connection of listener isn't known, any connection stops it; the caller closes the stream without answering it.
+    pub(super) fn is_wake(&self, listener: Option<SocketAddr>, stream: &dyn Stream) -> bool {
+        if !self.is_stopping() {
+            return false;
+        }
+
+        let wake = listener.and_then(|listener| self.0.wake.lock().unwrap().get(&listener).copied());
+
+        match (wake.flatten(), stream.peer_addr()) {
+            (Some(wake), Some(peer)) => wake == peer,
+            // the listener could not be woken, or the wake connection wasn't recorded; stop on the next connection
+            (None, peer) => {
+                // the wake connection is made over loopback; a connection from elsewhere is a client's, closed unanswered
+                if let Some(peer) = peer.filter(|peer| !peer.ip().is_loopback()) {
+                    warn!("Listener stopped by the connection of {peer}; closing it unanswered");
+                }
+
+                true
+            }
+            (Some(_), None) => false,
+        }
+    }
+
+    pub(super) fn stopped(&self) {
+        let (lock, cvar) = &self.0.stopped;
+
+        *lock.lock().unwrap() = true;
+
+        cvar.notify_all();
+
+        // an upgrade requested while the server was stopping would wait forever
+        #[cfg(unix)]
+        if let Some((_, grace)) = self.take_upgrade() {
+            self.finish_upgrade(Err(io::Error::other("server stopped")), grace);
+        }
+    }
+}
+
+/// wake_listener connects to the listener so a blocked accept returns
+///
+/// # return
+/// local address of the wake connection
+fn wake_listener(address: SocketAddr) -> Option<SocketAddr> {
+    trace!("Waking listener on {address}");
+
+    match TcpStream::connect(wake_address(address)) {
+        Ok(stream) => stream.local_addr().ok(),
+        Err(err) => {
+            warn!("Failed to wake listener: {err}");
+            None
+        }
+    }
+}
+
+/// wake_unix_listener connects to the unix socket at path so a blocked accept returns
+#[cfg(unix)]
+fn wake_unix_listener(path: &std::path::Path) {
+    trace!("Waking listener on {}", path.display());
+
+    if let Err(err) = std::os::unix::net::UnixStream::connect(path) {
+        warn!("Failed to wake listener: {err}");
+    }
+}
+
+#[cfg(not(unix))]
+fn wake_unix_listener(_: &std::path::Path) {}
+
+/// wake_address maps a wildcard bind address onto loopback so it can be connected to
+fn wake_address(address: SocketAddr) -> SocketAddr {
+    match address.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => {
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), address.port())
+        }
+        IpAddr::V6(ip) if ip.is_unspecified() => {
+            SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), address.port())
+        }
+        _ => address,
+    }
+}
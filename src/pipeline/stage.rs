@@ -0,0 +1,132 @@
+//! stage module defines the traits implemented by the logic of each pipeline stage
+//!
+//! Every trait is implemented for matching `Fn` closures & fn pointers; so handlers can capture state such as connection pools or caches. Stages are shared between a pipeline's threads and cloned into recovered threads by [Builder::fix](super::builder::pipeline::Builder::fix).
+use std::sync::mpsc;
+
+use crate::{
+    http::{
+        request::Request,
+        response::{response_status_code::ResponseStatusCode, Response},
+    },
+    setting::ServerSetting,
+};
+
+use super::{keep_alive::Connection, pipeline::Bytes, stream::Stream};
+
+/// Parser reads a request from a client connection; over any [Stream]
+pub trait Parser: Send + Sync + 'static {
+    fn parse(&self, stream: &mut dyn Stream) -> Result<Request, ResponseStatusCode>;
+}
+
+impl<F> Parser for F
+where
+    F: Fn(&mut dyn Stream) -> Result<Request, ResponseStatusCode> + Send + Sync + 'static,
+{
+    fn parse(&self, stream: &mut dyn Stream) -> Result<Request, ResponseStatusCode> {
+        self(stream)
+    }
+}
+
+/// Action generates the response to a parsed request
+///
+/// Requests that failed to parse are passed on as the error status code so an error page can be generated.
+pub trait Action<U>: Send + Sync + 'static {
+    fn act(
+        &self,
+        request: &Result<Request, ResponseStatusCode>,
+        settings: &ServerSetting,
+        utility_thread: &mut mpsc::Sender<U>,
+    ) -> Result<Response, ResponseStatusCode>;
+}
+
+impl<U, F> Action<U> for F
+where
+    F: Fn(
+            &Result<Request, ResponseStatusCode>,
+            &ServerSetting,
+            &mut mpsc::Sender<U>,
+        ) -> Result<Response, ResponseStatusCode>
+        + Send
+        + Sync
+        + 'static,
+{
+    fn act(
+        &self,
+        request: &Result<Request, ResponseStatusCode>,
+        settings: &ServerSetting,
+        utility_thread: &mut mpsc::Sender<U>,
+    ) -> Result<Response, ResponseStatusCode> {
+        self(request, settings, utility_thread)
+    }
+}
+
+/// Compressor converts a response into the bytes sent to the client
+///
+/// The response should be written with [Response::as_bytes_for] the version of the request, so a HTTP/1.0 client is answered in HTTP/1.0.
+pub trait Compressor: Send + Sync + 'static {
+    fn compress(&self, response: Response, request: Option<Request>, settings: ServerSetting) -> Bytes;
+}
+
+impl<F> Compressor for F
+where
+    F: Fn(Response, Option<Request>, ServerSetting) -> Bytes + Send + Sync + 'static,
+{
+    fn compress(&self, response: Response, request: Option<Request>, settings: ServerSetting) -> Bytes {
+        self(response, request, settings)
+    }
+}
+
+/// RequestStage runs between the parser and action stages; e.g. authentication or rate limiting
+///
+/// Returning an error status code stops the request from reaching the action stage's handlers; the action stage generates its error page instead.
+pub trait RequestStage: Send + Sync + 'static {
+    fn process(
+        &self,
+        connection: &Connection,
+        request: Result<Request, ResponseStatusCode>,
+        settings: &ServerSetting,
+    ) -> Result<Request, ResponseStatusCode>;
+}
+
+impl<F> RequestStage for F
+where
+    F: Fn(&Connection, Result<Request, ResponseStatusCode>, &ServerSetting) -> Result<Request, ResponseStatusCode>
+        + Send
+        + Sync
+        + 'static,
+{
+    fn process(
+        &self,
+        connection: &Connection,
+        request: Result<Request, ResponseStatusCode>,
+        settings: &ServerSetting,
+    ) -> Result<Request, ResponseStatusCode> {
+        self(connection, request, settings)
+    }
+}
+
+/// ResponseStage runs between the action and compression stages; e.g. response templating or access logging
+pub trait ResponseStage: Send + Sync + 'static {
+    fn process(
+        &self,
+        connection: &Connection,
+        response: Response,
+        request: Option<&Request>,
+        settings: &ServerSetting,
+    ) -> Response;
+}
+
+impl<F> ResponseStage for F
+where
+    F: Fn(&Connection, Response, Option<&Request>, &ServerSetting) -> Response + Send + Sync + 'static,
+{
+    fn process(
+        &self,
+        connection: &Connection,
+        response: Response,
+        request: Option<&Request>,
+        settings: &ServerSetting,
+    ) -> Response {
+        self(connection, response, request, settings)
+    }
+}
@@ -0,0 +1,163 @@
+//! stream module defines the client connections a pipeline can serve
+use std::{
+    fmt::Debug,
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpStream},
+    time::Duration,
+};
+
+#[cfg(unix)]
+use std::{mem::MaybeUninit, os::unix::net::UnixStream};
+
+/// Stream is a client connection requests are read from & responses are written to
+///
+/// Implemented for [TcpStream] and, on unix, [UnixStream]; so every stage works unchanged over both. Connections of TLS listeners are decrypted by a stream wrapping either.
+pub trait Stream: Read + Write + Send + Debug + 'static {
+    /// peer_addr returns the address of the client; None if the client has no ip address, e.g. over a unix socket
+    fn peer_addr(&self) -> Option<SocketAddr>;
+
+    /// set_read_timeout sets how long a read blocks before failing; reads block indefinitely if None
+    ///
+    /// # Errors
+    /// Any io::Error returned by the OS is returned
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()>;
+
+    /// set_nonblocking sets if reads & writes fail with WouldBlock instead of waiting
+    ///
+    /// # Errors
+    /// Any io::Error returned by the OS is returned
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()>;
+
+    /// peek_len checks for data waiting to be read without consuming any of it
+    ///
+    /// # return
+    /// 0 if the client has closed the connection; otherwise the number of bytes peeked, which may be fewer than are waiting
+    ///
+    /// # Errors
+    /// Any io::Error returned by the OS is returned; WouldBlock if no data is waiting on a nonblocking stream
+    fn peek_len(&mut self) -> io::Result<usize>;
+
+    /// unread hands back bytes read past the end of a request; so they are read first when the next request is parsed
+    ///
+    /// The bytes are dropped by streams that can't hold them; the connections served by a pipeline always can.
+    fn unread(&mut self, bytes: &[u8]) {
+        let _ = bytes;
+    }
+}
+
+/// Buffered is a stream holding the bytes read past the end of a request; which are read before anything else the client sends
+#[derive(Debug)]
+pub(super) struct Buffered {
+    stream: Box<dyn Stream>,
+    unread: Vec<u8>,
+}
+
+impl Buffered {
+    pub(super) fn new(stream: Box<dyn Stream>) -> Self {
+        Self {
+            stream,
+            unread: Vec::new(),
+        }
+    }
+}
+
+impl Read for Buffered {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.unread.is_empty() {
+            return self.stream.read(buf);
+        }
+
+        let read = buf.len().min(self.unread.len());
+        buf[..read].copy_from_slice(&self.unread[..read]);
+        self.unread.drain(..read);
+
+        Ok(read)
+    }
+}
+
+impl Write for Buffered {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl Stream for Buffered {
+    fn peer_addr(&self) -> Option<SocketAddr> {
+        self.stream.peer_addr()
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.stream.set_read_timeout(timeout)
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.stream.set_nonblocking(nonblocking)
+    }
+
+    fn peek_len(&mut self) -> io::Result<usize> {
+        match self.unread.len() {
+            0 => self.stream.peek_len(),
+            len => Ok(len),
+        }
+    }
+
+    fn unread(&mut self, bytes: &[u8]) {
+        // bytes were read before anything still held
+        self.unread.splice(..0, bytes.iter().copied());
+    }
+}
+
+impl Stream for TcpStream {
+    fn peer_addr(&self) -> Option<SocketAddr> {
+        TcpStream::peer_addr(self).ok()
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        TcpStream::set_nonblocking(self, nonblocking)
+    }
+
+    fn peek_len(&mut self) -> io::Result<usize> {
+        self.peek(&mut [0; 1])
+    }
+}
+
+#[cfg(unix)]
+impl Stream for UnixStream {
+    fn peer_addr(&self) -> Option<SocketAddr> {
+        None
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        UnixStream::set_read_timeout(self, timeout)
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        UnixStream::set_nonblocking(self, nonblocking)
+    }
+
+    fn peek_len(&mut self) -> io::Result<usize> {
+        // UnixStream::peek isn't stable; only the number of bytes is needed so the buffer is never read
+        socket2::SockRef::from(&*self).peek(&mut [MaybeUninit::uninit(); 1])
+    }
+}
+
+impl From<TcpStream> for Box<dyn Stream> {
+    fn from(stream: TcpStream) -> Self {
+        Box::new(stream)
+    }
+}
+
+#[cfg(unix)]
+impl From<UnixStream> for Box<dyn Stream> {
+    fn from(stream: UnixStream) -> Self {
+        Box::new(stream)
+    }
+}
@@ -0,0 +1,235 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, UdpSocket},
+    os::unix::{
+        io::OwnedFd,
+        net::{UnixListener, UnixStream},
+    },
+    sync::mpsc,
+    time::Duration,
+};
+
+use serial_test::serial;
+
+use crate::{
+    http::{
+        body::{Body, ContentType, Text},
+        request::Request,
+        response::{response_status_code::ResponseStatusCode, Response},
+    },
+    pipeline::{activation::passed_fds, Connection, Server, ServerError},
+    setting::{ListenerSetting, ServerSetting},
+    test_tools::server_env::{self, ServerEnv},
+};
+
+const INHERITED_PORT: u16 = 8102;
+const EXTRA_PORT: u16 = 8103;
+const SETTING_PORT: u16 = 8104;
+
+fn v4(port: u16) -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], port))
+}
+
+fn setting(listeners: Vec<ListenerSetting>) -> ServerSetting {
+    ServerSetting {
+        address: String::from("127.0.0.1"),
+        port: SETTING_PORT,
+        paths: HashMap::new(),
+        pipelines: Some(1),
+        listeners,
+    }
+}
+
+/// tag_listener records the index of the listener that accepted the connection in the x-listener header
+fn tag_listener(
+    connection: &Connection,
+    request: Result<Request, ResponseStatusCode>,
+    _: &ServerSetting,
+) -> Result<Request, ResponseStatusCode> {
+    let mut request = request?;
+
+    if let Some(listener) = connection.listener() {
+        request.headers.insert("x-listener", &listener.index().to_string());
+    }
+
+    Ok(request)
+}
+
+/// echo answers with the listener & host of the request
+fn echo(
+    request: &Result<Request, ResponseStatusCode>,
+    _: &ServerSetting,
+    _: &mut mpsc::Sender<()>,
+) -> Result<Response, ResponseStatusCode> {
+    let request = match request {
+        Ok(request) => request,
+        Err(err) => return Err(*err),
+    };
+
+    let field = |name: &str| request.headers.get(name).unwrap_or_default().to_string();
+
+    Ok(Response {
+        status: ResponseStatusCode::Ok,
+        header: HashMap::new(),
+        body: Some(Body {
+            content_type: ContentType::Text(Text::plain),
+            content: format!("{}|{}", field("x-listener"), field("host")).into_bytes(),
+        }),
+    })
+}
+
+fn server(setting: ServerSetting, inherited: Vec<OwnedFd>) -> Server<()> {
+    let builder = server_env::builder()
+        .add_request_stage("Tag listener", tag_listener)
+        .set_action(echo);
+
+    server_env::server(setting, builder).set_inherited(inherited)
+}
+
+/// start runs server; the inherited sockets are already listening, so clients can connect before it runs
+fn start(server: Server<()>) -> ServerEnv<()> {
+    ServerEnv::spawn(server)
+}
+
+fn request<S: Read + Write>(stream: S, raw: &str) -> String {
+    server_env::exchange(stream, raw).unwrap()
+}
+
+fn tcp(port: u16) -> TcpStream {
+    let stream = TcpStream::connect(v4(port)).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+    stream
+}
+
+mod listen_fds {
+    use super::*;
+
+    #[test]
+    fn passed_to_this_process() {
+        assert_eq!(passed_fds(Some("42"), None, Some("2"), (42, 1)).unwrap(), 3..5);
+        assert_eq!(passed_fds(Some(" 42\n"), None, Some("0"), (42, 1)).unwrap(), 3..3);
+    }
+
+    #[test]
+    fn passed_to_another_process() {
+        assert!(passed_fds(Some("41"), None, Some("2"), (42, 1)).unwrap().is_empty());
+        assert!(passed_fds(None, None, Some("2"), (42, 1)).unwrap().is_empty());
+        assert!(passed_fds(Some("42"), None, None, (42, 1)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn passed_by_upgrading_parent() {
+        assert_eq!(passed_fds(None, Some("1"), Some("2"), (42, 1)).unwrap(), 3..5);
+
+        // passed to an ancestor, or announced by a supervisor for another process
+        assert!(passed_fds(None, Some("7"), Some("2"), (42, 1)).unwrap().is_empty());
+        assert!(passed_fds(Some("41"), Some("1"), Some("2"), (42, 1)).unwrap().is_empty());
+
+        let err = passed_fds(None, Some("parent"), Some("2"), (42, 1)).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn malformed_variables() {
+        for (listen_pid, listen_fds) in [("pid", "2"), ("42", "two"), ("", "2")] {
+            let err = passed_fds(Some(listen_pid), None, Some(listen_fds), (42, 1)).unwrap_err();
+
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        }
+    }
+}
+
+#[test]
+#[serial]
+fn inherited_socket_replaces_listener() {
+    // bound by the test as a supervisor would; the listener in the settings is never bound
+    let inherited = TcpListener::bind(v4(INHERITED_PORT)).unwrap();
+
+    let server = start(server(
+        setting(vec![ListenerSetting {
+            default_host: Some(String::from("example.com")),
+            ..ListenerSetting::new("127.0.0.1", SETTING_PORT)
+        }]),
+        vec![OwnedFd::from(inherited)],
+    ));
+
+    // the options of the replaced listener apply to the inherited socket
+    let response = request(tcp(INHERITED_PORT), "GET / HTTP/1.1\r\n\r\n");
+    assert!(response.ends_with("\r\n\r\n0|example.com"), "{response}");
+
+    assert!(TcpStream::connect(v4(SETTING_PORT)).is_err());
+
+    server.stop();
+
+    assert!(TcpStream::connect(v4(INHERITED_PORT)).is_err());
+}
+
+#[test]
+#[serial]
+fn extra_inherited_sockets_are_served() {
+    let first = TcpListener::bind(v4(INHERITED_PORT)).unwrap();
+    let extra = TcpListener::bind(v4(EXTRA_PORT)).unwrap();
+
+    let _server = start(server(
+        setting(Vec::new()),
+        vec![OwnedFd::from(first), OwnedFd::from(extra)],
+    ));
+
+    let response = request(tcp(INHERITED_PORT), "GET / HTTP/1.1\r\nhost:localhost\r\n\r\n");
+    assert!(response.ends_with("\r\n\r\n0|localhost"), "{response}");
+
+    let response = request(tcp(EXTRA_PORT), "GET / HTTP/1.1\r\nhost:localhost\r\n\r\n");
+    assert!(response.ends_with("\r\n\r\n1|localhost"), "{response}");
+
+    // address & port are covered by the first inherited socket
+    assert!(TcpStream::connect(v4(SETTING_PORT)).is_err());
+}
+
+#[test]
+#[serial]
+fn inherited_unix_socket_is_left_in_place() {
+    let path = std::env::temp_dir().join(format!("pipelined_activation_{}.sock", std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    let inherited = UnixListener::bind(&path).unwrap();
+
+    let server = start(server(
+        setting(Vec::new()),
+        vec![OwnedFd::from(inherited)],
+    ));
+
+    let stream = UnixStream::connect(&path).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+    let response = request(stream, "GET / HTTP/1.1\r\nhost:localhost\r\n\r\n");
+    assert!(response.ends_with("\r\n\r\n0|localhost"), "{response}");
+
+    server.stop();
+
+    // the socket's file belongs to the process that bound it
+    assert!(fs::symlink_metadata(&path).is_ok());
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn sockets_that_cant_be_served_are_rejected() {
+    let datagram = UdpSocket::bind(v4(0)).unwrap();
+
+    let not_listening = socket2::Socket::new(socket2::Domain::IPV4, socket2::Type::STREAM, None).unwrap();
+    not_listening.bind(&v4(0).into()).unwrap();
+
+    let mut rejected: Vec<OwnedFd> = vec![OwnedFd::from(datagram)];
+
+    if cfg!(target_os = "linux") {
+        rejected.push(OwnedFd::from(not_listening));
+    }
+
+    for fd in rejected {
+        let err = server(setting(Vec::new()), vec![fd]).run().unwrap_err();
+
+        assert!(matches!(err, ServerError::Bind { .. }), "{err}");
+    }
+}
@@ -0,0 +1,106 @@
+use std::{
+    collections::HashMap,
+    net::TcpListener,
+    sync::mpsc,
+    thread,
+};
+
+use serial_test::serial;
+
+use crate::{
+    http::{
+        request::Request,
+        response::{response_status_code::ResponseStatusCode, Response},
+    },
+    pipeline::{builder::pipeline::Builder, default, BuildError, Server, ServerError, Stream},
+    setting::ServerSetting,
+};
+
+const ADDRESS: &str = "localhost";
+const PORT: u16 = 8093;
+
+fn not_found(
+    _: &Result<Request, ResponseStatusCode>,
+    _: &ServerSetting,
+    _: &mut mpsc::Sender<()>,
+) -> Result<Response, ResponseStatusCode> {
+    Err(ResponseStatusCode::NotFound)
+}
+
+fn setting() -> ServerSetting {
+    ServerSetting {
+        address: ADDRESS.to_string(),
+        port: PORT,
+        paths: HashMap::new(),
+        pipelines: Some(1),
+        listeners: Vec::new(),
+    }
+}
+
+fn builder() -> Builder<()> {
+    let (tx, _rx) = mpsc::channel();
+
+    Builder::default()
+        .set_settings(setting())
+        .set_parser(|stream: &mut dyn Stream| default::parser::parser::<64, 1024, 200, 1000>(stream))
+        .set_action(not_found)
+        .set_compression(default::no_compression)
+        .set_utility_thread(tx)
+}
+
+#[test]
+fn complete_builder_is_valid() {
+    assert_eq!(builder().validate(), Ok(()));
+}
+
+#[test]
+fn missing_pieces_are_named() {
+    assert_eq!(Builder::<()>::default().validate(), Err(BuildError::MissingParser));
+
+    let mut missing = builder();
+    missing.action = None;
+    assert_eq!(missing.validate(), Err(BuildError::MissingAction));
+
+    let mut missing = builder();
+    missing.compression = None;
+    assert_eq!(missing.validate(), Err(BuildError::MissingCompression));
+
+    let mut missing = builder();
+    missing.utility_sender = None;
+    assert_eq!(missing.validate(), Err(BuildError::MissingUtilitySender));
+
+    let mut missing = builder();
+    missing.settings = None;
+    assert_eq!(missing.validate(), Err(BuildError::MissingSettings));
+    assert_eq!(missing.build().err(), Some(BuildError::MissingSettings));
+}
+
+#[test]
+fn try_new_rejects_missing_stage() {
+    let mut missing = builder();
+    missing.parser = None;
+
+    let (tx, _rx) = mpsc::channel();
+    let server = Server::try_new(setting(), (tx, thread::spawn(|| {})), missing);
+
+    assert_eq!(server.err(), Some(BuildError::MissingParser));
+}
+
+#[test]
+#[serial]
+fn run_returns_bind_error() {
+    let _occupied = TcpListener::bind(format!("{ADDRESS}:{PORT}")).unwrap();
+
+    let (tx, _rx) = mpsc::channel();
+    let server = Server::try_new(setting(), (tx, thread::spawn(|| {})), builder()).unwrap();
+
+    let handle = server.shutdown_handle();
+
+    match server.run() {
+        Err(ServerError::Bind { address, .. }) => assert_eq!(address, format!("{ADDRESS}:{PORT}")),
+        result => panic!("expected bind error: {result:?}"),
+    }
+
+    // waiters are released even though the server never started
+    handle.wait();
+}
@@ -0,0 +1,142 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serial_test::serial;
+
+use crate::{
+    http::{
+        request::Request,
+        response::{response_status_code::ResponseStatusCode, Response},
+    },
+    pipeline::{builder::pipeline::Builder, Connection},
+    setting::ServerSetting,
+    test_tools::server_env::{self, builder, setting, ServerEnv},
+};
+
+const PORT: u16 = 8094;
+
+fn start(builder: Builder<()>) -> ServerEnv<()> {
+    ServerEnv::new(server_env::server(setting(PORT), builder), PORT)
+}
+
+fn request(raw: &str) -> Option<String> {
+    server_env::request(PORT, raw)
+}
+
+/// authorize rejects requests without an authorization header
+fn authorize(
+    _: &Connection,
+    request: Result<Request, ResponseStatusCode>,
+    _: &ServerSetting,
+) -> Result<Request, ResponseStatusCode> {
+    let request = request?;
+
+    match request.headers.contains("authorization") {
+        true => Ok(request),
+        false => Err(ResponseStatusCode::Unauthorized),
+    }
+}
+
+/// tag returns a response stage appending name to the x-stages header
+fn tag(name: &'static str) -> impl Fn(&Connection, Response, Option<&Request>, &ServerSetting) -> Response {
+    move |_, mut response, _, _| {
+        let stages = match response.header.remove("x-stages") {
+            Some(stages) => format!("{stages},{name}"),
+            None => name.to_string(),
+        };
+        response.header.insert(String::from("x-stages"), stages);
+
+        response
+    }
+}
+
+#[test]
+fn stages_are_built_in_order() {
+    let builder = builder()
+        .set_settings(setting(PORT))
+        .add_request_stage("Authorize", authorize)
+        .add_response_stage("First", tag("first"))
+        .add_response_stage("Second", tag("second"));
+
+    let (tx, pipeline) = builder.build().unwrap();
+
+    let names: Vec<&str> = pipeline.metrics.stages.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, vec!["Authorize", "First", "Second"]);
+
+    assert!(pipeline.pipeline_state());
+
+    // the parser drains until every sender is dropped
+    drop(tx);
+    assert!(pipeline.shutdown(Instant::now() + Duration::from_secs(1)));
+}
+
+#[test]
+#[serial]
+fn request_stage_rejects_request() {
+    let _server = start(builder().add_request_stage("Authorize", authorize));
+
+    let response = request("GET / HTTP/1.1\r\nhost:localhost\r\n\r\n").unwrap();
+    assert!(response.starts_with("HTTP/1.1 401"), "{response}");
+
+    let response = request("GET / HTTP/1.1\r\nhost:localhost\r\nauthorization:token\r\n\r\n").unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    assert!(response.ends_with("\r\n\r\nok"), "{response}");
+}
+
+#[test]
+#[serial]
+fn response_stages_run_in_order() {
+    let _server = start(
+        builder()
+            .add_response_stage("First", tag("first"))
+            .add_response_stage("Second", tag("second")),
+    );
+
+    let response = request("GET / HTTP/1.1\r\nhost:localhost\r\n\r\n").unwrap();
+    assert!(response.contains("x-stages: first,second\r\n"), "{response}");
+}
+
+/// a panicking custom stage is respawned by recovery like the built in stages
+#[test]
+#[serial]
+fn panicking_stage_is_recovered() {
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let stage = {
+        let calls = calls.clone();
+
+        move |_: &Connection, request: Result<Request, ResponseStatusCode>, _: &ServerSetting| {
+            // connections opened while waiting for the server to start are not counted
+            if request.is_ok() && calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                panic!("first request fails");
+            }
+
+            request
+        }
+    };
+
+    let _server = start(builder().add_request_stage("Flaky", stage));
+
+    // connection is dropped along with the panicking thread
+    assert_eq!(request("GET / HTTP/1.1\r\nhost:localhost\r\n\r\n").unwrap_or_default(), "");
+
+    let start = Instant::now();
+    let response = loop {
+        match request("GET / HTTP/1.1\r\nhost:localhost\r\n\r\n") {
+            Some(response) if !response.is_empty() => break response,
+            _ => {
+                assert!(start.elapsed() < Duration::from_secs(5), "stage was not recovered");
+                thread::sleep(Duration::from_millis(25));
+            }
+        }
+    };
+
+    assert!(response.ends_with("\r\n\r\nok"), "{response}");
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,91 @@
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    sync::{atomic::Ordering, Arc},
+};
+
+use crate::pipeline::dispatch::{DispatchStrategy, Dispatcher, PipelineLoad};
+
+fn loads(queued: &[usize]) -> Vec<PipelineLoad> {
+    queued
+        .iter()
+        .map(|&queued| {
+            let load = PipelineLoad {
+                connections: Arc::default(),
+                queues: Vec::new(),
+            };
+            load.connections.store(queued, Ordering::Release);
+
+            load
+        })
+        .collect()
+}
+
+#[test]
+fn round_robin_cycles() {
+    let loads = loads(&[0, 0, 0]);
+    let mut dispatcher = Dispatcher::new(DispatchStrategy::RoundRobin);
+
+    let selected: Vec<usize> = (0..6).map(|_| dispatcher.select(&loads, None)).collect();
+
+    assert_eq!(selected, vec![1, 2, 0, 1, 2, 0]);
+}
+
+#[test]
+fn least_loaded_picks_minimum() {
+    let loads = loads(&[4, 1, 3, 2]);
+    let mut dispatcher = Dispatcher::new(DispatchStrategy::LeastLoaded);
+
+    assert_eq!(dispatcher.select(&loads, None), 1);
+
+    loads[3].connections.store(0, Ordering::Release);
+
+    assert_eq!(dispatcher.select(&loads, None), 3);
+}
+
+#[test]
+fn power_of_two_avoids_most_loaded() {
+    let loads = loads(&[0, 10]);
+    let mut dispatcher = Dispatcher::new(DispatchStrategy::PowerOfTwoChoices);
+
+    // with two pipelines both are always compared
+    for _ in 0..32 {
+        assert_eq!(dispatcher.select(&loads, None), 0);
+    }
+}
+
+#[test]
+fn power_of_two_stays_in_bounds() {
+    let loads = loads(&[0, 0, 0, 0, 0]);
+    let mut dispatcher = Dispatcher::new(DispatchStrategy::PowerOfTwoChoices);
+
+    for _ in 0..256 {
+        assert!(dispatcher.select(&loads, None) < loads.len());
+    }
+}
+
+#[test]
+fn client_ip_hash_is_sticky() {
+    let loads = loads(&[0, 0, 0, 0]);
+    let mut dispatcher = Dispatcher::new(DispatchStrategy::ClientIpHash);
+
+    let peer = Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 7)));
+    let first = dispatcher.select(&loads, peer);
+
+    for _ in 0..8 {
+        assert_eq!(dispatcher.select(&loads, peer), first);
+    }
+}
+
+#[test]
+fn single_pipeline() {
+    let loads = loads(&[3]);
+
+    for strategy in [
+        DispatchStrategy::RoundRobin,
+        DispatchStrategy::LeastLoaded,
+        DispatchStrategy::PowerOfTwoChoices,
+        DispatchStrategy::ClientIpHash,
+    ] {
+        assert_eq!(Dispatcher::new(strategy).select(&loads, None), 0);
+    }
+}
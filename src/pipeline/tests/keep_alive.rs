@@ -0,0 +1,373 @@
+mod negotiate {
+    use std::{collections::HashMap, time::Duration};
+
+    use crate::{
+        http::{
+            body::{Body, ContentType, Text},
+            method::Method,
+            request::Request,
+            response::{response_status_code::ResponseStatusCode, Response},
+            version::Version,
+        },
+        pipeline::KeepAlive,
+    };
+
+    const KEEP_ALIVE: KeepAlive = KeepAlive {
+        idle_timeout: Duration::from_secs(5),
+        max_requests: 3,
+    };
+
+    fn request(version: Version, connection: Option<&str>) -> Result<Request, ResponseStatusCode> {
+        let mut request = Request::new(Method::Get, "/", version);
+
+        if let Some(connection) = connection {
+            request.headers.insert("connection", connection);
+        }
+
+        Ok(request)
+    }
+
+    fn response() -> Response {
+        Response {
+            status: ResponseStatusCode::Ok,
+            header: HashMap::new(),
+            body: Some(Body {
+                content_type: ContentType::Text(Text::plain),
+                content: b"ok".to_vec(),
+            }),
+        }
+    }
+
+    #[test]
+    fn http_1_1_defaults_to_keep_alive() {
+        let mut response = response();
+
+        assert!(KEEP_ALIVE.negotiate(1, &request(Version::Http1_1, None), &mut response));
+        assert_eq!(response.header.get("Connection").unwrap(), "keep-alive");
+        assert_eq!(response.header.get("Keep-Alive").unwrap(), "timeout=5, max=2");
+    }
+
+    #[test]
+    fn http_1_0_defaults_to_close() {
+        let mut response = response();
+
+        assert!(!KEEP_ALIVE.negotiate(1, &request(Version::Http1_0, None), &mut response));
+        assert_eq!(response.header.get("Connection").unwrap(), "close");
+
+        let mut response = self::response();
+
+        assert!(KEEP_ALIVE.negotiate(1, &request(Version::Http1_0, Some("Keep-Alive")), &mut response));
+        assert_eq!(response.header.get("Connection").unwrap(), "keep-alive");
+    }
+
+    #[test]
+    fn connection_close_is_honoured() {
+        let mut response = response();
+
+        assert!(!KEEP_ALIVE.negotiate(1, &request(Version::Http1_1, Some("keep-alive, close")), &mut response));
+        assert_eq!(response.header.get("Connection").unwrap(), "close");
+        assert!(!response.header.contains_key("Keep-Alive"));
+    }
+
+    #[test]
+    fn max_requests_closes() {
+        let mut response = response();
+
+        assert!(!KEEP_ALIVE.negotiate(3, &request(Version::Http1_1, None), &mut response));
+        assert_eq!(response.header.get("Connection").unwrap(), "close");
+    }
+
+    #[test]
+    fn bodyless_responses_keep_alive() {
+        for status in [ResponseStatusCode::NoContent, ResponseStatusCode::NotModified] {
+            let mut response = response();
+            response.status = status;
+            response.body = None;
+
+            assert!(KEEP_ALIVE.negotiate(1, &request(Version::Http1_1, None), &mut response));
+            assert!(!response.header.contains_key("Content-Length"));
+        }
+
+        let mut head = Request::new(Method::Head, "/", Version::Http1_1);
+        head.headers.insert("connection", "keep-alive");
+
+        let mut response = response();
+        response.body = None;
+
+        assert!(KEEP_ALIVE.negotiate(1, &Ok(head), &mut response));
+        assert!(!response.header.contains_key("Content-Length"));
+
+        // e.g. a redirect; its end is told by an empty body
+        let mut response = self::response();
+        response.status = ResponseStatusCode::Found;
+        response.body = None;
+
+        assert!(KEEP_ALIVE.negotiate(1, &request(Version::Http1_1, None), &mut response));
+        assert_eq!(response.header.get("Content-Length").unwrap(), "0");
+    }
+
+    #[test]
+    fn unframed_or_failed_requests_close() {
+        let mut response = response();
+        response.body = None;
+        response
+            .header
+            .insert(String::from("Transfer-Encoding"), String::from("chunked"));
+
+        assert!(!KEEP_ALIVE.negotiate(1, &request(Version::Http1_1, None), &mut response));
+
+        let mut response = self::response();
+
+        assert!(!KEEP_ALIVE.negotiate(1, &Err(ResponseStatusCode::BadRequest), &mut response));
+        assert_eq!(response.header.get("Connection").unwrap(), "close");
+    }
+}
+
+mod server {
+    use std::{
+        collections::HashMap,
+        io::{Read, Write},
+        net::TcpStream,
+        sync::mpsc,
+        time::{Duration, Instant},
+    };
+
+    use serial_test::serial;
+
+    use crate::{
+        http::{
+            body::{Body, ContentType, Text},
+            method::Method,
+            request::Request,
+            response::{response_status_code::ResponseStatusCode, Response},
+        },
+        pipeline::KeepAlive,
+        setting::ServerSetting,
+        test_tools::server_env::{self, ServerEnv, ADDRESS},
+    };
+
+    const PORT: u16 = 8091;
+
+    fn echo_action(
+        request: &Result<Request, ResponseStatusCode>,
+        _: &ServerSetting,
+        _: &mut mpsc::Sender<()>,
+    ) -> Result<Response, ResponseStatusCode> {
+        let status = match request {
+            // answered without a body; as a HEAD request or with 204
+            Ok(request) if request.method == Method::Head => ResponseStatusCode::Ok,
+            Ok(request) if request.path == "/empty" => ResponseStatusCode::NoContent,
+            Ok(_) => ResponseStatusCode::Ok,
+            Err(err) => *err,
+        };
+
+        let body = match request {
+            Ok(request) if request.method == Method::Head || request.path == "/empty" => None,
+            _ => Some(Body {
+                content_type: ContentType::Text(Text::plain),
+                content: b"done".to_vec(),
+            }),
+        };
+
+        Ok(Response {
+            status,
+            header: HashMap::new(),
+            body,
+        })
+    }
+
+    fn start_server(keep_alive: KeepAlive) -> ServerEnv<()> {
+        let builder = server_env::builder().set_action(echo_action).set_keep_alive(keep_alive);
+
+        ServerEnv::new(server_env::server(server_env::setting(PORT), builder), PORT)
+    }
+
+    fn connect() -> TcpStream {
+        let stream = TcpStream::connect(format!("{ADDRESS}:{PORT}")).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+        stream
+    }
+
+    /// read_response reads a single response framed by its Content-Length; a response without one has no body
+    fn read_response(stream: &mut TcpStream) -> String {
+        let mut response = Vec::new();
+        let mut byte = [0; 1];
+
+        while !response.ends_with(b"\r\n\r\n") {
+            assert_eq!(stream.read(&mut byte).unwrap(), 1, "connection closed mid response");
+            response.push(byte[0]);
+        }
+
+        let head = String::from_utf8(response.clone()).unwrap();
+        let content_length: usize = head
+            .lines()
+            .find_map(|line| line.strip_prefix("Content-Length: "))
+            .map_or(0, |length| length.parse().unwrap());
+
+        let mut body = vec![0; content_length];
+        stream.read_exact(&mut body).unwrap();
+        response.append(&mut body);
+
+        String::from_utf8(response).unwrap()
+    }
+
+    fn is_closed(stream: &mut TcpStream) -> bool {
+        matches!(stream.read(&mut [0; 1]), Ok(0))
+    }
+
+    #[test]
+    #[serial]
+    fn requests_share_connection() {
+        let _server = start_server(KeepAlive::default());
+
+        let mut stream = connect();
+
+        for _ in 0..3 {
+            stream.write_all(b"GET / HTTP/1.1\r\nhost:localhost\r\n\r\n").unwrap();
+
+            let response = read_response(&mut stream);
+
+            assert!(response.starts_with("HTTP/1.1 200 Ok\r\n"));
+            assert!(response.contains("Connection: keep-alive\r\n"));
+            assert!(response.ends_with("done"));
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn bodyless_responses_end_their_head() {
+        let _server = start_server(KeepAlive::default());
+
+        let mut stream = connect();
+
+        // the request following each bodyless response is only answered if its head was ended
+        for request in [
+            &b"GET /empty HTTP/1.1\r\nhost:localhost\r\n\r\n"[..],
+            b"HEAD / HTTP/1.1\r\nhost:localhost\r\n\r\n",
+        ] {
+            stream.write_all(request).unwrap();
+
+            let response = read_response(&mut stream);
+            assert!(response.contains("Connection: keep-alive\r\n"), "{response}");
+            assert!(response.ends_with("\r\n\r\n"), "{response}");
+
+            stream.write_all(b"GET / HTTP/1.1\r\nhost:localhost\r\n\r\n").unwrap();
+
+            let response = read_response(&mut stream);
+            assert!(response.starts_with("HTTP/1.1 200 Ok\r\n"), "{response}");
+            assert!(response.ends_with("\r\n\r\ndone"), "{response}");
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn connection_close_closes() {
+        let _server = start_server(KeepAlive::default());
+
+        let mut stream = connect();
+
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nhost:localhost\r\nconnection: close\r\n\r\n")
+            .unwrap();
+
+        let response = read_response(&mut stream);
+
+        assert!(response.contains("Connection: close\r\n"));
+        assert!(is_closed(&mut stream));
+    }
+
+    #[test]
+    #[serial]
+    fn http_1_0_is_answered_in_kind_and_closed() {
+        let _server = start_server(KeepAlive::default());
+
+        let mut stream = connect();
+
+        stream.write_all(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+
+        let response = read_response(&mut stream);
+
+        assert!(response.starts_with("HTTP/1.0 200 Ok\r\n"), "{response}");
+        assert!(response.contains("Connection: close\r\n"), "{response}");
+        assert!(is_closed(&mut stream));
+    }
+
+    #[test]
+    #[serial]
+    fn unsupported_version_is_rejected() {
+        let _server = start_server(KeepAlive::default());
+
+        let mut stream = connect();
+
+        stream.write_all(b"GET / HTTP/2.0\r\nhost:localhost\r\n\r\n").unwrap();
+
+        let response = read_response(&mut stream);
+
+        assert!(response.starts_with("HTTP/1.1 505 "), "{response}");
+    }
+
+    #[test]
+    #[serial]
+    fn max_requests_closes() {
+        let _server = start_server(KeepAlive {
+            max_requests: 2,
+            ..KeepAlive::default()
+        });
+
+        let mut stream = connect();
+
+        stream.write_all(b"GET / HTTP/1.1\r\nhost:localhost\r\n\r\n").unwrap();
+        assert!(read_response(&mut stream).contains("Connection: keep-alive\r\n"));
+
+        stream.write_all(b"GET / HTTP/1.1\r\nhost:localhost\r\n\r\n").unwrap();
+        assert!(read_response(&mut stream).contains("Connection: close\r\n"));
+
+        assert!(is_closed(&mut stream));
+    }
+
+    #[test]
+    #[serial]
+    fn idle_connection_times_out() {
+        let _server = start_server(KeepAlive {
+            idle_timeout: Duration::from_millis(100),
+            ..KeepAlive::default()
+        });
+
+        let mut stream = connect();
+
+        stream.write_all(b"GET / HTTP/1.1\r\nhost:localhost\r\n\r\n").unwrap();
+        assert!(read_response(&mut stream).contains("Connection: keep-alive\r\n"));
+
+        let start = Instant::now();
+
+        assert!(is_closed(&mut stream));
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    #[serial]
+    fn pipelined_requests_are_answered_in_order() {
+        let _server = start_server(KeepAlive::default());
+
+        let mut stream = connect();
+
+        // both requests arrive in a single packet; the body of the first is framed by its Content-Length
+        stream
+            .write_all(
+                b"POST / HTTP/1.1\r\nhost:localhost\r\ncontent-type:text/plain\r\ncontent-length:5\r\n\r\nhello\
+                GET / HTTP/1.1\r\nhost:localhost\r\nconnection: close\r\n\r\n",
+            )
+            .unwrap();
+
+        let response = read_response(&mut stream);
+        assert!(response.starts_with("HTTP/1.1 200 Ok\r\n"), "{response}");
+        assert!(response.contains("Connection: keep-alive\r\n"), "{response}");
+
+        let response = read_response(&mut stream);
+        assert!(response.starts_with("HTTP/1.1 200 Ok\r\n"), "{response}");
+        assert!(response.contains("Connection: close\r\n"), "{response}");
+
+        assert!(is_closed(&mut stream));
+    }
+}
@@ -0,0 +1,307 @@
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    sync::mpsc,
+    time::Duration,
+};
+
+use serial_test::serial;
+
+use crate::{
+    http::{
+        body::{Body, ContentType, Text},
+        request::Request,
+        response::{response_status_code::ResponseStatusCode, Response},
+    },
+    pipeline::{builder::pipeline::Builder, Connection, Listener},
+    setting::{ListenerSetting, ServerSetting},
+    test_tools::server_env::{self, ServerEnv},
+};
+
+const V4_PORT: u16 = 8098;
+const DUAL_PORT: u16 = 8099;
+const V6_PORT: u16 = 8100;
+
+fn setting(listeners: Vec<ListenerSetting>) -> ServerSetting {
+    ServerSetting {
+        address: String::from("localhost"),
+        port: V4_PORT,
+        paths: HashMap::new(),
+        pipelines: Some(1),
+        listeners,
+    }
+}
+
+/// tag_listener records the index of the listener that accepted the connection in the x-listener header
+fn tag_listener(
+    connection: &Connection,
+    request: Result<Request, ResponseStatusCode>,
+    _: &ServerSetting,
+) -> Result<Request, ResponseStatusCode> {
+    let mut request = request?;
+
+    if let Some(listener) = connection.listener() {
+        request.headers.insert("x-listener", &listener.index().to_string());
+    }
+
+    Ok(request)
+}
+
+/// echo answers with the listener & host of the request
+fn echo(
+    request: &Result<Request, ResponseStatusCode>,
+    _: &ServerSetting,
+    _: &mut mpsc::Sender<()>,
+) -> Result<Response, ResponseStatusCode> {
+    let request = match request {
+        Ok(request) => request,
+        Err(err) => return Err(*err),
+    };
+
+    let field = |name: &str| request.headers.get(name).unwrap_or_default().to_string();
+
+    Ok(Response {
+        status: ResponseStatusCode::Ok,
+        header: HashMap::new(),
+        body: Some(Body {
+            content_type: ContentType::Text(Text::plain),
+            content: format!("{}|{}", field("x-listener"), field("host")).into_bytes(),
+        }),
+    })
+}
+
+fn builder() -> Builder<()> {
+    server_env::builder().set_action(echo)
+}
+
+fn start(listeners: Vec<ListenerSetting>, wait_on: SocketAddr) -> ServerEnv<()> {
+    let builder = builder().add_request_stage("Tag listener", tag_listener);
+
+    ServerEnv::new_on(server_env::server(setting(listeners), builder), wait_on)
+}
+
+fn request(address: SocketAddr, raw: &str) -> String {
+    server_env::request_to(address, raw).unwrap()
+}
+
+fn v4(port: u16) -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], port))
+}
+
+fn v6(port: u16) -> SocketAddr {
+    SocketAddr::from(([0, 0, 0, 0, 0, 0, 0, 1], port))
+}
+
+#[test]
+#[serial]
+fn listeners_feed_the_same_pipelines() {
+    let server = start(
+        vec![
+            ListenerSetting::new("127.0.0.1", V4_PORT),
+            ListenerSetting::new("::", DUAL_PORT),
+        ],
+        v4(V4_PORT),
+    );
+
+    let raw = "GET / HTTP/1.1\r\nhost:localhost\r\n\r\n";
+
+    assert!(request(v4(V4_PORT), raw).ends_with("\r\n\r\n0|localhost"));
+
+    // the IPv6 wildcard is dual-stack unless ipv6_only is set
+    assert!(request(v4(DUAL_PORT), raw).ends_with("\r\n\r\n1|localhost"));
+    assert!(request(v6(DUAL_PORT), raw).ends_with("\r\n\r\n1|localhost"));
+
+    // every listener is woken & closed on shutdown
+    drop(server);
+
+    assert!(TcpStream::connect(v4(V4_PORT)).is_err());
+    assert!(TcpStream::connect(v6(DUAL_PORT)).is_err());
+}
+
+#[test]
+#[serial]
+fn ipv6_only_listener_refuses_ipv4() {
+    let _server = start(
+        vec![ListenerSetting {
+            ipv6_only: true,
+            ..ListenerSetting::new("[::]", V6_PORT)
+        }],
+        v6(V6_PORT),
+    );
+
+    assert!(request(v6(V6_PORT), "GET / HTTP/1.1\r\nhost:localhost\r\n\r\n").ends_with("\r\n\r\n0|localhost"));
+    assert!(TcpStream::connect(v4(V6_PORT)).is_err());
+}
+
+#[test]
+#[serial]
+fn listener_options_apply_to_requests() {
+    let _server = start(
+        vec![
+            ListenerSetting::new("127.0.0.1", V4_PORT),
+            ListenerSetting {
+                allowed_hosts: vec![String::from("example.com"), String::from("www.example.com")],
+                default_host: Some(String::from("example.com")),
+                ..ListenerSetting::new("127.0.0.1", DUAL_PORT)
+            },
+        ],
+        v4(V4_PORT),
+    );
+
+    let response = request(v4(DUAL_PORT), "GET / HTTP/1.1\r\nhost:localhost\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 421"), "{response}");
+
+    let response = request(v4(DUAL_PORT), "GET / HTTP/1.1\r\nhost:WWW.example.com\r\n\r\n");
+    assert!(response.ends_with("\r\n\r\n1|WWW.example.com"), "{response}");
+
+    let response = request(v4(DUAL_PORT), "GET / HTTP/1.1\r\n\r\n");
+    assert!(response.ends_with("\r\n\r\n1|example.com"), "{response}");
+
+    // the options of one listener don't apply to the others
+    let response = request(v4(V4_PORT), "GET / HTTP/1.1\r\nhost:localhost\r\n\r\n");
+    assert!(response.ends_with("\r\n\r\n0|localhost"), "{response}");
+}
+
+#[test]
+#[serial]
+fn bind_failure_is_reported() {
+    let taken = std::net::TcpListener::bind(v4(V4_PORT)).unwrap();
+
+    let listeners = vec![
+        ListenerSetting::new("127.0.0.1", DUAL_PORT),
+        ListenerSetting::new("127.0.0.1", V4_PORT),
+    ];
+
+    let server = server_env::server(setting(listeners), builder());
+
+    assert!(matches!(server.run(), Err(crate::pipeline::ServerError::Bind { .. })));
+
+    // listeners bound before the failure are closed
+    assert!(TcpStream::connect(v4(DUAL_PORT)).is_err());
+
+    drop(taken);
+}
+
+#[test]
+fn route_without_listener_options() {
+    let listener = Listener::new(0, ListenerSetting::new("localhost", V4_PORT));
+    let request: Request = "GET / HTTP/1.1\r\nhost:localhost\r\n\r\n".parse().unwrap();
+
+    assert_eq!(listener.route(Ok(request)).unwrap().headers.get("host"), Some("localhost"));
+    assert_eq!(listener.route(Err(ResponseStatusCode::BadRequest)), Err(ResponseStatusCode::BadRequest));
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::{
+        fs,
+        os::unix::{
+            fs::PermissionsExt,
+            net::{UnixListener, UnixStream},
+        },
+        path::PathBuf,
+    };
+
+    use super::*;
+
+    use crate::pipeline::ServerError;
+
+    /// socket_path returns a socket path unique to the test
+    fn socket_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("pipelined_listener_{}_{name}.sock", std::process::id()))
+    }
+
+    fn unix_request(path: &PathBuf, raw: &str) -> String {
+        let stream = UnixStream::connect(path).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+        server_env::exchange(stream, raw).unwrap()
+    }
+
+    #[test]
+    #[serial]
+    fn unix_socket_feeds_the_same_pipelines() {
+        let path = socket_path("serve");
+
+        let server = start(
+            vec![
+                ListenerSetting::new("127.0.0.1", V4_PORT),
+                ListenerSetting {
+                    mode: Some(0o600),
+                    default_host: Some(String::from("localhost")),
+                    ..ListenerSetting::unix(&path)
+                },
+            ],
+            v4(V4_PORT),
+        );
+
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+
+        // the default parser, action & sender are unchanged over a unix socket
+        let response = unix_request(&path, "GET / HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.ends_with("\r\n\r\n1|localhost"), "{response}");
+
+        assert!(request(v4(V4_PORT), "GET / HTTP/1.1\r\nhost:localhost\r\n\r\n").ends_with("\r\n\r\n0|localhost"));
+
+        // the socket's file is removed on shutdown
+        drop(server);
+
+        assert!(!path.exists());
+    }
+
+    #[test]
+    #[serial]
+    fn stale_socket_is_replaced() {
+        let path = socket_path("stale");
+
+        // a dropped listener leaves its file behind, as a crashed server would
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        let _server = start(
+            vec![ListenerSetting::new("127.0.0.1", V4_PORT), ListenerSetting::unix(&path)],
+            v4(V4_PORT),
+        );
+
+        assert!(unix_request(&path, "GET / HTTP/1.1\r\nhost:localhost\r\n\r\n").ends_with("\r\n\r\n1|localhost"));
+    }
+
+    #[test]
+    #[serial]
+    fn socket_in_use_is_not_replaced() {
+        let path = socket_path("in_use");
+        let live = UnixListener::bind(&path).unwrap();
+
+        let server = server_env::server(setting(vec![ListenerSetting::unix(&path)]), builder());
+
+        assert!(matches!(server.run(), Err(ServerError::Bind { .. })));
+
+        // the live socket keeps its file
+        assert!(UnixStream::connect(&path).is_ok());
+
+        drop(live);
+        fs::remove_file(path).unwrap();
+    }
+
+    /// idle kept alive connections are polled the same way over a unix socket
+    #[test]
+    fn unix_stream_readiness() {
+        use crate::pipeline::keep_alive::{readiness, Readiness};
+
+        let (mut server, mut client) = UnixStream::pair().unwrap();
+
+        assert_eq!(readiness(&mut server), Readiness::Idle);
+
+        client.write_all(b"GET").unwrap();
+        assert_eq!(readiness(&mut server), Readiness::Readable);
+
+        // peeking doesn't consume the request
+        assert_eq!(readiness(&mut server), Readiness::Readable);
+
+        drop(client);
+        server.read_to_end(&mut Vec::new()).unwrap();
+        assert_eq!(readiness(&mut server), Readiness::Closed);
+    }
+}
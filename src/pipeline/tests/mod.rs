@@ -1 +1,16 @@
 mod server;
+mod overflow;
+mod dispatch;
+mod keep_alive;
+mod stage;
+mod build;
+mod custom_stage;
+mod workers;
+mod scale;
+mod reload;
+mod listener;
+mod tls;
+#[cfg(unix)]
+mod activation;
+#[cfg(unix)]
+mod upgrade;
@@ -0,0 +1,111 @@
+use std::{
+    io::Read,
+    net::{TcpListener, TcpStream},
+    sync::Arc,
+    time::Duration,
+};
+
+use crate::pipeline::{
+    metrics::StageMetrics,
+    overflow::{Overflow, OverflowPolicy},
+    queue::BlockingQueue,
+};
+
+/// connection returns both ends of a local tcp connection
+fn connection() -> (TcpStream, TcpStream) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+
+    let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    client
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    let (server, _) = listener.accept().unwrap();
+
+    (client, server)
+}
+
+fn read_response(mut client: TcpStream) -> String {
+    let mut response = Vec::new();
+    client.read_to_end(&mut response).unwrap();
+
+    String::from_utf8(response).unwrap()
+}
+
+/// assert_rejection checks response is the 503 rendered for a policy with a retry_after of 3; whatever order its headers are in
+fn assert_rejection(response: String) {
+    assert!(response.starts_with("HTTP/1.1 503 Service Unavailable\r\n"), "{response}");
+    assert!(response.ends_with("\r\n\r\n503 Service Unavailable"), "{response}");
+
+    for header in ["Retry-After: 3", "Connection: close", "Content-Length: 23", "Content-Type: text/plain"] {
+        assert!(response.contains(&format!("\r\n{header}\r\n")), "{response}");
+    }
+}
+
+#[test]
+fn reject_answers_new_connection() {
+    let metrics = Arc::new(StageMetrics::default());
+    let overflow = Overflow::new(OverflowPolicy::Reject { retry_after: 3 }, metrics.clone());
+    let queue: BlockingQueue<(TcpStream, u8), 1> = BlockingQueue::default();
+
+    let (_queued_client, queued) = connection();
+    let (rejected_client, rejected) = connection();
+
+    overflow.push(&queue, (queued, 1));
+    overflow.push(&queue, (rejected, 2));
+
+    assert_rejection(read_response(rejected_client));
+
+    assert_eq!(queue.pop(Some(Duration::ZERO)).unwrap().1, 1);
+
+    assert_eq!(metrics.rejected(), 1);
+    assert_eq!(metrics.dropped(), 0);
+    assert_eq!(metrics.blocked(), 0);
+}
+
+#[test]
+fn drop_oldest_answers_queued_connection() {
+    let metrics = Arc::new(StageMetrics::default());
+    let overflow = Overflow::new(OverflowPolicy::DropOldest { retry_after: 3 }, metrics.clone());
+    let queue: BlockingQueue<(TcpStream, u8), 1> = BlockingQueue::default();
+
+    let (dropped_client, dropped) = connection();
+    let (_queued_client, queued) = connection();
+
+    overflow.push(&queue, (dropped, 1));
+    overflow.push(&queue, (queued, 2));
+
+    assert_rejection(read_response(dropped_client));
+
+    assert_eq!(queue.pop(Some(Duration::ZERO)).unwrap().1, 2);
+
+    assert_eq!(metrics.dropped(), 1);
+    assert_eq!(metrics.rejected(), 0);
+}
+
+#[test]
+fn block_waits_for_space() {
+    let metrics = Arc::new(StageMetrics::default());
+    let overflow = Overflow::new(OverflowPolicy::Block, metrics.clone());
+    let queue: Arc<BlockingQueue<(TcpStream, u8), 1>> = Arc::new(BlockingQueue::default());
+
+    let (_first_client, first) = connection();
+    let (_second_client, second) = connection();
+
+    overflow.push(&queue, (first, 1));
+
+    let producer = {
+        let queue = queue.clone();
+        let overflow = overflow.clone();
+        std::thread::spawn(move || overflow.push(&queue, (second, 2)))
+    };
+
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(!producer.is_finished());
+
+    assert_eq!(queue.pop(None).unwrap().1, 1);
+    producer.join().unwrap();
+    assert_eq!(queue.pop(None).unwrap().1, 2);
+
+    assert_eq!(metrics.blocked(), 1);
+}
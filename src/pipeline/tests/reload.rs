@@ -0,0 +1,213 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+use crate::{
+    setting::{ServerSetting, SettingsError, SettingsLoader},
+    test_tools::{dir_env::DirEnv, server_env},
+};
+
+const PORT: u16 = 8097;
+
+fn setting() -> ServerSetting {
+    server_env::setting(PORT)
+}
+
+/// settings_file writes contents to a file unique to the test
+fn settings_file(name: &str, contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("pipelined_reload_{}_{name}.ron", std::process::id()));
+
+    fs::write(&path, contents).unwrap();
+
+    path
+}
+
+/// domain returns settings serving localhost from the folder of the test named name
+fn domain(name: &str, port: u16, pipelines: &str) -> String {
+    format!(
+        r#"(address: "localhost", port: {port}, paths: {{"localhost": (path: "reload_{name}", allow: ["html"])}}, {pipelines})"#
+    )
+}
+
+/// domain_folder creates the folder domain expects under source; each test uses its own as they run in parallel
+fn domain_folder(name: &str) -> DirEnv {
+    DirEnv::new(&format!("source/reload_{name}"))
+}
+
+fn loader(path: &Path) -> SettingsLoader {
+    SettingsLoader::default().set_file(path).set_env(false)
+}
+
+mod apply {
+    use super::*;
+
+    use crate::pipeline::{reload::apply, scale::Scaler, ScaleHandle};
+
+    #[test]
+    fn swaps_valid_settings() {
+        let _folder = domain_folder("swap");
+        let path = settings_file("swap", &domain("swap", PORT, ""));
+        let settings = RwLock::new(setting());
+
+        apply(&loader(&path), &settings, &ScaleHandle::default()).unwrap();
+
+        let settings = settings.read().unwrap();
+        assert_eq!(settings.paths["localhost"].allow, vec![String::from("html")]);
+        assert_eq!(settings.pipelines, None);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn keeps_settings_on_failure() {
+        let settings = RwLock::new(setting());
+
+        let path = settings_file("parse", "(address: \"localhost\", port: ");
+        assert!(matches!(apply(&loader(&path), &settings, &ScaleHandle::default()), Err(SettingsError::Parse { .. })));
+        fs::remove_file(path).unwrap();
+
+        let path = settings_file("invalid", &domain("invalid", 0, ""));
+        assert!(matches!(apply(&loader(&path), &settings, &ScaleHandle::default()), Err(SettingsError::Invalid(_))));
+        fs::remove_file(path).unwrap();
+
+        let path = settings_file("missing", "");
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(apply(&loader(&path), &settings, &ScaleHandle::default()), Err(SettingsError::Io { .. })));
+
+        assert!(settings.read().unwrap().paths.is_empty());
+    }
+
+    #[test]
+    fn listener_is_kept_and_pipelines_resized() {
+        let _folder = domain_folder("listener");
+        let path = settings_file("listener", &domain("listener", PORT + 100, "pipelines: Some(3)"));
+        let settings = RwLock::new(setting());
+        let scale = ScaleHandle::default();
+
+        apply(&loader(&path), &settings, &scale).unwrap();
+
+        assert_eq!(settings.read().unwrap().port, PORT);
+        assert_eq!(settings.read().unwrap().pipelines, Some(3));
+        assert_eq!(Scaler::new(scale, None).target(1, 0), Some(3));
+
+        fs::remove_file(path).unwrap();
+    }
+}
+
+mod server {
+    use std::{
+        sync::mpsc,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use serial_test::serial;
+
+    use super::*;
+
+    use crate::{
+        http::{
+            body::{Body, ContentType, Text},
+            request::Request,
+            response::{response_status_code::ResponseStatusCode, Response},
+        },
+        pipeline::Reload,
+        test_tools::server_env::ServerEnv,
+    };
+
+    /// paths answers with the domains in the current settings
+    fn paths(
+        request: &Result<Request, ResponseStatusCode>,
+        settings: &ServerSetting,
+        _: &mut mpsc::Sender<()>,
+    ) -> Result<Response, ResponseStatusCode> {
+        if let Err(err) = request {
+            return Err(*err);
+        }
+
+        let mut domains: Vec<&str> = settings.paths.keys().map(String::as_str).collect();
+        domains.sort();
+
+        Ok(Response {
+            status: ResponseStatusCode::Ok,
+            header: HashMap::new(),
+            body: Some(Body {
+                content_type: ContentType::Text(Text::plain),
+                content: format!("[{}]", domains.join(",")).into_bytes(),
+            }),
+        })
+    }
+
+    fn start(reload: Reload) -> ServerEnv<()> {
+        let server = server_env::server(setting(), server_env::builder().set_action(paths)).set_reload(reload);
+
+        ServerEnv::new(server, PORT)
+    }
+
+    fn request() -> String {
+        server_env::request(PORT, "GET / HTTP/1.1\r\nhost:localhost\r\n\r\n").unwrap()
+    }
+
+    /// wait_for_body blocks until the server responds with body
+    fn wait_for_body(body: &str) {
+        let start = Instant::now();
+
+        while !request().ends_with(&format!("\r\n\r\n{body}")) {
+            assert!(start.elapsed() < Duration::from_secs(5), "settings were not reloaded");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn watched_file_is_reloaded() {
+        let _folder = domain_folder("watch");
+        let path = settings_file("watch", "(address: \"localhost\", port: 8097, paths: {})");
+
+        let _server = start(Reload {
+            loader: loader(&path),
+            poll: Some(Duration::from_millis(10)),
+            hangup: false,
+        });
+
+        assert!(request().ends_with("\r\n\r\n[]"));
+
+        thread::sleep(Duration::from_millis(20));
+        fs::write(&path, domain("watch", PORT, "")).unwrap();
+        wait_for_body("[localhost]");
+
+        // an invalid file keeps the current settings
+        thread::sleep(Duration::from_millis(20));
+        fs::write(&path, "(address: ").unwrap();
+        thread::sleep(Duration::from_millis(100));
+        assert!(request().ends_with("\r\n\r\n[localhost]"));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    #[serial]
+    fn hangup_reloads_file() {
+        let _folder = domain_folder("hangup");
+        let path = settings_file("hangup", &domain("hangup", PORT, ""));
+
+        let _server = start(Reload {
+            loader: loader(&path),
+            poll: None,
+            hangup: true,
+        });
+
+        // the file isn't watched, so nothing changes until SIGHUP
+        thread::sleep(Duration::from_millis(150));
+        assert!(request().ends_with("\r\n\r\n[]"));
+
+        signal_hook::low_level::raise(signal_hook::consts::SIGHUP).unwrap();
+        wait_for_body("[localhost]");
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,176 @@
+mod policy {
+    use std::time::Duration;
+
+    use crate::pipeline::{
+        scale::{pipeline_count, Scaler},
+        AutoScale, ScaleHandle,
+    };
+
+    fn policy() -> AutoScale {
+        AutoScale {
+            min: 2,
+            max: 4,
+            grow_above: 8,
+            shrink_below: 2,
+            cooldown: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn grows_and_shrinks_within_bounds() {
+        let policy = policy();
+
+        assert_eq!(policy.target(2, 2 * 9), 3);
+        assert_eq!(policy.target(4, 4 * 9), 4);
+
+        assert_eq!(policy.target(3, 3 * 5), 3);
+
+        assert_eq!(policy.target(3, 3), 2);
+        assert_eq!(policy.target(2, 0), 2);
+
+        // counts outside the bounds are brought back within them
+        assert_eq!(policy.target(6, 6 * 5), 4);
+    }
+
+    #[test]
+    fn admin_request_overrides_policy() {
+        let handle = ScaleHandle::default();
+        let mut scaler = Scaler::new(handle.clone(), Some(policy()));
+
+        handle.scale_to(6);
+        assert_eq!(scaler.target(3, 0), Some(6));
+
+        // requests are only applied once
+        assert_eq!(scaler.target(6, 6 * 5), Some(4));
+
+        handle.scale_to(0);
+        assert_eq!(scaler.target(4, 0), Some(1));
+    }
+
+    #[test]
+    fn policy_waits_for_cooldown() {
+        let handle = ScaleHandle::default();
+        let mut scaler = Scaler::new(
+            handle,
+            Some(AutoScale {
+                cooldown: Duration::from_secs(60),
+                ..policy()
+            }),
+        );
+
+        assert_eq!(scaler.target(3, 0), None);
+    }
+
+    #[test]
+    fn count_defaults_to_available_parallelism() {
+        assert_eq!(pipeline_count(Some(3)), 3);
+        assert_eq!(
+            pipeline_count(None),
+            std::thread::available_parallelism().map(|count| count.get()).unwrap_or(1)
+        );
+    }
+}
+
+mod server {
+    use std::{
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use serial_test::serial;
+
+    use crate::{
+        pipeline::{AutoScale, Server, ServerError},
+        setting::ServerSetting,
+        test_tools::server_env::{self, builder, setting, ServerEnv},
+    };
+
+    const PORT: u16 = 8096;
+
+    fn server(pipelines: usize) -> Server<()> {
+        let setting = ServerSetting {
+            pipelines: Some(pipelines),
+            ..setting(PORT)
+        };
+
+        server_env::server(setting, builder())
+    }
+
+    /// wait_for_pipelines blocks until the server is running the given number of pipelines
+    fn wait_for_pipelines(server: &ServerEnv<()>, pipelines: usize) {
+        let handle = server.server().scale_handle();
+        let start = Instant::now();
+
+        while handle.pipelines() != pipelines || server.server().metrics().len() != pipelines {
+            assert!(start.elapsed() < Duration::from_secs(5), "server was not resized to {pipelines} pipelines");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn request() -> String {
+        server_env::request(PORT, "GET / HTTP/1.1\r\nhost:localhost\r\n\r\n").unwrap()
+    }
+
+    #[test]
+    #[serial]
+    fn starts_with_setting_count() {
+        let server = ServerEnv::new(server(3), PORT);
+        wait_for_pipelines(&server, 3);
+
+        for _ in 0..6 {
+            assert!(request().ends_with("\r\n\r\nok"));
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn admin_resize() {
+        let server = ServerEnv::new(server(1), PORT);
+        wait_for_pipelines(&server, 1);
+
+        let handle = server.server().scale_handle();
+
+        handle.scale_to(3);
+        wait_for_pipelines(&server, 3);
+
+        for _ in 0..6 {
+            assert!(request().ends_with("\r\n\r\nok"));
+        }
+
+        handle.scale_to(1);
+        wait_for_pipelines(&server, 1);
+
+        for _ in 0..3 {
+            assert!(request().ends_with("\r\n\r\nok"));
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn autoscale_shrinks_idle_server() {
+        let autoscale = AutoScale {
+            min: 1,
+            max: 3,
+            grow_above: 64,
+            shrink_below: 1,
+            cooldown: Duration::ZERO,
+        };
+
+        let server = ServerEnv::new(server(3).set_autoscale(autoscale), PORT);
+
+        wait_for_pipelines(&server, 1);
+
+        assert!(request().ends_with("\r\n\r\nok"));
+    }
+
+    #[test]
+    #[serial]
+    fn zero_pipelines_is_rejected() {
+        let server = server(0);
+        let handle = server.shutdown_handle();
+
+        assert!(matches!(server.run(), Err(ServerError::NoPipelines)));
+
+        handle.wait();
+    }
+}
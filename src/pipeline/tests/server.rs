@@ -1,8 +1,6 @@
 use std::{
     collections::HashMap,
-    net::TcpStream,
-    path::PathBuf,
-    sync::{mpsc::{Sender}, Arc, Condvar, Mutex},
+    sync::{Arc, Condvar, Mutex},
     thread::{self, JoinHandle},
 };
 use cyclic_data_types::list::List;
@@ -16,7 +14,8 @@ use crate::{
     },
     pipeline::{
         builder::pipeline::Builder,
-        Server,
+        default::action::FileUtilitySender,
+        Server, Stream,
     },
     setting::{DomainPath, ServerSetting},
 };
@@ -24,19 +23,19 @@ use crate::{
 const ADDRESS: &str = "localhost";
 const PORT: u16 = 8080;
 
+/// action serving files through a file utility thread
+type FileAction = fn(
+    &Result<Request, ResponseStatusCode>,
+    &ServerSetting,
+    &mut FileUtilitySender<FileError>,
+) -> Result<Response, ResponseStatusCode>;
+
 fn server_initialization(
     trigger_cond: Arc<(Mutex<bool>, Condvar)>,
-    parser: fn(&mut TcpStream) -> Result<Request, ResponseStatusCode>,
-    action: fn(
-        &Result<Request, ResponseStatusCode>,
-        &ServerSetting,
-        &mut Sender<(PathBuf, Sender<Result<Vec<u8>, FileError>>)>,
-    ) -> Result<Response, ResponseStatusCode>,
+    parser: fn(&mut dyn Stream) -> Result<Request, ResponseStatusCode>,
+    action: FileAction,
     compression: fn(Response, Option<Request>, ServerSetting) -> Vec<u8>,
-    utility_thread: (
-        Sender<(PathBuf, Sender<Result<Vec<u8>, FileError>>)>,
-        JoinHandle<()>,
-    ),
+    utility_thread: (FileUtilitySender<FileError>, JoinHandle<()>),
 ) -> JoinHandle<()> {
     thread::spawn(move || {
         let utility_thread = utility_thread;
@@ -53,10 +52,13 @@ fn server_initialization(
                     DomainPath {
                         path: String::from(""),
                         allow: vec![String::from("html")],
+                        tls: None,
                     },
                 );
                 tmp
             },
+            pipelines: Some(1),
+            listeners: Vec::new(),
         };
 
         trace!("Setting initialized ⚙️");
@@ -82,7 +84,7 @@ fn server_initialization(
         }
 
         trace!("Server starting 💽🏃‍♂️");
-        server.run::<1>();
+        let _ = server.run();
     })
 }
 
@@ -95,7 +97,7 @@ fn create_mb_string(n: usize) -> String {
     s
 }
 
-fn split_bytes_at_body<'a>(vec: &'a Vec<u8>) -> Option<(&'a[u8], &'a[u8])> {
+fn split_bytes_at_body(vec: &[u8]) -> Option<(&[u8], &[u8])> {
     let iter = vec.iter().enumerate();
     let mut buf: List<4, u8, true> = List::default();
     let target: List<4, u8, true> = [13, 10, 13, 10].into();
@@ -130,7 +132,7 @@ mod request_test{
                 default::{
                     self,
                     action::{generate_read_only_file_utility_thread, FileUtilitySender, NO_BOUND},
-                }, tests::server::{server_initialization, ADDRESS, PORT},
+                }, tests::server::{server_initialization, ADDRESS, PORT}, Stream,
             },
             setting::{ServerSetting},
             test_tools::file_env::FileEnv, logging::logger_init,
@@ -151,7 +153,7 @@ mod request_test{
             // start server in separated thread
             let server_thread = server_initialization(
                 Arc::clone(&pair),
-                |stream: &mut TcpStream| {
+                |stream: &mut dyn Stream| {
                     trace!("Starting parsing 📄🔍");
                     let data = default::parser::parser::<64, 1024, 20, 250>(stream);
                     trace!("Finished parsing 📄🔍\n{:?}", data);
@@ -203,7 +205,7 @@ mod request_test{
                 assert!(!server_thread.is_finished());
 
                 let mut data = [0; 128];
-                stream.read(&mut data).unwrap();
+                let _ = stream.read(&mut data).unwrap();
                 trace!("Response received 💻 📃💨 💽");
 
                 let response = String::from_utf8(data.to_vec());
@@ -240,7 +242,7 @@ mod request_test{
             // start server in separated thread
             let server_thread = server_initialization(
                 Arc::clone(&pair),
-                |stream: &mut TcpStream| {
+                |stream: &mut dyn Stream| {
                     trace!("Starting parsing 📄🔍");
                     let data = default::parser::parser::<64, 1024, 20, 250>(stream);
                     trace!("Finished parsing 📄🔍\n{:?}", data);
@@ -281,17 +283,17 @@ mod request_test{
                 let mut streams: [TcpStream; 4] =
                     core::array::from_fn(|_| TcpStream::connect(format!("{}:{}", ADDRESS, PORT)).unwrap());
 
-                for i in 0..4 {
+                for (i, stream) in streams.iter_mut().enumerate() {
                     trace!("Request {} sent 💽 📃💨 💻", i + 1);
-                    let _ = streams[i]
+                    let _ = stream
                         .write(format!("GET request_{}.html HTTP/1.1\n\rhost:localhost", i + 1).as_bytes());
-                    streams[i].shutdown(Shutdown::Write).unwrap();
+                    stream.shutdown(Shutdown::Write).unwrap();
                 }
-                for i in 0..4 {
+                for (i, stream) in streams.iter_mut().enumerate() {
                     assert!(!server_thread.is_finished());
 
                     let mut data = [0; 128];
-                    streams[i].read(&mut data).unwrap();
+                    let _ = stream.read(&mut data).unwrap();
                     trace!("Response {} received 💻 📃💨 💽", i + 1);
 
                     let response = String::from_utf8(data.to_vec());
@@ -330,7 +332,7 @@ mod request_test{
                 default::{
                     self,
                     action::{generate_read_only_file_utility_thread, FileUtilitySender, NO_BOUND},
-                }, tests::server::{server_initialization, ADDRESS, PORT, create_mb_string},
+                }, tests::server::{server_initialization, ADDRESS, PORT, create_mb_string}, Stream,
             },
             setting::{ServerSetting},
             test_tools::file_env::FileEnv,
@@ -351,7 +353,7 @@ mod request_test{
             // start server in separated thread
             let server_thread = server_initialization(
                 Arc::clone(&pair),
-                |stream: &mut TcpStream| {
+                |stream: &mut dyn Stream| {
                     trace!("Starting parsing 📄🔍");
                     let data = default::parser::parser::<64, 1024, 20, 250>(stream);
                     trace!("Finished parsing 📄🔍\n{:?}", data);
@@ -404,7 +406,7 @@ mod request_test{
                 assert!(!server_thread.is_finished());
 
                 let mut data = [0; 128];
-                stream.read(&mut data).unwrap();
+                let _ = stream.read(&mut data).unwrap();
                 trace!("Response received 💻 📃💨 💽");
 
                 let response = String::from_utf8(data.to_vec());
@@ -441,7 +443,7 @@ mod request_test{
             // start server in separated thread
             let _server_thread = server_initialization(
                 Arc::clone(&pair),
-                |stream: &mut TcpStream| {
+                |stream: &mut dyn Stream| {
                     trace!("Starting parsing 📄🔍");
                     let data = default::parser::parser::<64, 1024, 20, 250>(stream);
                     trace!("Finished parsing 📄🔍\n{:?}", data);
@@ -492,7 +494,7 @@ mod request_test{
                             thread::spawn(
                                 move|| {
                                     let mut data = [0; 128];
-                                    stream.read(&mut data).unwrap();
+                                    let _ = stream.read(&mut data).unwrap();
                                     trace!("Response {} received 💻 📃💨 💽", i+1);
                             
                                     let response = String::from_utf8(data.to_vec());
@@ -509,17 +511,14 @@ mod request_test{
                             )
                         }
                     ).collect();
-                while streams.len() > 0 {
+                while !streams.is_empty() {
                     for i in (0..streams.len()).rev() {
                         if streams[i].is_finished() {
                             let steam = streams.pop().unwrap();
 
                             match steam.join() {
-                                Ok(_) => assert!(true),
-                                Err(err) => {
-                                    println!("{:?}", err);
-                                    assert!(false);
-                                }
+                                Ok(_) => {}
+                                Err(err) => panic!("{:?}", err),
                             }
                         }
                     }
@@ -536,8 +535,8 @@ mod request_test{
         let file_2_content = Arc::new(RwLock::new(create_mb_string(10)));
 
         // create file environment
-        let _file_1 = FileEnv::new("source\\request_1.html", &*file_1_content.read().unwrap());
-        let _file_2 = FileEnv::new("source\\request_2.html", &*file_2_content.read().unwrap());
+        let _file_1 = FileEnv::new("source\\request_1.html", &file_1_content.read().unwrap());
+        let _file_2 = FileEnv::new("source\\request_2.html", &file_2_content.read().unwrap());
 
         trace!("File environment created 📁");
 
@@ -546,7 +545,7 @@ mod request_test{
         // start server in separated thread
         let _server_thread = server_initialization(
             Arc::clone(&pair),
-            |stream: &mut TcpStream| {
+            |stream: &mut dyn Stream| {
                 trace!("Starting parsing 📄🔍");
                 let data = default::parser::parser::<64, 1024, 20, 250>(stream);
                 trace!("Finished parsing 📄🔍\n{:?}", data);
@@ -610,7 +609,7 @@ mod request_test{
                                     let data = &data_buffer[..size];
 
                                     let data_str = String::from_utf8(data.to_vec()).unwrap();
-                                    response.push_str(&data_str.trim_end_matches('\0'));
+                                    response.push_str(data_str.trim_end_matches('\0'));
                                 }
                                 trace!("Response {} received 💻 📃💨 💽", i+1);
                         
@@ -627,19 +626,14 @@ mod request_test{
                         )
                     }
                 ).collect();
-            while streams.len() > 0 {
+            while !streams.is_empty() {
                 for i in (0..streams.len()).rev() {
                     if streams[i].is_finished() {
                         let steam = streams.pop().unwrap();
 
                         match steam.join() {
-                            Ok(_) => {
-                                assert!(true);
-                            },
-                            Err(err) => {
-                                println!("{:?}", err);
-                                assert!(false);
-                            }
+                            Ok(_) => {}
+                            Err(err) => panic!("{:?}", err),
                         }
                     }
                 }
@@ -660,7 +654,7 @@ mod compression_test{
     use log::{trace};
     use serial_test::serial;
 
-    use crate::{test_tools::file_env::FileEnv, pipeline::{tests::server::{server_initialization, ADDRESS, PORT, split_bytes_at_body}, default::{self, action::{FileUtilitySender, generate_read_only_file_utility_thread, NO_BOUND}}}, http::{request::Request, response::{response_status_code::ResponseStatusCode, Response}}, setting::ServerSetting, file::FileError};
+    use crate::{test_tools::file_env::FileEnv, pipeline::{tests::server::{server_initialization, ADDRESS, PORT, split_bytes_at_body}, default::{self, action::{FileUtilitySender, generate_read_only_file_utility_thread, NO_BOUND}}, Stream}, http::{request::Request, response::{response_status_code::ResponseStatusCode, Response}}, setting::ServerSetting, file::FileError};
     
     const FILE_1_CONTENT: &str = "abcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyz";
 
@@ -669,7 +663,7 @@ mod compression_test{
     #[serial]
     fn no_compression() {
         // create file environment
-        let _file_1 = FileEnv::new("source\\file_1.html", &FILE_1_CONTENT);
+        let _file_1 = FileEnv::new("source\\file_1.html", FILE_1_CONTENT);
         trace!("File environment created 📁");
 
         let pair = Arc::new((Mutex::new(false), Condvar::new()));
@@ -677,7 +671,7 @@ mod compression_test{
         // start server in separated thread
         let _server_thread = server_initialization(
             Arc::clone(&pair),
-            |stream: &mut TcpStream| {
+            |stream: &mut dyn Stream| {
                 trace!("Starting parsing 📄🔍");
                 let data = default::parser::parser::<64, 1024, 20, 250>(stream);
                 trace!("Finished parsing 📄🔍\n{:?}", data);
@@ -743,7 +737,7 @@ mod compression_test{
     #[serial]
     fn gzip() {
         // create file environment
-        let _file_1 = FileEnv::new("source\\file_1.html", &FILE_1_CONTENT);
+        let _file_1 = FileEnv::new("source\\file_1.html", FILE_1_CONTENT);
         trace!("File environment created 📁");
 
         let pair = Arc::new((Mutex::new(false), Condvar::new()));
@@ -751,7 +745,7 @@ mod compression_test{
         // start server in separated thread
         let _server_thread = server_initialization(
             Arc::clone(&pair),
-            |stream: &mut TcpStream| {
+            |stream: &mut dyn Stream| {
                 trace!("Starting parsing 📄🔍");
                 let data = default::parser::parser::<64, 1024, 20, 250>(stream);
                 trace!("Finished parsing 📄🔍\n{:?}", data);
@@ -837,7 +831,7 @@ mod compression_test{
     #[serial]
     fn deflate() {
         // create file environment
-        let _file_1 = FileEnv::new("source\\file_1.html", &FILE_1_CONTENT);
+        let _file_1 = FileEnv::new("source\\file_1.html", FILE_1_CONTENT);
         trace!("File environment created 📁");
 
         let pair = Arc::new((Mutex::new(false), Condvar::new()));
@@ -845,7 +839,7 @@ mod compression_test{
         // start server in separated thread
         let _server_thread = server_initialization(
             Arc::clone(&pair),
-            |stream: &mut TcpStream| {
+            |stream: &mut dyn Stream| {
                 trace!("Starting parsing 📄🔍");
                 let data = default::parser::parser::<64, 1024, 20, 250>(stream);
                 trace!("Finished parsing 📄🔍\n{:?}", data);
@@ -931,7 +925,7 @@ mod compression_test{
     #[serial]
     fn zlib() {
         // create file environment
-        let _file_1 = FileEnv::new("source\\file_1.html", &FILE_1_CONTENT);
+        let _file_1 = FileEnv::new("source\\file_1.html", FILE_1_CONTENT);
         trace!("File environment created 📁");
 
         let pair = Arc::new((Mutex::new(false), Condvar::new()));
@@ -939,7 +933,7 @@ mod compression_test{
         // start server in separated thread
         let _server_thread = server_initialization(
             Arc::clone(&pair),
-            |stream: &mut TcpStream| {
+            |stream: &mut dyn Stream| {
                 trace!("Starting parsing 📄🔍");
                 let data = default::parser::parser::<64, 1024, 20, 250>(stream);
                 trace!("Finished parsing 📄🔍\n{:?}", data);
@@ -1044,7 +1038,7 @@ mod error_recovery_test{
                 default::{
                     self,
                     action::{generate_read_only_file_utility_thread, FileUtilitySender, NO_BOUND},
-                }, tests::server::{server_initialization, ADDRESS, PORT},
+                }, tests::server::{server_initialization, ADDRESS, PORT}, Stream,
             },
             setting::{ServerSetting},
             test_tools::file_env::FileEnv,
@@ -1065,11 +1059,11 @@ mod error_recovery_test{
             // start server in separated thread
             let server_thread = server_initialization(
                 Arc::clone(&pair),
-                |stream: &mut TcpStream| {
+                |stream: &mut dyn Stream| {
                     trace!("Starting parsing 📄🔍");
                     let data = default::parser::parser::<64, 1024, 20, 250>(stream);
 
-                    if let Ok(Request(Method::Get { file }, _)) = &data {
+                    if let Ok(Request { method: Method::Get, target: file, .. }) = &data {
                         if file == "request_2.html" {
                             error!("Parser Panic");
                             panic!("Simulated Panic") 
@@ -1114,15 +1108,15 @@ mod error_recovery_test{
                 let mut streams: [TcpStream; 3] =
                     core::array::from_fn(|_| TcpStream::connect(format!("{}:{}", ADDRESS, PORT)).unwrap());
 
-                for i in 0..3 {
+                for (i, stream) in streams.iter_mut().enumerate() {
                     trace!("Request {} sent 💽 📃💨 💻", i + 1);
                     match i {
                         1 => {
-                            let _ = streams[i]
+                            let _ = stream
                                 .write(b"GET request_2.html HTTP/1.1\n\rhost:localhost");
                         },
                         _ => {
-                            let _ = streams[i]
+                            let _ = stream
                                 .write(b"GET request_1.html HTTP/1.1\n\rhost:localhost");
                         }
                     }
@@ -1132,7 +1126,7 @@ mod error_recovery_test{
                     assert!(!server_thread.is_finished());
 
                     let mut data = [0; 128];
-                    streams[i * 2].read(&mut data).unwrap();//skip streams[1] due to panic
+                    let _ = streams[i * 2].read(&mut data).unwrap();//skip streams[1] due to panic
                     trace!("Response {} received 💻 📃💨 💽", (i * 2) + 1);
 
                     let response = String::from_utf8(data.to_vec());
@@ -1164,7 +1158,7 @@ mod error_recovery_test{
             // start server in separated thread
             let server_thread = server_initialization(
                 Arc::clone(&pair),
-                |stream: &mut TcpStream| {
+                |stream: &mut dyn Stream| {
                     trace!("Starting parsing 📄🔍");
                     let data = default::parser::parser::<64, 1024, 20, 250>(stream);
                     trace!("Finished parsing 📄🔍\n{:?}", data);
@@ -1175,7 +1169,7 @@ mod error_recovery_test{
                 setting: &ServerSetting,
                 utility_thread: &mut FileUtilitySender<FileError>| {
                     trace!("Staring action 💪");
-                    if let Ok(Request(Method::Get { file }, _)) = request {
+                    if let Ok(Request { method: Method::Get, target: file, .. }) = request {
                         if file == "request_2.html" {
                             error!("Action Panic");
                             panic!("Simulated Panic") 
@@ -1211,15 +1205,15 @@ mod error_recovery_test{
                 let mut streams: [TcpStream; 3] =
                     core::array::from_fn(|_| TcpStream::connect(format!("{}:{}", ADDRESS, PORT)).unwrap());
 
-                for i in 0..3 {
+                for (i, stream) in streams.iter_mut().enumerate() {
                     trace!("Request {} sent 💽 📃💨 💻", i + 1);
                     match i {
                         1 => {
-                            let _ = streams[i]
+                            let _ = stream
                                 .write(b"GET request_2.html HTTP/1.1\n\rhost:localhost");
                         },
                         _ => {
-                            let _ = streams[i]
+                            let _ = stream
                                 .write(b"GET request_1.html HTTP/1.1\n\rhost:localhost");
                         }
                     }
@@ -1229,7 +1223,7 @@ mod error_recovery_test{
                     assert!(!server_thread.is_finished());
 
                     let mut data = [0; 128];
-                    streams[i * 2].read(&mut data).unwrap();//skip streams[1] due to panic
+                    let _ = streams[i * 2].read(&mut data).unwrap();//skip streams[1] due to panic
                     trace!("Response {} received 💻 📃💨 💽", (i * 2) + 1);
 
                     let response = String::from_utf8(data.to_vec());
@@ -1261,7 +1255,7 @@ mod error_recovery_test{
             // start server in separated thread
             let server_thread = server_initialization(
                 Arc::clone(&pair),
-                |stream: &mut TcpStream| {
+                |stream: &mut dyn Stream| {
                     trace!("Starting parsing 📄🔍");
                     let data = default::parser::parser::<64, 1024, 20, 250>(stream);
                     trace!("Finished parsing 📄🔍\n{:?}", data);
@@ -1280,7 +1274,7 @@ mod error_recovery_test{
                 |response: Response, request: Option<Request>, settings: ServerSetting| {
                     trace!("Staring compression 💥");
                     trace!("compression: {request:?}");
-                    if None == request {
+                    if request.is_none() {
                         error!("Compressor Panic");
                         panic!("Simulated Panic")
                     }
@@ -1307,15 +1301,15 @@ mod error_recovery_test{
                 let mut streams: [TcpStream; 3] =
                     core::array::from_fn(|_| TcpStream::connect(format!("{}:{}", ADDRESS, PORT)).unwrap());
 
-                for i in 0..3 {
+                for (i, stream) in streams.iter_mut().enumerate() {
                     trace!("Request {} sent 💽 📃💨 💻", i + 1);
                     match i {
                         1 => {
-                            let _ = streams[i]
+                            let _ = stream
                                 .write(b"GET request_2.html HTTP/1.1\n\rhost:localhost");
                         },
                         _ => {
-                            let _ = streams[i]
+                            let _ = stream
                                 .write(b"GET request_1.html HTTP/1.1\n\rhost:localhost");
                         }
                     }
@@ -1325,7 +1319,7 @@ mod error_recovery_test{
                     assert!(!server_thread.is_finished());
 
                     let mut data = [0; 128];
-                    streams[i * 2].read(&mut data).unwrap();//skip streams[1] due to panic
+                    let _ = streams[i * 2].read(&mut data).unwrap();//skip streams[1] due to panic
                     trace!("Response {} received 💻 📃💨 💽", (i * 2) + 1);
 
                     let response = String::from_utf8(data.to_vec());
@@ -1342,4 +1336,162 @@ mod error_recovery_test{
             }
         }
     }
-}
\ No newline at end of file
+}
+mod shutdown_test {
+    use std::{
+        collections::HashMap,
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        sync::mpsc,
+        thread::{self, JoinHandle},
+        time::{Duration, Instant},
+    };
+
+    use serial_test::serial;
+
+    use crate::{
+        http::{
+            body::{Body, ContentType, Text},
+            request::Request,
+            response::{response_status_code::ResponseStatusCode, Response},
+        },
+        pipeline::{builder::pipeline::Builder, default, Server, ShutdownHandle, Stream},
+        setting::ServerSetting,
+    };
+
+    const ADDRESS: &str = "localhost";
+    const PORT: u16 = 8090;
+
+    fn echo_action(
+        request: &Result<Request, ResponseStatusCode>,
+        _: &ServerSetting,
+        _: &mut mpsc::Sender<()>,
+    ) -> Result<Response, ResponseStatusCode> {
+        let status = match request {
+            Ok(_) => ResponseStatusCode::Ok,
+            Err(err) => *err,
+        };
+
+        Ok(Response {
+            status,
+            header: HashMap::new(),
+            body: Some(Body {
+                content_type: ContentType::Text(Text::plain),
+                content: b"done".to_vec(),
+            }),
+        })
+    }
+
+    fn start_server() -> (JoinHandle<()>, ShutdownHandle) {
+        let setting = ServerSetting {
+            address: ADDRESS.to_string(),
+            port: PORT,
+            paths: HashMap::new(),
+            pipelines: Some(2),
+            listeners: Vec::new(),
+        };
+
+        let (tx, _rx) = mpsc::channel();
+        let utility_thread = (tx.clone(), thread::spawn(|| {}));
+
+        let builder = Builder::default()
+            .set_settings(setting.clone())
+            .set_parser(|stream: &mut dyn Stream| default::parser::parser::<64, 1024, 200, 1000>(stream))
+            .set_action(echo_action)
+            .set_compression(default::no_compression)
+            .set_utility_thread(tx);
+
+        let server = Server::new(setting, utility_thread, builder);
+        let handle = server.shutdown_handle();
+
+        let server_thread = thread::spawn(move || server.run().unwrap());
+
+        // wait for listener to bind
+        let start = Instant::now();
+        while TcpStream::connect(format!("{ADDRESS}:{PORT}")).is_err() {
+            assert!(start.elapsed() < Duration::from_secs(5), "server failed to start");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        (server_thread, handle)
+    }
+
+    fn request() -> TcpStream {
+        let mut stream = TcpStream::connect(format!("{ADDRESS}:{PORT}")).unwrap();
+
+        stream.write_all(b"GET / HTTP/1.1\r\nhost:localhost\r\n\r\n").unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+        stream
+    }
+
+    fn wait_for(server_thread: &JoinHandle<()>, timeout: Duration) -> bool {
+        let start = Instant::now();
+
+        while !server_thread.is_finished() {
+            if start.elapsed() > timeout {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        true
+    }
+
+    #[test]
+    #[serial]
+    fn run_returns_after_shutdown() {
+        let (server_thread, handle) = start_server();
+
+        assert!(!handle.is_shutdown());
+
+        handle.shutdown(Duration::from_secs(1));
+
+        assert!(handle.is_shutdown());
+        assert!(wait_for(&server_thread, Duration::from_secs(5)));
+
+        server_thread.join().unwrap();
+        handle.wait();
+
+        assert!(TcpStream::connect(format!("{ADDRESS}:{PORT}")).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn queued_request_drains_before_exit() {
+        let (server_thread, handle) = start_server();
+
+        let mut stream = request();
+
+        handle.shutdown(Duration::from_secs(2));
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+
+        assert_eq!(
+            String::from_utf8(response).unwrap(),
+            "HTTP/1.1 200 Ok\r\nContent-Length: 4\r\nContent-Type: text/plain\r\n\r\ndone"
+        );
+
+        assert!(wait_for(&server_thread, Duration::from_secs(5)));
+        server_thread.join().unwrap();
+    }
+
+    #[test]
+    fn unknown_wake_connection_stops_on_next_connection() {
+        let handle = ShutdownHandle::default();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let _client = TcpStream::connect(address).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+
+        assert!(!handle.is_wake(Some(address), &stream));
+
+        // the listener wasn't recorded; so it has no wake connection to tell apart from clients
+        handle.shutdown(Duration::ZERO);
+
+        assert!(handle.is_wake(Some(address), &stream));
+    }
+}
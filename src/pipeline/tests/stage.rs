@@ -0,0 +1,217 @@
+mod traits {
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            mpsc, Arc,
+        },
+    };
+
+    use crate::{
+        http::{
+            method::Method,
+            request::Request,
+            response::{response_status_code::ResponseStatusCode, Response},
+            version::Version,
+        },
+        pipeline::{default, Action, Compressor},
+        setting::ServerSetting,
+    };
+
+    fn setting() -> ServerSetting {
+        ServerSetting {
+            address: String::from(""),
+            port: 8080,
+            paths: HashMap::new(),
+            pipelines: None,
+            listeners: Vec::new(),
+        }
+    }
+
+    fn request() -> Result<Request, ResponseStatusCode> {
+        Ok(Request::new(Method::Get, "/", Version::Http1_1))
+    }
+
+    /// CountingAction is a stateful action implemented without a closure
+    struct CountingAction(AtomicUsize);
+
+    impl Action<()> for CountingAction {
+        fn act(
+            &self,
+            _: &Result<Request, ResponseStatusCode>,
+            _: &ServerSetting,
+            _: &mut mpsc::Sender<()>,
+        ) -> Result<Response, ResponseStatusCode> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+
+            Err(ResponseStatusCode::NotFound)
+        }
+    }
+
+    #[test]
+    fn fn_pointer_compressor() {
+        let compressor: fn(Response, Option<Request>, ServerSetting) -> Vec<u8> = default::no_compression;
+
+        let response = Response {
+            status: ResponseStatusCode::Ok,
+            header: HashMap::new(),
+            body: None,
+        };
+
+        assert_eq!(
+            compressor.compress(response, None, setting()),
+            b"HTTP/1.1 200 Ok\r\n\r\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn closure_captures_state() {
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let action = {
+            let calls = calls.clone();
+
+            move |_: &Result<Request, ResponseStatusCode>, _: &ServerSetting, _: &mut mpsc::Sender<()>| {
+                calls.fetch_add(1, Ordering::SeqCst);
+
+                Err(ResponseStatusCode::NotFound)
+            }
+        };
+
+        let (mut tx, _rx) = mpsc::channel();
+
+        assert_eq!(action.act(&request(), &setting(), &mut tx).unwrap_err(), ResponseStatusCode::NotFound);
+        assert_eq!(action.act(&request(), &setting(), &mut tx).unwrap_err(), ResponseStatusCode::NotFound);
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn struct_implementation() {
+        let action = CountingAction(AtomicUsize::new(0));
+        let (mut tx, _rx) = mpsc::channel();
+
+        assert!(action.act(&request(), &setting(), &mut tx).is_err());
+
+        assert_eq!(action.0.load(Ordering::SeqCst), 1);
+    }
+}
+
+mod server {
+    use std::{
+        collections::HashMap,
+        io::{Read, Write},
+        net::TcpStream,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            mpsc, Arc,
+        },
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use serial_test::serial;
+
+    use crate::{
+        http::{
+            body::{Body, ContentType, Text},
+            request::Request,
+            response::{response_status_code::ResponseStatusCode, Response},
+        },
+        pipeline::{builder::pipeline::Builder, default, Server, Stream},
+        setting::ServerSetting,
+    };
+
+    const ADDRESS: &str = "localhost";
+    const PORT: u16 = 8092;
+
+    fn request() -> Option<String> {
+        let mut stream = TcpStream::connect(format!("{ADDRESS}:{PORT}")).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+        stream.write_all(b"GET / HTTP/1.1\r\nhost:localhost\r\n\r\n").unwrap();
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).ok()?;
+
+        String::from_utf8(response).ok()
+    }
+
+    /// a panicking action is rebuilt by recovery using the same captured state
+    #[test]
+    #[serial]
+    fn recovered_action_keeps_state() {
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let action = {
+            let calls = calls.clone();
+
+            move |request: &Result<Request, ResponseStatusCode>, _: &ServerSetting, _: &mut mpsc::Sender<()>| {
+                // connections opened while waiting for the server to start are not counted
+                if let Err(err) = request {
+                    return Err(*err);
+                }
+
+                if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                    panic!("first request fails");
+                }
+
+                Ok(Response {
+                    status: ResponseStatusCode::Ok,
+                    header: HashMap::new(),
+                    body: Some(Body {
+                        content_type: ContentType::Text(Text::plain),
+                        content: calls.load(Ordering::SeqCst).to_string().into_bytes(),
+                    }),
+                })
+            }
+        };
+
+        let setting = ServerSetting {
+            address: ADDRESS.to_string(),
+            port: PORT,
+            paths: HashMap::new(),
+            pipelines: Some(1),
+            listeners: Vec::new(),
+        };
+
+        let (tx, _rx) = mpsc::channel();
+        let utility_thread = (tx.clone(), thread::spawn(|| {}));
+
+        let builder = Builder::default()
+            .set_settings(setting.clone())
+            .set_parser(|stream: &mut dyn Stream| default::parser::parser::<64, 1024, 200, 1000>(stream))
+            .set_action(action)
+            .set_compression(default::no_compression)
+            .set_utility_thread(tx);
+
+        let server = Server::new(setting, utility_thread, builder);
+        let handle = server.shutdown_handle();
+
+        let server_thread = thread::spawn(move || server.run().unwrap());
+
+        let start = Instant::now();
+        while TcpStream::connect(format!("{ADDRESS}:{PORT}")).is_err() {
+            assert!(start.elapsed() < Duration::from_secs(5), "server failed to start");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        // connection is dropped along with the panicking thread
+        assert_eq!(request().unwrap_or_default(), "");
+
+        let response = loop {
+            match request() {
+                Some(response) if !response.is_empty() => break response,
+                _ => {
+                    assert!(start.elapsed() < Duration::from_secs(5), "action was not recovered");
+                    thread::sleep(Duration::from_millis(25));
+                }
+            }
+        };
+
+        assert!(response.ends_with("\r\n\r\n2"));
+
+        handle.shutdown(Duration::from_secs(1));
+        server_thread.join().unwrap();
+    }
+}
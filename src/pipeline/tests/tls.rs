@@ -0,0 +1,384 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{ErrorKind, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    path::PathBuf,
+    sync::{mpsc, Arc},
+    thread,
+    time::{Duration, Instant},
+};
+
+use rustls::{
+    crypto::ring,
+    pki_types::{CertificateDer, ServerName},
+    ClientConfig, ClientConnection, RootCertStore, StreamOwned,
+};
+use serial_test::serial;
+
+use crate::{
+    http::{
+        body::{Body, ContentType, Text},
+        request::Request,
+        response::{response_status_code::ResponseStatusCode, Response},
+    },
+    pipeline::{
+        builder::pipeline::Builder,
+        tls::{self, TlsStream},
+        Connection, KeepAlive, ServerError,
+    },
+    setting::{Certificate, DomainPath, ListenerSetting, ServerSetting},
+    test_tools::{
+        dir_env::DirEnv,
+        server_env::{self, ServerEnv},
+    },
+};
+
+const PORT: u16 = 8101;
+
+fn address() -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], PORT))
+}
+
+/// Identity is a self-signed certificate generated for a test
+struct Identity {
+    der: CertificateDer<'static>,
+    certificate: Certificate,
+}
+
+/// identity generates a certificate for names; writing it & its key to folder, named after the first name
+fn identity(folder: &str, names: &[&str]) -> Identity {
+    let name = names[0];
+    let names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+    let generated = rcgen::generate_simple_self_signed(names).unwrap();
+
+    let certificate = Certificate {
+        cert: PathBuf::from(folder).join(format!("{name}.crt")),
+        key: PathBuf::from(folder).join(format!("{name}.key")),
+    };
+
+    fs::write(&certificate.cert, generated.cert.pem()).unwrap();
+    fs::write(&certificate.key, generated.signing_key.serialize_pem()).unwrap();
+
+    Identity {
+        der: generated.cert.der().clone(),
+        certificate,
+    }
+}
+
+/// setting serves TLS with listener's certificate; and domain's certificate to clients naming example.com
+fn setting(listener: &Identity, domain: &Identity) -> ServerSetting {
+    ServerSetting {
+        address: String::from("127.0.0.1"),
+        port: PORT,
+        paths: HashMap::from([(
+            String::from("example.com"),
+            DomainPath {
+                path: String::new(),
+                allow: vec![String::from("html")],
+                tls: Some(domain.certificate.clone()),
+            },
+        )]),
+        pipelines: Some(1),
+        listeners: vec![ListenerSetting {
+            tls: Some(listener.certificate.clone()),
+            ..ListenerSetting::new("127.0.0.1", PORT)
+        }],
+    }
+}
+
+/// tls records if the listener that accepted the connection terminates TLS in the x-tls header
+fn tls(
+    connection: &Connection,
+    request: Result<Request, ResponseStatusCode>,
+    _: &ServerSetting,
+) -> Result<Request, ResponseStatusCode> {
+    let mut request = request?;
+
+    let tls = connection.listener().is_some_and(|listener| listener.is_tls());
+    request.headers.insert("x-tls", &tls.to_string());
+
+    Ok(request)
+}
+
+/// echo answers with the x-tls header & host of the request
+fn echo(
+    request: &Result<Request, ResponseStatusCode>,
+    _: &ServerSetting,
+    _: &mut mpsc::Sender<()>,
+) -> Result<Response, ResponseStatusCode> {
+    let request = match request {
+        Ok(request) => request,
+        Err(err) => return Err(*err),
+    };
+
+    let field = |name: &str| request.headers.get(name).unwrap_or_default().to_string();
+
+    Ok(Response {
+        status: ResponseStatusCode::Ok,
+        header: HashMap::new(),
+        body: Some(Body {
+            content_type: ContentType::Text(Text::plain),
+            content: format!("{}|{}", field("x-tls"), field("host")).into_bytes(),
+        }),
+    })
+}
+
+fn builder() -> Builder<()> {
+    server_env::builder().add_request_stage("TLS", tls).set_action(echo)
+}
+
+fn start(setting: ServerSetting) -> ServerEnv<()> {
+    start_with(setting, builder())
+}
+
+fn start_with(setting: ServerSetting, builder: Builder<()>) -> ServerEnv<()> {
+    ServerEnv::new_on(server_env::server(setting, builder), address())
+}
+
+/// connect completes a handshake naming server_name; trusting only roots
+fn connect(server_name: &str, roots: &[&Identity]) -> StreamOwned<ClientConnection, TcpStream> {
+    let mut store = RootCertStore::empty();
+    for root in roots {
+        store.add(root.der.clone()).unwrap();
+    }
+
+    let config = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(store)
+        .with_no_client_auth();
+
+    let server_name = ServerName::try_from(server_name.to_string()).unwrap();
+    let mut connection = ClientConnection::new(Arc::new(config), server_name).unwrap();
+
+    let mut socket = TcpStream::connect(address()).unwrap();
+    socket.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+    while connection.is_handshaking() {
+        connection.complete_io(&mut socket).unwrap();
+    }
+
+    StreamOwned::new(connection, socket)
+}
+
+/// presented returns the certificate the server presented over stream
+fn presented(stream: &StreamOwned<ClientConnection, TcpStream>) -> CertificateDer<'static> {
+    stream.conn.peer_certificates().unwrap()[0].clone().into_owned()
+}
+
+/// read_response reads a single response, ending with body, from a kept alive connection
+fn read_response<R: Read>(stream: &mut R, body: &str) -> String {
+    let mut response = Vec::new();
+    let mut buffer = [0; 1024];
+
+    while !response.ends_with(format!("\r\n\r\n{body}").as_bytes()) {
+        let read = stream.read(&mut buffer).unwrap();
+        assert_ne!(read, 0, "connection closed before the response was read");
+
+        response.extend_from_slice(&buffer[..read]);
+    }
+
+    String::from_utf8(response).unwrap()
+}
+
+#[test]
+#[serial]
+fn sni_selects_domain_certificate() {
+    let _folder = DirEnv::new("tls_sni");
+    let default = identity("tls_sni", &["localhost"]);
+    let example = identity("tls_sni", &["example.com"]);
+
+    let _server = start(setting(&default, &example));
+
+    for (server_name, expected) in [("example.com", &example), ("localhost", &default)] {
+        let mut stream = connect(server_name, &[&default, &example]);
+        assert_eq!(presented(&stream), expected.der, "{server_name}");
+
+        stream
+            .write_all(format!("GET / HTTP/1.1\r\nhost:{server_name}\r\nconnection:close\r\n\r\n").as_bytes())
+            .unwrap();
+
+        // the server closes with close_notify; so a truncated response would fail to read
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.ends_with(&format!("\r\n\r\ntrue|{server_name}")), "{response}");
+    }
+}
+
+#[test]
+#[serial]
+fn unknown_domain_gets_listener_certificate() {
+    let _folder = DirEnv::new("tls_fallback");
+    let default = identity("tls_fallback", &["localhost", "other.com"]);
+    let example = identity("tls_fallback", &["example.com"]);
+
+    let _server = start(setting(&default, &example));
+
+    for server_name in ["other.com", "EXAMPLE.com"] {
+        let stream = connect(server_name, &[&default, &example]);
+
+        let expected = match server_name.eq_ignore_ascii_case("example.com") {
+            true => &example.der,
+            false => &default.der,
+        };
+        assert_eq!(&presented(&stream), expected, "{server_name}");
+    }
+}
+
+#[test]
+#[serial]
+fn kept_alive_connection_serves_requests() {
+    let _folder = DirEnv::new("tls_keep_alive");
+    let default = identity("tls_keep_alive", &["localhost"]);
+    let example = identity("tls_keep_alive", &["example.com"]);
+
+    let _server = start_with(
+        setting(&default, &example),
+        builder().set_keep_alive(KeepAlive::default()),
+    );
+
+    let mut stream = connect("localhost", &[&default]);
+
+    stream.write_all(b"GET / HTTP/1.1\r\nhost:localhost\r\n\r\n").unwrap();
+    let response = read_response(&mut stream, "true|localhost");
+    assert!(response.contains("Connection: keep-alive\r\n"), "{response}");
+
+    // the connection waits idle between requests; with its next request arriving encrypted
+    thread::sleep(Duration::from_millis(100));
+
+    stream.write_all(b"GET / HTTP/1.1\r\nhost:example.com\r\nconnection:close\r\n\r\n").unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.ends_with("\r\n\r\ntrue|example.com"), "{response}");
+}
+
+#[test]
+#[serial]
+fn plaintext_client_is_dropped() {
+    let _folder = DirEnv::new("tls_plaintext");
+    let default = identity("tls_plaintext", &["localhost"]);
+    let example = identity("tls_plaintext", &["example.com"]);
+
+    let _server = start(setting(&default, &example));
+
+    let mut stream = TcpStream::connect(address()).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    stream.write_all(b"GET / HTTP/1.1\r\nhost:localhost\r\n\r\n").unwrap();
+
+    // at most an alert is sent back before the connection is closed
+    let mut response = Vec::new();
+    let _ = stream.read_to_end(&mut response);
+    assert!(!response.starts_with(b"HTTP"), "{}", String::from_utf8_lossy(&response));
+
+    // the failed handshake doesn't affect other clients
+    let mut stream = connect("localhost", &[&default]);
+    stream.write_all(b"GET / HTTP/1.1\r\nhost:localhost\r\nconnection:close\r\n\r\n").unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.ends_with("\r\n\r\ntrue|localhost"), "{response}");
+}
+
+/// clients stalling their handshake hold neither the listener nor the parser; which the single parser worker would be blocked on
+#[test]
+#[serial]
+fn stalled_handshakes_dont_hold_up_parsing() {
+    let _folder = DirEnv::new("tls_stalled");
+    let default = identity("tls_stalled", &["localhost"]);
+    let example = identity("tls_stalled", &["example.com"]);
+
+    let _server = start(setting(&default, &example));
+
+    let _stalled: Vec<TcpStream> = (0..3).map(|_| TcpStream::connect(address()).unwrap()).collect();
+
+    let start = Instant::now();
+
+    let mut stream = connect("localhost", &[&default]);
+    stream.write_all(b"GET / HTTP/1.1\r\nhost:localhost\r\nconnection:close\r\n\r\n").unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    assert!(response.ends_with("\r\n\r\ntrue|localhost"), "{response}");
+    assert!(start.elapsed() < Duration::from_secs(2));
+}
+
+#[test]
+#[serial]
+fn unusable_certificates_fail_to_start() {
+    let _folder = DirEnv::new("tls_unusable");
+    let default = identity("tls_unusable", &["localhost"]);
+    let example = identity("tls_unusable", &["example.com"]);
+
+    let missing = Identity {
+        der: default.der.clone(),
+        certificate: Certificate {
+            cert: PathBuf::from("tls_unusable/missing.crt"),
+            key: default.certificate.key.clone(),
+        },
+    };
+
+    // the key of example.com doesn't belong to the certificate of localhost
+    let mismatched = Identity {
+        der: default.der.clone(),
+        certificate: Certificate {
+            cert: default.certificate.cert.clone(),
+            key: example.certificate.key.clone(),
+        },
+    };
+
+    for setting in [
+        setting(&missing, &example),
+        setting(&mismatched, &example),
+        setting(&default, &mismatched),
+    ] {
+        let server = server_env::server(setting, builder());
+
+        let err = server.run().unwrap_err();
+        assert!(matches!(err, ServerError::Tls { .. }), "{err}");
+    }
+
+    // nothing was left bound
+    assert!(TcpStream::connect(address()).is_err());
+}
+
+/// a client sending its handshake a byte at a time is dropped once the whole handshake takes too long; not just a single read
+#[test]
+fn trickled_handshake_times_out() {
+    let _folder = DirEnv::new("tls_trickle");
+    let default = identity("tls_trickle", &["localhost"]);
+
+    let config = tls::server_config(&default.certificate, &HashMap::new()).unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let client = thread::spawn(move || {
+        let mut stream = TcpStream::connect(address).unwrap();
+
+        // the header of a handshake record that never arrives in full
+        for byte in [0x16, 0x03, 0x01, 0x02, 0x00].into_iter().chain([0; 40]) {
+            if stream.write_all(&[byte]).is_err() {
+                break;
+            }
+
+            thread::sleep(Duration::from_millis(25));
+        }
+    });
+
+    let (stream, _) = listener.accept().unwrap();
+    let mut stream = TlsStream::new(stream.into(), config).unwrap();
+
+    let start = Instant::now();
+    let err = stream.handshake_within(Duration::from_millis(300)).unwrap_err();
+
+    assert!(matches!(err.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock), "{err}");
+    assert!(start.elapsed() < Duration::from_millis(600), "{:?}", start.elapsed());
+
+    drop(stream);
+    client.join().unwrap();
+}
@@ -0,0 +1,198 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{ErrorKind, Read, Write},
+    net::{SocketAddr, TcpStream},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use serial_test::serial;
+
+use crate::{
+    http::{
+        body::{Body, ContentType, Text},
+        request::Request,
+        response::{response_status_code::ResponseStatusCode, Response},
+    },
+    pipeline::{listen_fds, Server},
+    setting::{ListenerSetting, ServerSetting},
+    test_tools::server_env::{self, ServerEnv},
+};
+
+const PORT: u16 = 8105;
+
+fn address() -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], PORT))
+}
+
+fn socket_path() -> PathBuf {
+    std::env::temp_dir().join("pipelined_upgrade.sock")
+}
+
+fn setting() -> ServerSetting {
+    ServerSetting {
+        address: String::from("127.0.0.1"),
+        port: PORT,
+        paths: HashMap::new(),
+        pipelines: Some(1),
+        listeners: vec![
+            ListenerSetting::new("127.0.0.1", PORT),
+            ListenerSetting::unix(socket_path()),
+        ],
+    }
+}
+
+/// server answers every request with name; after the delay in the x-delay header, in milliseconds
+fn server(name: &'static str) -> Server<()> {
+    let answer = move |request: &Result<Request, ResponseStatusCode>, _: &ServerSetting, _: &mut mpsc::Sender<()>| {
+        let request = match request {
+            Ok(request) => request,
+            Err(err) => return Err(*err),
+        };
+
+        if let Some(delay) = request.headers.get("x-delay").and_then(|delay| delay.parse().ok()) {
+            thread::sleep(Duration::from_millis(delay));
+        }
+
+        Ok(Response {
+            status: ResponseStatusCode::Ok,
+            header: HashMap::new(),
+            body: Some(Body {
+                content_type: ContentType::Text(Text::plain),
+                content: name.as_bytes().to_vec(),
+            }),
+        })
+    };
+
+    server_env::server(setting(), server_env::builder().set_action(answer))
+}
+
+fn start() -> ServerEnv<()> {
+    ServerEnv::new_on(server("old"), address())
+}
+
+fn request<S: Read + Write>(stream: S, headers: &str) -> String {
+    server_env::exchange(stream, &format!("GET / HTTP/1.1\r\nhost:localhost\r\n{headers}\r\n")).unwrap()
+}
+
+fn tcp() -> TcpStream {
+    let stream = TcpStream::connect(address()).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+    stream
+}
+
+fn unix() -> UnixStream {
+    let stream = UnixStream::connect(socket_path()).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+    stream
+}
+
+/// new_process_command runs new_process in a copy of the test binary
+fn new_process_command() -> Command {
+    let mut command = Command::new(std::env::current_exe().unwrap());
+
+    command
+        .args(["pipeline::tests::upgrade::new_process", "--exact", "--ignored", "--test-threads=1"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    command
+}
+
+/// new_process is the server sockets are handed over to by sockets_are_handed_to_new_process; it does nothing unless started with sockets
+#[test]
+#[ignore]
+fn new_process() {
+    let sockets = listen_fds().unwrap();
+
+    if sockets.is_empty() {
+        return;
+    }
+
+    let server = server("new").set_inherited(sockets);
+    let handle = server.shutdown_handle();
+
+    // stops by itself in case the test fails before stopping it
+    thread::spawn(move || {
+        thread::sleep(Duration::from_secs(10));
+        handle.shutdown(Duration::from_secs(1));
+    });
+
+    server.run().unwrap();
+}
+
+#[test]
+#[serial]
+fn sockets_are_handed_to_new_process() {
+    let _ = fs::remove_file(socket_path());
+
+    let server = start();
+
+    assert!(request(tcp(), "").ends_with("\r\n\r\nold"));
+
+    // reaches the action stage before the upgrade; so it is answered by the old process
+    let in_flight = thread::spawn(|| request(tcp(), "x-delay:500\r\n"));
+    thread::sleep(Duration::from_millis(100));
+
+    let mut new_process = server
+        .server()
+        .shutdown_handle()
+        .upgrade(new_process_command(), Duration::from_secs(5))
+        .unwrap();
+
+    // the old process stops accepting once the sockets are handed over
+    let response = request(tcp(), "");
+    assert!(response.ends_with("\r\n\r\nnew"), "{response}");
+
+    let response = request(unix(), "");
+    assert!(response.ends_with("\r\n\r\nnew"), "{response}");
+
+    let response = in_flight.join().unwrap();
+    assert!(response.ends_with("\r\n\r\nold"), "{response}");
+
+    // the old process drains & returns from run by itself
+    server.join();
+
+    // the new process owns the sockets, so the unix socket's file is left in place
+    let response = request(unix(), "");
+    assert!(response.ends_with("\r\n\r\nnew"), "{response}");
+
+    new_process.kill().unwrap();
+    new_process.wait().unwrap();
+
+    let _ = fs::remove_file(socket_path());
+}
+
+#[test]
+#[serial]
+fn failed_upgrade_resumes_accepting() {
+    let _ = fs::remove_file(socket_path());
+
+    let server = start();
+
+    let err = server
+        .server()
+        .shutdown_handle()
+        .upgrade(Command::new("pipelined_upgrade_missing_binary"), Duration::from_secs(1))
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+
+    let response = request(tcp(), "");
+    assert!(response.ends_with("\r\n\r\nold"), "{response}");
+
+    let response = request(unix(), "");
+    assert!(response.ends_with("\r\n\r\nold"), "{response}");
+
+    let handle = server.server().shutdown_handle();
+    drop(server);
+
+    // a stopped server has nothing to hand over
+    assert!(handle.upgrade(new_process_command(), Duration::from_secs(1)).is_err());
+}
@@ -0,0 +1,176 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serial_test::serial;
+
+use crate::{
+    http::{
+        body::{Body, ContentType, Text},
+        method::Method,
+        request::Request,
+        response::{response_status_code::ResponseStatusCode, Response},
+    },
+    pipeline::{builder::pipeline::Builder, Action, BuildError, Connection, PipelineMetrics},
+    setting::ServerSetting,
+    test_tools::server_env::{self, setting, ServerEnv},
+};
+
+const PORT: u16 = 8095;
+
+/// respond answers with the requested file name; sleeping while serving "/slow"
+fn respond(
+    request: &Result<Request, ResponseStatusCode>,
+    _: &ServerSetting,
+    _: &mut mpsc::Sender<()>,
+) -> Result<Response, ResponseStatusCode> {
+    let file = match request {
+        Ok(Request { method: Method::Get, target: file, .. }) => file.clone(),
+        Ok(_) => return Err(ResponseStatusCode::MethodNotAllowed),
+        Err(err) => return Err(*err),
+    };
+
+    if file == "/slow" {
+        thread::sleep(Duration::from_millis(500));
+    }
+
+    Ok(Response {
+        status: ResponseStatusCode::Ok,
+        header: HashMap::new(),
+        body: Some(Body {
+            content_type: ContentType::Text(Text::plain),
+            content: file.into_bytes(),
+        }),
+    })
+}
+
+fn builder<A: Action<()>>(action: A) -> Builder<()> {
+    server_env::builder().set_settings(setting(PORT)).set_action(action)
+}
+
+fn start(builder: Builder<()>) -> ServerEnv<()> {
+    ServerEnv::new(server_env::server(setting(PORT), builder), PORT)
+}
+
+fn metrics(server: &ServerEnv<()>) -> Arc<PipelineMetrics> {
+    server.server().metrics().remove(0)
+}
+
+fn request(file: &str) -> Option<String> {
+    server_env::request(PORT, &format!("GET {file} HTTP/1.1\r\nhost:localhost\r\n\r\n"))
+}
+
+#[test]
+fn zero_workers_is_rejected() {
+    assert_eq!(builder(respond).set_action_workers(0).validate(), Err(BuildError::NoWorkers));
+    assert_eq!(
+        builder(respond)
+            .add_response_stage_with_workers("Empty", 0, |_: &Connection, response, _: Option<&Request>, _: &ServerSetting| response)
+            .validate(),
+        Err(BuildError::NoWorkers)
+    );
+}
+
+#[test]
+fn worker_metrics_per_stage() {
+    let builder = builder(respond)
+        .set_parser_workers(2)
+        .set_action_workers(3)
+        .add_request_stage_with_workers(
+            "Pass",
+            2,
+            |_: &Connection, request, _: &ServerSetting| request,
+        );
+
+    let (tx, pipeline) = builder.build().unwrap();
+
+    let workers: Vec<(&str, usize)> = pipeline
+        .metrics
+        .workers
+        .iter()
+        .map(|(name, workers)| (name.as_str(), workers.len()))
+        .collect();
+
+    assert_eq!(
+        workers,
+        vec![("Parser", 2), ("Pass", 2), ("Action", 3), ("Compression", 1), ("Sender", 1)]
+    );
+
+    assert!(pipeline.pipeline_state());
+
+    drop(tx);
+    assert!(pipeline.shutdown(Instant::now() + Duration::from_secs(1)));
+}
+
+/// a slow request only occupies one of the action stage's workers
+#[test]
+#[serial]
+fn slow_action_does_not_block_stage() {
+    let server = start(builder(respond).set_action_workers(2));
+
+    let slow = thread::spawn(|| request("/slow"));
+    thread::sleep(Duration::from_millis(100));
+
+    let start = Instant::now();
+    assert!(request("/fast").unwrap().ends_with("\r\n\r\n/fast"));
+    assert!(start.elapsed() < Duration::from_millis(350), "fast request waited on slow request");
+
+    assert!(slow.join().unwrap().unwrap().ends_with("\r\n\r\n/slow"));
+
+    let metrics = metrics(&server);
+    let (name, action) = &metrics.workers[1];
+    assert_eq!(name, "Action");
+
+    // probe connections opened while starting the server are also processed
+    assert!(action.iter().all(|worker| worker.processed() > 0));
+    assert!(action.iter().any(|worker| worker.busy() >= Duration::from_millis(500)));
+    assert!(action.iter().all(|worker| (0.0..=1.0).contains(&worker.utilisation())));
+}
+
+/// recovery replaces the panicked worker while its sibling keeps running
+#[test]
+#[serial]
+fn recovery_replaces_dead_workers() {
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let action = {
+        let calls = calls.clone();
+
+        move |request: &Result<Request, ResponseStatusCode>, setting: &ServerSetting, utility: &mut mpsc::Sender<()>| {
+            // connections opened while waiting for the server to start are not counted
+            if request.is_ok() && calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                panic!("first request fails");
+            }
+
+            respond(request, setting, utility)
+        }
+    };
+
+    let server = start(builder(action).set_action_workers(2));
+
+    // connection is dropped along with the panicking worker
+    assert_eq!(request("/").unwrap_or_default(), "");
+
+    let start = Instant::now();
+    let restarts = loop {
+        let metrics = metrics(&server);
+        let restarts: Vec<u64> = metrics.workers[1].1.iter().map(|worker| worker.restarts()).collect();
+
+        if restarts.iter().sum::<u64>() > 0 {
+            break restarts;
+        }
+
+        assert!(start.elapsed() < Duration::from_secs(5), "worker was not recovered");
+        thread::sleep(Duration::from_millis(25));
+    };
+
+    assert!(restarts == vec![1, 0] || restarts == vec![0, 1], "{restarts:?}");
+
+    assert!(request("/").unwrap().ends_with("\r\n\r\n/"));
+}
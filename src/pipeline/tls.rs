@@ -0,0 +1,251 @@
+//! tls module terminates TLS on the connections accepted by a listener; so every stage after the handshake reads & writes plaintext
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug},
+    io::{self, Read, Write},
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use rustls::{
+    crypto::{ring, CryptoProvider},
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+    ServerConfig, ServerConnection,
+};
+
+use crate::setting::{Certificate, DomainPath};
+
+use super::stream::Stream;
+
+// time a client has to complete its whole handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// server_config creates the TLS configuration of a listener
+///
+/// Clients are presented the certificate of the domain in paths they name in their handshake (SNI); or certificate if the domain has no certificate, or no domain is named.
+///
+/// # Errors
+/// A message describing the first certificate or key that can't be loaded is returned
+pub(super) fn server_config(
+    certificate: &Certificate,
+    paths: &HashMap<String, DomainPath>,
+) -> Result<Arc<ServerConfig>, String> {
+    let provider = Arc::new(ring::default_provider());
+
+    let mut domains = HashMap::new();
+    for (domain, domain_path) in paths {
+        if let Some(certificate) = &domain_path.tls {
+            let certified = certified_key(certificate, &provider)
+                .map_err(|err| format!("certificate of domain {domain}: {err}"))?;
+
+            domains.insert(domain.to_ascii_lowercase(), certified);
+        }
+    }
+
+    let resolver = SniResolver {
+        default: certified_key(certificate, &provider)?,
+        domains,
+    };
+
+    let mut config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|err| err.to_string())?
+        .with_no_client_auth()
+        .with_cert_resolver(Arc::new(resolver));
+
+    config.alpn_protocols = vec![b"http/1.1".to_vec(), b"http/1.0".to_vec()];
+
+    Ok(Arc::new(config))
+}
+
+/// certified_key reads the certificate chain & private key of certificate; checking that they belong together
+fn certified_key(certificate: &Certificate, provider: &CryptoProvider) -> Result<Arc<CertifiedKey>, String> {
+    let cert = certificate.cert.display();
+    let key = certificate.key.display();
+
+    let chain = CertificateDer::pem_file_iter(&certificate.cert)
+        .and_then(|chain| chain.collect::<Result<Vec<_>, _>>())
+        .map_err(|err| format!("{cert}: {err}"))?;
+
+    if chain.is_empty() {
+        return Err(format!("{cert}: no certificates found"));
+    }
+
+    let private_key = PrivateKeyDer::from_pem_file(&certificate.key).map_err(|err| format!("{key}: {err}"))?;
+
+    CertifiedKey::from_der(chain, private_key, provider)
+        .map(Arc::new)
+        .map_err(|err| format!("{cert} & {key}: {err}"))
+}
+
+/// SniResolver selects the certificate of the domain a client names
+#[derive(Debug)]
+struct SniResolver {
+    default: Arc<CertifiedKey>,
+    domains: HashMap<String, Arc<CertifiedKey>>,
+}
+
+impl ResolvesServerCert for SniResolver {
+    fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        let certified = client_hello
+            .server_name()
+            .and_then(|name| self.domains.get(&name.to_ascii_lowercase()));
+
+        Some(certified.unwrap_or(&self.default).clone())
+    }
+}
+
+/// TlsStream is a connection accepted by a TLS listener; the handshake is completed by [handshake](TlsStream::handshake) before the connection is sent to a pipeline
+pub(super) struct TlsStream {
+    connection: ServerConnection,
+    stream: Box<dyn Stream>,
+}
+
+impl TlsStream {
+    /// new wraps a stream accepted by a listener; no data is exchanged until the stream is read or written
+    ///
+    /// # Errors
+    /// A rustls::Error is returned if config can't be used to accept connections
+    pub(super) fn new(stream: Box<dyn Stream>, config: Arc<ServerConfig>) -> Result<Self, rustls::Error> {
+        Ok(Self {
+            connection: ServerConnection::new(config)?,
+            stream,
+        })
+    }
+
+    /// handshake completes the handshake; failing if it takes longer than the server allows
+    ///
+    /// # Errors
+    /// See [handshake_within](TlsStream::handshake_within)
+    pub(super) fn handshake(&mut self) -> io::Result<()> {
+        self.handshake_within(HANDSHAKE_TIMEOUT)
+    }
+
+    /// handshake_within completes the handshake; failing once timeout has passed since it started
+    ///
+    /// Each read waits only for the time left; so a client sending its handshake a byte at a time can't hold the thread past timeout.
+    ///
+    /// # Errors
+    /// TimedOut if the handshake isn't complete within timeout, or the error of the read, write or handshake message that failed
+    pub(super) fn handshake_within(&mut self, timeout: Duration) -> io::Result<()> {
+        let deadline = Instant::now() + timeout;
+
+        while self.connection.is_handshaking() {
+            self.write_pending()?;
+
+            if !self.connection.is_handshaking() {
+                break;
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "handshake wasn't completed in time"));
+            }
+
+            self.stream.set_read_timeout(Some(remaining))?;
+
+            if self.connection.read_tls(&mut self.stream)? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+
+            if let Err(err) = self.connection.process_new_packets() {
+                // the alert describing the failure is sent before the connection is dropped
+                let _ = self.write_pending();
+
+                return Err(io::Error::new(io::ErrorKind::InvalidData, err));
+            }
+        }
+
+        self.write_pending()?;
+
+        self.stream.set_read_timeout(None)
+    }
+
+    /// write_pending writes every TLS record waiting to be sent
+    fn write_pending(&mut self) -> io::Result<()> {
+        while self.connection.wants_write() {
+            self.connection.write_tls(&mut self.stream)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Debug for TlsStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsStream")
+            .field("server_name", &self.connection.server_name())
+            .field("handshaking", &self.connection.is_handshaking())
+            .field("stream", &self.stream)
+            .finish()
+    }
+}
+
+impl Read for TlsStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        rustls::Stream::new(&mut self.connection, &mut self.stream).read(buf)
+    }
+}
+
+impl Write for TlsStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        rustls::Stream::new(&mut self.connection, &mut self.stream).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        rustls::Stream::new(&mut self.connection, &mut self.stream).flush()
+    }
+}
+
+impl Stream for TlsStream {
+    fn peer_addr(&self) -> Option<SocketAddr> {
+        self.stream.peer_addr()
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.stream.set_read_timeout(timeout)
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.stream.set_nonblocking(nonblocking)
+    }
+
+    fn peek_len(&mut self) -> io::Result<usize> {
+        loop {
+            // records already received may hold plaintext even if the socket has nothing waiting
+            let state = self
+                .connection
+                .process_new_packets()
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+            if state.plaintext_bytes_to_read() > 0 {
+                return Ok(state.plaintext_bytes_to_read());
+            }
+
+            if state.peer_has_closed() || self.connection.read_tls(&mut self.stream)? == 0 {
+                return Ok(0);
+            }
+        }
+    }
+}
+
+impl Drop for TlsStream {
+    fn drop(&mut self) {
+        // close_notify lets the client tell a complete response from a truncated one
+        if self.connection.is_handshaking() {
+            return;
+        }
+
+        self.connection.send_close_notify();
+
+        while self.connection.wants_write() {
+            match self.connection.write_tls(&mut self.stream) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {}
+            }
+        }
+    }
+}
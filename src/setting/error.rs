@@ -0,0 +1,66 @@
+use std::{error::Error, fmt::Display, io, path::PathBuf};
+
+use super::Format;
+
+/// SettingsError enum defines the reasons settings could not be loaded
+#[derive(Debug)]
+pub enum SettingsError {
+    /// the settings file could not be read or written
+    Io { path: PathBuf, source: io::Error },
+    /// the settings file's extension isn't a supported format
+    Format { path: PathBuf },
+    /// the settings file could not be parsed; line & column are 1 based, or 0 if the format doesn't report where parsing failed
+    Parse {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// an environment variable could not be parsed into the setting it overrides
+    Env { variable: String, value: String },
+    /// the settings were loaded but describe an unusable server
+    Invalid(String),
+    /// the settings could not be written out in format
+    Serialize { format: Format, message: String },
+}
+
+impl Display for SettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettingsError::Io { path, source } => {
+                write!(f, "Failed to access settings at {}: {source}", path.display())
+            }
+            SettingsError::Format { path } => write!(
+                f,
+                "Unsupported settings format {}; expected .ron, .toml, .json, .yaml or .yml",
+                path.display()
+            ),
+            SettingsError::Parse {
+                path,
+                line,
+                column,
+                message,
+            } => write!(f, "Failed to parse settings at {}:{line}:{column}: {message}", path.display()),
+            SettingsError::Env { variable, value } => {
+                write!(f, "Failed to parse environment variable {variable}={value:?}")
+            }
+            SettingsError::Invalid(reason) => write!(f, "Invalid settings: {reason}"),
+            SettingsError::Serialize { format, message } => {
+                write!(f, "Failed to write settings as {format:?}: {message}")
+            }
+        }
+    }
+}
+
+impl Error for SettingsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SettingsError::Io { source, .. } => Some(source),
+            SettingsError::Format { .. }
+            | SettingsError::Parse { .. }
+            | SettingsError::Env { .. }
+            | SettingsError::Invalid(_)
+            | SettingsError::Serialize { .. } => None,
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::path::Path;
+
+use ron::{extensions::Extensions, ser::PrettyConfig, Options};
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::SettingsError;
+
+/// Format enum defines the file formats settings can be written in; every format shares the same schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// `.ron`; optional settings can be written without wrapping them in Some
+    Ron,
+    /// `.toml`
+    Toml,
+    /// `.json`
+    Json,
+    /// `.yaml` or `.yml`
+    Yaml,
+}
+
+impl Format {
+    /// from_path detects the format of the file at path from its extension
+    ///
+    /// # Errors
+    /// A SettingsError::Format is returned if the extension is missing or not one of the supported formats
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Format, SettingsError> {
+        let path = path.as_ref();
+
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+
+        match extension.as_deref() {
+            Some("ron") => Ok(Format::Ron),
+            Some("toml") => Ok(Format::Toml),
+            Some("json") => Ok(Format::Json),
+            Some("yaml") | Some("yml") => Ok(Format::Yaml),
+            _ => Err(SettingsError::Format {
+                path: path.to_path_buf(),
+            }),
+        }
+    }
+
+    /// parse deserializes contents, read from path, in this format
+    ///
+    /// # Errors
+    /// A SettingsError::Parse with the line & column contents fail to parse at is returned
+    pub(super) fn parse<T: DeserializeOwned>(self, path: &Path, contents: &str) -> Result<T, SettingsError> {
+        let parse_error = |(line, column): (usize, usize), message: String| SettingsError::Parse {
+            path: path.to_path_buf(),
+            line,
+            column,
+            message,
+        };
+
+        match self {
+            Format::Ron => Options::default()
+                .with_default_extension(Extensions::IMPLICIT_SOME)
+                .from_str(contents)
+                .map_err(|err| parse_error((err.position.line, err.position.col), err.code.to_string())),
+            Format::Toml => toml::from_str(contents).map_err(|err| {
+                let position = err.span().map_or((0, 0), |span| position(contents, span.start));
+
+                parse_error(position, err.message().to_string())
+            }),
+            Format::Json => {
+                serde_json::from_str(contents).map_err(|err| parse_error((err.line(), err.column()), err.to_string()))
+            }
+            Format::Yaml => serde_yaml::from_str(contents).map_err(|err| {
+                let position = err
+                    .location()
+                    .map_or((0, 0), |location| (location.line(), location.column()));
+
+                parse_error(position, err.to_string())
+            }),
+        }
+    }
+
+    /// serialize writes value out in this format
+    ///
+    /// # Errors
+    /// A SettingsError::Serialize is returned if value can't be represented in this format
+    pub(super) fn serialize<T: Serialize>(self, value: &T) -> Result<String, SettingsError> {
+        let serialized = match self {
+            Format::Ron => ron::ser::to_string_pretty(value, PrettyConfig::default()).map_err(|err| err.to_string()),
+            Format::Toml => toml::to_string_pretty(value).map_err(|err| err.to_string()),
+            Format::Json => serde_json::to_string_pretty(value).map_err(|err| err.to_string()),
+            Format::Yaml => serde_yaml::to_string(value).map_err(|err| err.to_string()),
+        };
+
+        serialized.map_err(|message| SettingsError::Serialize { format: self, message })
+    }
+}
+
+/// position converts a byte offset into contents into a 1 based line & column
+fn position(contents: &str, offset: usize) -> (usize, usize) {
+    let before = &contents[..offset.min(contents.len())];
+
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |line| line.chars().count()) + 1;
+
+    (line, column)
+}
@@ -0,0 +1,199 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::{de::DeserializeOwned, Deserialize};
+
+use super::{DomainPath, Format, ListenerSetting, ServerSetting, SettingsError};
+
+/// prefix of the environment variables that override settings; e.g. `PIPELINED_PORT`
+pub const ENV_PREFIX: &str = "PIPELINED_";
+
+/// SettingsLayer is a partial set of settings; fields that are unset fall through to the layer below
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct SettingsLayer {
+    pub address: Option<String>,
+    pub port: Option<u16>,
+    pub paths: Option<HashMap<String, DomainPath>>,
+    pub pipelines: Option<usize>,
+    pub listeners: Option<Vec<ListenerSetting>>,
+}
+
+impl SettingsLayer {
+    /// from_file parses the file at path, in the [Format] of its extension; any field may be left out
+    ///
+    /// # Errors
+    /// A SettingsError is returned if the format isn't supported, the file can't be read, or the line & column the file fails to parse at
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<SettingsLayer, SettingsError> {
+        let path = path.as_ref();
+        let format = Format::from_path(path)?;
+
+        let contents = fs::read_to_string(path).map_err(|source| SettingsError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        format.parse(path, &contents)
+    }
+
+    /// from_env reads the `PIPELINED_*` environment variables
+    ///
+    /// # Errors
+    /// A SettingsError naming the variable is returned if a value can't be parsed
+    pub fn from_env() -> Result<SettingsLayer, SettingsError> {
+        SettingsLayer::from_vars(env::vars())
+    }
+
+    /// from_vars reads `PIPELINED_ADDRESS`, `PIPELINED_PORT`, `PIPELINED_PIPELINES`, `PIPELINED_PATHS` & `PIPELINED_LISTENERS` from vars; other variables are ignored
+    ///
+    /// `PIPELINED_PATHS` is a ron map of domains; e.g. `{"localhost": (path: "", allow: ["html"])}`. `PIPELINED_LISTENERS` is a ron list of listeners; e.g. `[(address: "::", port: 8080)]`.
+    ///
+    /// # Errors
+    /// A SettingsError naming the variable is returned if a value can't be parsed
+    pub fn from_vars<I: IntoIterator<Item = (String, String)>>(vars: I) -> Result<SettingsLayer, SettingsError> {
+        let mut layer = SettingsLayer::default();
+
+        for (variable, value) in vars {
+            let key = match variable.strip_prefix(ENV_PREFIX) {
+                Some(key) => key,
+                None => continue,
+            };
+
+            match key {
+                "ADDRESS" => layer.address = Some(value),
+                "PORT" => layer.port = Some(parse_var(&variable, &value)?),
+                "PIPELINES" => layer.pipelines = Some(parse_var(&variable, &value)?),
+                "PATHS" => layer.paths = Some(parse_ron_var(&variable, &value)?),
+                "LISTENERS" => layer.listeners = Some(parse_ron_var(&variable, &value)?),
+                _ => {}
+            }
+        }
+
+        Ok(layer)
+    }
+
+    /// apply overwrites the fields of setting that are set in the layer
+    pub fn apply(self, setting: &mut ServerSetting) {
+        if let Some(address) = self.address {
+            setting.address = address;
+        }
+
+        if let Some(port) = self.port {
+            setting.port = port;
+        }
+
+        if let Some(paths) = self.paths {
+            setting.paths = paths;
+        }
+
+        if let Some(pipelines) = self.pipelines {
+            setting.pipelines = Some(pipelines);
+        }
+
+        if let Some(listeners) = self.listeners {
+            setting.listeners = listeners;
+        }
+    }
+}
+
+fn parse_var<T: FromStr>(variable: &str, value: &str) -> Result<T, SettingsError> {
+    value.trim().parse().map_err(|_| SettingsError::Env {
+        variable: variable.to_string(),
+        value: value.to_string(),
+    })
+}
+
+fn parse_ron_var<T: DeserializeOwned>(variable: &str, value: &str) -> Result<T, SettingsError> {
+    ron::from_str(value).map_err(|_| SettingsError::Env {
+        variable: variable.to_string(),
+        value: value.to_string(),
+    })
+}
+
+/// SettingsLoader builds settings from layered sources, each overriding the last: defaults, a settings file, `PIPELINED_*` environment variables, then explicit overrides
+///
+/// # Example
+/// ```ignore
+/// let settings = SettingsLoader::default()
+///     .set_file("config/settings.ron")
+///     .set_overrides(SettingsLayer {
+///         port: Some(9000),
+///         ..SettingsLayer::default()
+///     })
+///     .load()?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingsLoader {
+    pub file: Option<PathBuf>,
+    pub env: bool,
+    pub overrides: SettingsLayer,
+}
+
+impl Default for SettingsLoader {
+    fn default() -> Self {
+        Self {
+            file: None,
+            env: true,
+            overrides: SettingsLayer::default(),
+        }
+    }
+}
+
+impl SettingsLoader {
+    /// set_file sets the file layered over the defaults
+    pub fn set_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.file = Some(path.into());
+
+        self
+    }
+
+    /// set_env sets if `PIPELINED_*` environment variables are layered over the file
+    pub fn set_env(mut self, env: bool) -> Self {
+        self.env = env;
+
+        self
+    }
+
+    /// set_overrides sets the settings layered over every other source
+    pub fn set_overrides(mut self, overrides: SettingsLayer) -> Self {
+        self.overrides = overrides;
+
+        self
+    }
+
+    /// load merges every source, then validates the result
+    ///
+    /// # Errors
+    /// A SettingsError is returned if a source can't be read or parsed, or the merged settings are invalid
+    pub fn load(&self) -> Result<ServerSetting, SettingsError> {
+        let setting = self.merge()?;
+
+        setting.validate()?;
+
+        Ok(setting)
+    }
+
+    /// merge layers every source without validating the result
+    ///
+    /// # Errors
+    /// A SettingsError is returned if a source can't be read or parsed
+    pub fn merge(&self) -> Result<ServerSetting, SettingsError> {
+        let mut setting = ServerSetting::default();
+
+        if let Some(file) = &self.file {
+            SettingsLayer::from_file(file)?.apply(&mut setting);
+        }
+
+        if self.env {
+            SettingsLayer::from_env()?.apply(&mut setting);
+        }
+
+        self.overrides.clone().apply(&mut setting);
+
+        Ok(setting)
+    }
+}
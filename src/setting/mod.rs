@@ -0,0 +1,371 @@
+//! Setting module handles the reading and storing of server settings
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize, Serializer};
+
+pub use self::{
+    error::SettingsError,
+    format::Format,
+    layer::{SettingsLayer, SettingsLoader, ENV_PREFIX},
+};
+
+mod error;
+mod format;
+mod layer;
+
+#[cfg(test)]
+mod tests;
+
+/// folder every domain's path is relative to
+pub const SOURCE_FOLDER: &str = "source";
+
+/// default file settings are loaded from
+pub const SETTINGS_FILE: &str = "settings.ron";
+
+/// ServerSetting is a struct that stores key information required for server start up, HTTP method handling and file retrieval
+///
+/// The same schema is read from every [Format]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct ServerSetting {
+    /// address of the listener used when listeners is empty
+    pub address: String,
+    /// port of the listener used when listeners is empty
+    pub port: u16,
+    #[serde(serialize_with = "sorted")]
+    pub paths: HashMap<String, DomainPath>,
+    /// number of pipelines the server starts with; one per available core if unset
+    #[serde(default)]
+    pub pipelines: Option<usize>,
+    /// sockets the server accepts connections on; a single listener on address & port if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub listeners: Vec<ListenerSetting>,
+}
+
+/// DomainPath defines the path of domain in the source directory; and the extensions that can be received through GET or HEAD requests.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct DomainPath {
+    pub path: String,
+    pub allow: Vec<String>,
+    /// certificate presented to TLS clients that name the domain in their handshake (SNI); read when the listeners are bound
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<Certificate>,
+}
+
+/// Certificate locates a PEM encoded certificate chain & the private key it was issued for
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct Certificate {
+    /// path of the certificate chain; starting with the certificate of the server
+    pub cert: PathBuf,
+    /// path of the private key
+    pub key: PathBuf,
+}
+
+/// ListenerSetting defines a socket the server accepts connections on, and the options applied to the requests received through it
+///
+/// A listener binds address & port, unless path is set; in which case it binds a unix domain socket at path.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct ListenerSetting {
+    /// ip address or host name to bind; `::` accepts both IPv4 & IPv6 connections unless ipv6_only is set
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub address: String,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub port: u16,
+    /// only accept IPv6 connections on an IPv6 address
+    #[serde(default)]
+    pub ipv6_only: bool,
+    /// hosts requests received through the listener may be addressed to; any host if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_hosts: Vec<String>,
+    /// host requests without a Host header are addressed to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_host: Option<String>,
+    /// path of the unix domain socket to bind instead of address & port; only supported on unix
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    /// permissions of the unix domain socket's file; e.g. 0o660. The process' umask applies if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+    /// terminates TLS on the listener; presenting this certificate to clients that don't name a domain with its own certificate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<Certificate>,
+}
+
+impl ListenerSetting {
+    /// new creates a listener on address & port without any options
+    pub fn new<A: Into<String>>(address: A, port: u16) -> Self {
+        Self {
+            address: address.into(),
+            port,
+            ipv6_only: false,
+            allowed_hosts: Vec::new(),
+            default_host: None,
+            path: None,
+            mode: None,
+            tls: None,
+        }
+    }
+
+    /// unix creates a listener on the unix domain socket at path without any options
+    pub fn unix<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: Some(path.into()),
+            ..ListenerSetting::new(String::new(), 0)
+        }
+    }
+
+    /// name describes the socket the listener binds; e.g. for logs & errors
+    pub fn name(&self) -> String {
+        match &self.path {
+            Some(path) => path.display().to_string(),
+            None => format!("{}:{}", self.address, self.port),
+        }
+    }
+
+    /// allows_host checks if requests addressed to host can be received through the listener; ignoring case & any port
+    pub fn allows_host(&self, host: &str) -> bool {
+        if self.allowed_hosts.is_empty() {
+            return true;
+        }
+
+        let host = strip_port(host);
+
+        self.allowed_hosts
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(host))
+    }
+}
+
+impl Certificate {
+    /// validate checks that the certificate chain & key of owner exist
+    fn validate(&self, owner: &str) -> Result<(), SettingsError> {
+        for (name, path) in [("certificate", &self.cert), ("key", &self.key)] {
+            if !path.is_file() {
+                return Err(SettingsError::Invalid(format!(
+                    "{name} {} of {owner} does not exist",
+                    path.display()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn is_zero(port: &u16) -> bool {
+    *port == 0
+}
+
+/// strip_port removes the port from a Host header value; e.g. `localhost:8080` & `[::1]:8080`
+fn strip_port(host: &str) -> &str {
+    let (name, port) = match host.rsplit_once(':') {
+        Some(split) => split,
+        None => return host,
+    };
+
+    // the colons of a bare IPv6 address aren't a port separator
+    let bare_ipv6 = name.contains(':') && !name.ends_with(']');
+
+    match !name.is_empty() && !bare_ipv6 && port.bytes().all(|byte| byte.is_ascii_digit()) {
+        true => name,
+        false => host,
+    }
+}
+
+impl Default for ServerSetting {
+    fn default() -> Self {
+        Self {
+            address: String::from("localhost"),
+            port: 8080,
+            paths: HashMap::new(),
+            pipelines: None,
+            listeners: Vec::new(),
+        }
+    }
+}
+
+impl ServerSetting {
+    /// load reads settings from settings.ron in the working directory; see [load_from](ServerSetting::load_from)
+    ///
+    /// # Errors
+    /// A SettingsError is returned if the settings can't be read or parsed, or are invalid
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let server_setting: ServerSetting = ServerSetting::load()?;
+    /// ```
+    pub fn load() -> Result<ServerSetting, SettingsError> {
+        ServerSetting::load_from(SETTINGS_FILE)
+    }
+
+    /// load_from reads settings from the file at path, in the [Format] of its extension, layered over the defaults & under any `PIPELINED_*` environment variables
+    ///
+    /// Use a [SettingsLoader] to pick the layers or add explicit overrides.
+    ///
+    /// # Errors
+    /// A SettingsError is returned if the settings can't be read or parsed, or are invalid
+    pub fn load_from<P: AsRef<Path>>(path: P) -> Result<ServerSetting, SettingsError> {
+        SettingsLoader::default().set_file(path.as_ref()).load()
+    }
+
+    /// domain returns the path of the domain requests addressed to host are served from; ignoring any port, unless a domain is set for host & port
+    pub fn domain(&self, host: &str) -> Option<&DomainPath> {
+        self.paths
+            .get(host)
+            .or_else(|| self.paths.get(strip_port(host)))
+    }
+
+    /// effective_listeners returns the listeners the server binds; the listener on address & port if none are set
+    pub fn effective_listeners(&self) -> Vec<ListenerSetting> {
+        match self.listeners.is_empty() {
+            true => vec![ListenerSetting::new(self.address.clone(), self.port)],
+            false => self.listeners.clone(),
+        }
+    }
+
+    /// dump writes the settings out in format; e.g. to inspect the result of layering every source
+    ///
+    /// # Errors
+    /// A SettingsError::Serialize is returned if the settings can't be represented in format
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let merged = SettingsLoader::default().set_file("settings.toml").merge()?;
+    /// println!("{}", merged.dump(Format::Toml)?);
+    /// ```
+    pub fn dump(&self, format: Format) -> Result<String, SettingsError> {
+        format.serialize(self)
+    }
+
+    /// dump_to writes the settings to the file at path, in the [Format] of its extension
+    ///
+    /// # Errors
+    /// A SettingsError is returned if the format isn't supported, the settings can't be represented in it, or the file can't be written
+    pub fn dump_to<P: AsRef<Path>>(&self, path: P) -> Result<(), SettingsError> {
+        let path = path.as_ref();
+        let contents = self.dump(Format::from_path(path)?)?;
+
+        fs::write(path, contents).map_err(|source| SettingsError::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// validate checks that the settings describe a server that can be run; with domain folders relative to the source folder
+    ///
+    /// # Errors
+    /// A SettingsError::Invalid describing the first problem found is returned
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        self.validate_in(SOURCE_FOLDER)
+    }
+
+    /// validate_in checks that the settings describe a server that can be run; with domain folders relative to source
+    ///
+    /// # Errors
+    /// A SettingsError::Invalid describing the first problem found is returned
+    pub fn validate_in<P: AsRef<Path>>(&self, source: P) -> Result<(), SettingsError> {
+        // address & port are only bound when no listeners are set
+        if self.listeners.is_empty() && self.address.trim().is_empty() {
+            return Err(SettingsError::Invalid(String::from("address must not be empty")));
+        }
+
+        if self.listeners.is_empty() && self.port == 0 {
+            return Err(SettingsError::Invalid(String::from("port must not be 0")));
+        }
+
+        if self.pipelines == Some(0) {
+            return Err(SettingsError::Invalid(String::from("pipelines must not be 0")));
+        }
+
+        let mut bound: Vec<String> = Vec::new();
+
+        for listener in &self.listeners {
+            let name = listener.name();
+
+            match &listener.path {
+                Some(_) if !cfg!(unix) => {
+                    return Err(SettingsError::Invalid(format!(
+                        "listener {name} is a unix socket; which are only supported on unix"
+                    )));
+                }
+                Some(path) if path.as_os_str().is_empty() => {
+                    return Err(SettingsError::Invalid(String::from("listener path must not be empty")));
+                }
+                Some(_) => {}
+                None if listener.address.trim().is_empty() => {
+                    return Err(SettingsError::Invalid(String::from("listener address must not be empty")));
+                }
+                None if listener.port == 0 => {
+                    return Err(SettingsError::Invalid(format!("listener {name} port must not be 0")));
+                }
+                None => {}
+            }
+
+            match listener.mode {
+                Some(_) if listener.path.is_none() => {
+                    return Err(SettingsError::Invalid(format!(
+                        "listener {name} sets a mode without a path"
+                    )));
+                }
+                Some(mode) if mode > 0o777 => {
+                    return Err(SettingsError::Invalid(format!(
+                        "listener {name} mode {mode:o} is not a permission"
+                    )));
+                }
+                _ => {}
+            }
+
+            if let Some(certificate) = &listener.tls {
+                certificate.validate(&format!("listener {name}"))?;
+            }
+
+            if bound.contains(&name) {
+                return Err(SettingsError::Invalid(format!("listener {name} is set more than once")));
+            }
+
+            if let Some(host) = &listener.default_host {
+                if !listener.allows_host(host) {
+                    return Err(SettingsError::Invalid(format!(
+                        "listener {name} default host {host} is not an allowed host"
+                    )));
+                }
+            }
+
+            bound.push(name);
+        }
+
+        // sorted so the same problem is always reported first
+        let mut domains: Vec<(&String, &DomainPath)> = self.paths.iter().collect();
+        domains.sort_by_key(|(domain, _)| *domain);
+
+        for (domain, domain_path) in domains {
+            let folder = source.as_ref().join(&domain_path.path);
+
+            if !folder.is_dir() {
+                return Err(SettingsError::Invalid(format!(
+                    "folder {} of domain {domain} does not exist",
+                    folder.display()
+                )));
+            }
+
+            if domain_path.allow.is_empty() {
+                return Err(SettingsError::Invalid(format!(
+                    "domain {domain} does not allow any extensions"
+                )));
+            }
+
+            if let Some(certificate) = &domain_path.tls {
+                certificate.validate(&format!("domain {domain}"))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// sorted serializes paths ordered by domain; so dumps of the same settings are identical
+fn sorted<S: Serializer>(paths: &HashMap<String, DomainPath>, serializer: S) -> Result<S::Ok, S::Error> {
+    paths.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
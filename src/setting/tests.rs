@@ -0,0 +1,545 @@
+use std::{fs, path::PathBuf};
+
+use super::{Format, ServerSetting, SettingsError, SettingsLayer, SettingsLoader};
+
+/// settings_file writes contents to a file unique to the test; name includes the extension
+fn settings_file(name: &str, contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("pipelined_setting_{}_{name}", std::process::id()));
+
+    fs::write(&path, contents).unwrap();
+
+    path
+}
+
+fn vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+    vars.iter().map(|(variable, value)| (variable.to_string(), value.to_string())).collect()
+}
+
+mod file {
+    use super::*;
+
+    #[test]
+    fn fields_can_be_left_out() {
+        let path = settings_file("partial.ron", "(port: 9000, pipelines: 2)");
+
+        let layer = SettingsLayer::from_file(&path).unwrap();
+
+        assert_eq!(layer.address, None);
+        assert_eq!(layer.port, Some(9000));
+        assert_eq!(layer.paths, None);
+        assert_eq!(layer.pipelines, Some(2));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn parse_error_has_position() {
+        let path = settings_file("position.ron", "(\n    address: \"localhost\",\n    port: \"8080\",\n)");
+
+        match SettingsLayer::from_file(&path) {
+            Err(SettingsError::Parse { path: at, line, column, .. }) => {
+                assert_eq!(at, path);
+                assert_eq!(line, 3);
+                assert!(column > 1);
+            }
+            other => panic!("expected a parse error, got {other:?}"),
+        }
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn missing_file_is_io_error() {
+        let path = settings_file("missing.ron", "");
+        fs::remove_file(&path).unwrap();
+
+        match ServerSetting::load_from(&path) {
+            Err(SettingsError::Io { path: at, .. }) => assert_eq!(at, path),
+            other => panic!("expected an io error, got {other:?}"),
+        }
+    }
+}
+
+mod format {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    use crate::setting::DomainPath;
+
+    const FORMATS: [(&str, Format); 5] = [
+        ("ron", Format::Ron),
+        ("toml", Format::Toml),
+        ("json", Format::Json),
+        ("yaml", Format::Yaml),
+        ("yml", Format::Yaml),
+    ];
+
+    fn setting() -> ServerSetting {
+        ServerSetting {
+            address: String::from("0.0.0.0"),
+            port: 9000,
+            paths: HashMap::from([
+                (
+                    String::from("localhost"),
+                    DomainPath {
+                        path: String::from(""),
+                        allow: vec![String::from("html"), String::from("css")],
+                        tls: None,
+                    },
+                ),
+                (
+                    String::from("example.com"),
+                    DomainPath {
+                        path: String::from("example"),
+                        allow: vec![String::from("png")],
+                        tls: None,
+                    },
+                ),
+            ]),
+            pipelines: Some(2),
+            listeners: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn detected_by_extension() {
+        for (extension, format) in FORMATS {
+            assert_eq!(Format::from_path(format!("settings.{extension}")).unwrap(), format);
+        }
+
+        assert_eq!(Format::from_path("SETTINGS.TOML").unwrap(), Format::Toml);
+
+        for path in ["settings.ini", "settings"] {
+            assert!(matches!(Format::from_path(path), Err(SettingsError::Format { .. })));
+        }
+    }
+
+    #[test]
+    fn same_schema_in_every_format() {
+        let files = [
+            (
+                "schema.toml",
+                "address = \"0.0.0.0\"\nport = 9000\npipelines = 2\n\n[paths.localhost]\npath = \"\"\nallow = [\"html\", \"css\"]\n\n[paths.\"example.com\"]\npath = \"example\"\nallow = [\"png\"]\n",
+            ),
+            (
+                "schema.json",
+                r#"{"address": "0.0.0.0", "port": 9000, "pipelines": 2, "paths": {"localhost": {"path": "", "allow": ["html", "css"]}, "example.com": {"path": "example", "allow": ["png"]}}}"#,
+            ),
+            (
+                "schema.yaml",
+                "address: 0.0.0.0\nport: 9000\npipelines: 2\npaths:\n  localhost:\n    path: ''\n    allow: [html, css]\n  example.com:\n    path: example\n    allow: [png]\n",
+            ),
+        ];
+
+        for (name, contents) in files {
+            let path = settings_file(name, contents);
+
+            assert_eq!(SettingsLoader::default().set_file(&path).set_env(false).merge().unwrap(), setting(), "{name}");
+
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn parse_error_has_position() {
+        let files = [
+            ("position.toml", "address = \"localhost\"\nport = \"8080\"\n", 2),
+            ("position.json", "{\n  \"address\": \"localhost\",\n  \"port\": \"8080\"\n}", 3),
+            ("position.yaml", "address: localhost\nport: 8080\npipelines: many\n", 3),
+        ];
+
+        for (name, contents, expected) in files {
+            let path = settings_file(name, contents);
+
+            match SettingsLayer::from_file(&path) {
+                Err(SettingsError::Parse { line, column, .. }) => {
+                    assert_eq!(line, expected, "{name}");
+                    assert!(column > 1, "{name}");
+                }
+                other => panic!("expected a parse error in {name}, got {other:?}"),
+            }
+
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn dump_round_trips() {
+        for (extension, format) in FORMATS {
+            let path = settings_file(&format!("dump.{extension}"), "");
+
+            setting().dump_to(&path).unwrap();
+
+            assert_eq!(fs::read_to_string(&path).unwrap(), setting().dump(format).unwrap());
+            assert_eq!(SettingsLoader::default().set_file(&path).set_env(false).merge().unwrap(), setting(), "{extension}");
+
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn dump_is_stable() {
+        let dumped = setting().dump(Format::Json).unwrap();
+
+        assert!(dumped.find("example.com").unwrap() < dumped.find("localhost").unwrap());
+        assert_eq!(dumped, setting().dump(Format::Json).unwrap());
+    }
+}
+
+mod env {
+    use super::*;
+
+    #[test]
+    fn reads_prefixed_variables() {
+        let layer = SettingsLayer::from_vars(vars(&[
+            ("PIPELINED_ADDRESS", "0.0.0.0"),
+            ("PIPELINED_PORT", "9000"),
+            ("PIPELINED_PIPELINES", "3"),
+            ("PIPELINED_PATHS", r#"{"localhost": (path: "", allow: ["html"])}"#),
+            ("PORT", "1"),
+            ("PIPELINED_UNKNOWN", "1"),
+        ]))
+        .unwrap();
+
+        assert_eq!(layer.address.as_deref(), Some("0.0.0.0"));
+        assert_eq!(layer.port, Some(9000));
+        assert_eq!(layer.pipelines, Some(3));
+        assert_eq!(layer.paths.unwrap()["localhost"].allow, vec![String::from("html")]);
+    }
+
+    #[test]
+    fn bad_value_names_variable() {
+        for (variable, value) in [("PIPELINED_PORT", "70000"), ("PIPELINED_PATHS", "{")] {
+            match SettingsLayer::from_vars(vars(&[(variable, value)])) {
+                Err(SettingsError::Env { variable: at, value: was }) => {
+                    assert_eq!(at, variable);
+                    assert_eq!(was, value);
+                }
+                other => panic!("expected an env error, got {other:?}"),
+            }
+        }
+    }
+}
+
+mod layering {
+    use super::*;
+
+    #[test]
+    fn later_layers_take_precedence() {
+        let path = settings_file("layers.ron", "(address: \"0.0.0.0\", port: 9000, pipelines: 2)");
+
+        let mut setting = SettingsLoader::default().set_file(&path).set_env(false).merge().unwrap();
+        SettingsLayer::from_vars(vars(&[("PIPELINED_PORT", "9001")])).unwrap().apply(&mut setting);
+        SettingsLayer {
+            pipelines: Some(4),
+            ..SettingsLayer::default()
+        }
+        .apply(&mut setting);
+
+        assert_eq!(setting.address, "0.0.0.0");
+        assert_eq!(setting.port, 9001);
+        assert_eq!(setting.pipelines, Some(4));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn overrides_apply_over_file() {
+        let path = settings_file("overrides.ron", "(port: 9000)");
+
+        let setting = SettingsLoader::default()
+            .set_file(&path)
+            .set_env(false)
+            .set_overrides(SettingsLayer {
+                port: Some(9002),
+                ..SettingsLayer::default()
+            })
+            .load()
+            .unwrap();
+
+        assert_eq!(
+            setting,
+            ServerSetting {
+                port: 9002,
+                ..ServerSetting::default()
+            }
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+}
+
+mod listeners {
+    use super::*;
+
+    use crate::setting::{DomainPath, ListenerSetting};
+
+    #[test]
+    fn address_and_port_are_default_listener() {
+        let setting = ServerSetting::default();
+
+        assert_eq!(setting.effective_listeners(), vec![ListenerSetting::new("localhost", 8080)]);
+
+        let setting = ServerSetting {
+            listeners: vec![ListenerSetting::new("::", 9000), ListenerSetting::new("0.0.0.0", 9001)],
+            ..ServerSetting::default()
+        };
+
+        assert_eq!(setting.effective_listeners(), setting.listeners);
+    }
+
+    #[test]
+    fn allowed_hosts_ignore_case_and_port() {
+        let listener = ListenerSetting {
+            allowed_hosts: vec![String::from("example.com"), String::from("[::1]"), String::from("::1")],
+            ..ListenerSetting::new("::", 9000)
+        };
+
+        for host in ["example.com", "EXAMPLE.com:9000", "example.com:", "[::1]", "[::1]:9000", "::1"] {
+            assert!(listener.allows_host(host), "{host}");
+        }
+
+        for host in ["localhost", "example.com.evil", "[::2]:9000", "example.com:http"] {
+            assert!(!listener.allows_host(host), "{host}");
+        }
+
+        assert!(ListenerSetting::new("::", 9000).allows_host("anything"));
+    }
+
+    #[test]
+    fn domain_ignores_port() {
+        let domain = |path: &str| DomainPath {
+            path: path.to_string(),
+            allow: vec![String::from("html")],
+            tls: None,
+        };
+
+        let setting = ServerSetting {
+            paths: [
+                (String::from("localhost"), domain("any")),
+                (String::from("localhost:9000"), domain("9000")),
+                (String::from("[::1]"), domain("ipv6")),
+            ]
+            .into_iter()
+            .collect(),
+            ..ServerSetting::default()
+        };
+
+        let path = |host: &str| setting.domain(host).map(|domain| domain.path.as_str());
+
+        assert_eq!(path("localhost"), Some("any"));
+        assert_eq!(path("localhost:8080"), Some("any"));
+        assert_eq!(path("localhost:9000"), Some("9000"));
+        assert_eq!(path("[::1]:8080"), Some("ipv6"));
+        assert_eq!(path("example.com:8080"), None);
+    }
+
+    #[test]
+    fn read_from_file_and_env() {
+        let path = settings_file(
+            "listeners.toml",
+            "[[listeners]]\naddress = \"::\"\nport = 9000\nipv6_only = true\n\n[[listeners]]\naddress = \"0.0.0.0\"\nport = 9001\nallowed_hosts = [\"example.com\"]\ndefault_host = \"example.com\"\n",
+        );
+
+        let setting = SettingsLoader::default().set_file(&path).set_env(false).merge().unwrap();
+
+        assert_eq!(setting.listeners.len(), 2);
+        assert!(setting.listeners[0].ipv6_only);
+        assert_eq!(setting.listeners[1].default_host.as_deref(), Some("example.com"));
+
+        // the merged listeners survive a dump in every format
+        for format in [Format::Ron, Format::Toml, Format::Json, Format::Yaml] {
+            let dumped = settings_file(&format!("listeners_dump.{}", format!("{format:?}").to_lowercase()), "");
+            setting.dump_to(&dumped).unwrap();
+
+            assert_eq!(SettingsLoader::default().set_file(&dumped).set_env(false).merge().unwrap(), setting);
+
+            fs::remove_file(dumped).unwrap();
+        }
+
+        let layer = SettingsLayer::from_vars(vars(&[("PIPELINED_LISTENERS", r#"[(address: "::", port: 9002)]"#)])).unwrap();
+        assert_eq!(layer.listeners, Some(vec![ListenerSetting::new("::", 9002)]));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn invalid_listeners_are_rejected() {
+        let source = std::env::temp_dir();
+
+        let invalid = [
+            vec![ListenerSetting::new("", 9000)],
+            vec![ListenerSetting::new("::", 0)],
+            vec![ListenerSetting::new("::", 9000), ListenerSetting::new("::", 9000)],
+            vec![ListenerSetting {
+                allowed_hosts: vec![String::from("example.com")],
+                default_host: Some(String::from("localhost")),
+                ..ListenerSetting::new("::", 9000)
+            }],
+            vec![ListenerSetting::unix(""), ListenerSetting::new("::", 9000)],
+            vec![ListenerSetting {
+                mode: Some(0o660),
+                ..ListenerSetting::new("::", 9000)
+            }],
+            vec![ListenerSetting {
+                mode: Some(0o1777),
+                ..ListenerSetting::unix("/run/pipelined.sock")
+            }],
+            vec![
+                ListenerSetting::unix("/run/pipelined.sock"),
+                ListenerSetting::unix("/run/pipelined.sock"),
+            ],
+        ];
+
+        for listeners in invalid {
+            let setting = ServerSetting {
+                listeners,
+                ..ServerSetting::default()
+            };
+
+            assert!(
+                matches!(setting.validate_in(&source), Err(SettingsError::Invalid(_))),
+                "{setting:?} should be invalid"
+            );
+        }
+
+        let setting = ServerSetting {
+            listeners: vec![
+                ListenerSetting::new("::", 9000),
+                ListenerSetting::new("0.0.0.0", 9000),
+                ListenerSetting {
+                    mode: Some(0o660),
+                    ..ListenerSetting::unix("/run/pipelined.sock")
+                },
+            ],
+            ..ServerSetting::default()
+        };
+
+        assert_eq!(setting.validate_in(&source).is_ok(), cfg!(unix));
+    }
+
+    #[test]
+    fn address_and_port_are_only_required_without_listeners() {
+        let source = std::env::temp_dir();
+
+        let unset = ServerSetting {
+            address: String::new(),
+            port: 0,
+            ..ServerSetting::default()
+        };
+
+        assert!(matches!(unset.validate_in(&source), Err(SettingsError::Invalid(_))));
+
+        let setting = ServerSetting {
+            listeners: vec![ListenerSetting::new("::", 9000)],
+            ..unset
+        };
+
+        assert!(setting.validate_in(&source).is_ok(), "{setting:?} should be valid");
+    }
+}
+
+mod validate {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    use crate::setting::{Certificate, DomainPath, ListenerSetting};
+
+    fn setting(path: &str, allow: &[&str]) -> ServerSetting {
+        ServerSetting {
+            paths: HashMap::from([(
+                String::from("localhost"),
+                DomainPath {
+                    path: String::from(path),
+                    allow: allow.iter().map(|extension| extension.to_string()).collect(),
+                    tls: None,
+                },
+            )]),
+            ..ServerSetting::default()
+        }
+    }
+
+    #[test]
+    fn accepts_existing_folders() {
+        let source = std::env::temp_dir();
+
+        assert!(setting("", &["html"]).validate_in(&source).is_ok());
+    }
+
+    #[test]
+    fn rejects_unusable_settings() {
+        let source = std::env::temp_dir();
+
+        let invalid = [
+            ServerSetting {
+                port: 0,
+                ..setting("", &["html"])
+            },
+            ServerSetting {
+                address: String::from(" "),
+                ..setting("", &["html"])
+            },
+            ServerSetting {
+                pipelines: Some(0),
+                ..setting("", &["html"])
+            },
+            setting("pipelined_setting_missing_folder", &["html"]),
+            setting("", &[]),
+        ];
+
+        for setting in invalid {
+            assert!(
+                matches!(setting.validate_in(&source), Err(SettingsError::Invalid(_))),
+                "{setting:?} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn certificates_must_exist() {
+        let source = std::env::temp_dir();
+
+        let existing = settings_file("certificate.pem", "");
+        let missing = source.join("pipelined_setting_missing_certificate.pem");
+
+        let certificate = |cert: &PathBuf, key: &PathBuf| Certificate {
+            cert: cert.clone(),
+            key: key.clone(),
+        };
+
+        let with_listener = |certificate: Certificate| ServerSetting {
+            listeners: vec![ListenerSetting {
+                tls: Some(certificate),
+                ..ListenerSetting::new("localhost", 8443)
+            }],
+            ..setting("", &["html"])
+        };
+
+        let with_domain = |certificate: Certificate| {
+            let mut setting = setting("", &["html"]);
+            setting.paths.get_mut("localhost").unwrap().tls = Some(certificate);
+
+            setting
+        };
+
+        assert!(with_listener(certificate(&existing, &existing)).validate_in(&source).is_ok());
+        assert!(with_domain(certificate(&existing, &existing)).validate_in(&source).is_ok());
+
+        let invalid = [
+            with_listener(certificate(&missing, &existing)),
+            with_listener(certificate(&existing, &missing)),
+            with_domain(certificate(&missing, &existing)),
+            // a folder isn't a certificate
+            with_domain(certificate(&existing, &source)),
+        ];
+
+        for setting in invalid {
+            assert!(
+                matches!(setting.validate_in(&source), Err(SettingsError::Invalid(_))),
+                "{setting:?} should be invalid"
+            );
+        }
+    }
+}
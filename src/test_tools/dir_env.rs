@@ -0,0 +1,32 @@
+use std::{fs, path::PathBuf};
+
+/// Struct representing a directory environment
+pub struct DirEnv {
+    /// The path of the directory
+    path: PathBuf,
+}
+impl DirEnv {
+    /// Creates the directory at the given path, along with any missing parents
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the directory to create
+    pub fn new(path: &str) -> DirEnv {
+        fs::create_dir_all(path).unwrap();
+
+        DirEnv { path: PathBuf::from(path) }
+    }
+}
+impl Drop for DirEnv {
+    /// Deletes the directory when the DirEnv is dropped; empty parents are deleted as well
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.path).unwrap();
+
+        // parents still in use by other tests aren't empty, so they are left in place
+        for parent in self.path.ancestors().skip(1) {
+            if parent.as_os_str().is_empty() || fs::remove_dir(parent).is_err() {
+                break;
+            }
+        }
+    }
+}
@@ -16,8 +16,8 @@ impl FileEnv {
     /// * `file_name` - The name of the file to create
     /// * `content` - The content to write to the file
     pub fn new(file_name: &str, content: &str) -> FileEnv {
-        let mut file = File::create(&file_name).unwrap();
-        file.write(&content.as_bytes()).unwrap();
+        let mut file = File::create(file_name).unwrap();
+        file.write_all(content.as_bytes()).unwrap();
 
         FileEnv {
             file_name: file_name.to_string(),
@@ -1,2 +1,3 @@
+pub mod dir_env;
 pub mod file_env;
 pub mod server_env;
@@ -1,8 +1,173 @@
-pub struct ServerEnv {}
+//! server_env module runs servers for the duration of a test
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::{mpsc, Arc},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
 
-impl ServerEnv {}
+use crate::{
+    http::{
+        body::{Body, ContentType, Text},
+        request::Request,
+        response::{response_status_code::ResponseStatusCode, Response},
+    },
+    pipeline::{builder::pipeline::Builder, default, Server, Stream},
+    setting::ServerSetting,
+};
 
-impl Drop for ServerEnv {
-    /// Deletes the file when the FileEnv is dropped
-    fn drop(&mut self) {}
+/// host every ServerEnv is started on, unless it is given an address
+pub const ADDRESS: &str = "localhost";
+
+/// Struct representing a server running on its own thread
+///
+/// The server is shut down when the ServerEnv is dropped; so a failed assertion doesn't leave its sockets bound.
+pub struct ServerEnv<U: Clone + Send + Sync + 'static> {
+    server: Arc<Server<U>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl<U: Clone + Send + Sync + 'static> ServerEnv<U> {
+    /// Runs server; returning once it accepts connections on port of [ADDRESS]
+    pub fn new(server: Server<U>, port: u16) -> ServerEnv<U> {
+        ServerEnv::new_on(server, (ADDRESS, port))
+    }
+
+    /// Runs server; returning once it accepts connections on address
+    pub fn new_on<A: ToSocketAddrs>(server: Server<U>, address: A) -> ServerEnv<U> {
+        let env = ServerEnv::spawn(server);
+
+        let start = Instant::now();
+        while TcpStream::connect(&address).is_err() {
+            assert!(start.elapsed() < Duration::from_secs(5), "server failed to start");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        env
+    }
+
+    /// Runs server; without waiting for it to accept connections, e.g. as its sockets are already listening
+    pub fn spawn(server: Server<U>) -> ServerEnv<U> {
+        let server = Arc::new(server);
+
+        let thread = {
+            let server = server.clone();
+
+            thread::spawn(move || server.run().unwrap())
+        };
+
+        ServerEnv {
+            server,
+            thread: Some(thread),
+        }
+    }
+
+    /// The server being run
+    pub fn server(&self) -> &Server<U> {
+        &self.server
+    }
+
+    /// Shuts the server down; panicking if it failed while running
+    pub fn stop(mut self) {
+        self.server.shutdown_handle().shutdown(Duration::from_secs(1));
+
+        if let Some(thread) = self.thread.take() {
+            thread.join().unwrap();
+        }
+    }
+
+    /// Waits for the server to return from run; without requesting a shutdown
+    pub fn join(mut self) {
+        if let Some(thread) = self.thread.take() {
+            thread.join().unwrap();
+        }
+    }
+}
+
+impl<U: Clone + Send + Sync + 'static> Drop for ServerEnv<U> {
+    /// Shuts the server down when the ServerEnv is dropped
+    fn drop(&mut self) {
+        self.server.shutdown_handle().shutdown(Duration::from_secs(1));
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Creates settings for a single pipeline listening on port of [ADDRESS]
+pub fn setting(port: u16) -> ServerSetting {
+    ServerSetting {
+        address: ADDRESS.to_string(),
+        port,
+        paths: HashMap::new(),
+        pipelines: Some(1),
+        listeners: Vec::new(),
+    }
+}
+
+/// Action answering every request that was parsed with `ok`
+pub fn ok(
+    request: &Result<Request, ResponseStatusCode>,
+    _: &ServerSetting,
+    _: &mut mpsc::Sender<()>,
+) -> Result<Response, ResponseStatusCode> {
+    if let Err(err) = request {
+        return Err(*err);
+    }
+
+    Ok(Response {
+        status: ResponseStatusCode::Ok,
+        header: HashMap::new(),
+        body: Some(Body {
+            content_type: ContentType::Text(Text::plain),
+            content: b"ok".to_vec(),
+        }),
+    })
+}
+
+/// Creates a builder with the default parser & no compression; answering with [ok]
+pub fn builder() -> Builder<()> {
+    let (tx, _rx) = mpsc::channel();
+
+    Builder::default()
+        .set_parser(|stream: &mut dyn Stream| default::parser::parser::<64, 1024, 200, 1000>(stream))
+        .set_action(ok)
+        .set_compression(default::no_compression)
+        .set_utility_thread(tx)
+}
+
+/// Creates a server from builder; with a utility thread that does nothing
+pub fn server(setting: ServerSetting, builder: Builder<()>) -> Server<()> {
+    let (tx, _rx) = mpsc::channel();
+
+    Server::new(setting, (tx, thread::spawn(|| {})), builder)
+}
+
+/// Writes raw to stream; returning everything read until the server closes it
+///
+/// # return
+/// None if the stream can't be read, or the response isn't UTF-8
+pub fn exchange<S: Read + Write>(mut stream: S, raw: &str) -> Option<String> {
+    stream.write_all(raw.as_bytes()).unwrap();
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).ok()?;
+
+    String::from_utf8(response).ok()
+}
+
+/// Sends raw on a new connection to port of [ADDRESS]; see [exchange]
+pub fn request(port: u16, raw: &str) -> Option<String> {
+    request_to((ADDRESS, port), raw)
+}
+
+/// Sends raw on a new connection to address; see [exchange]
+pub fn request_to<A: ToSocketAddrs>(address: A, raw: &str) -> Option<String> {
+    let stream = TcpStream::connect(address).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+    exchange(stream, raw)
 }